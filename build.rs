@@ -0,0 +1,49 @@
+use std::collections::HashSet;
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// Turns `src/computer/opcodes.csv` into the `OPCODES` table used by the
+/// CPU, the disassembler and the instruction reference.
+fn main() {
+    let source = "src/computer/opcodes.csv";
+    println!("cargo:rerun-if-changed={}", source);
+
+    let csv = fs::read_to_string(source).expect("could not read the opcode table");
+    let mut entries: Vec<Option<String>> = vec![None; 256];
+    let mut seen = HashSet::new();
+
+    for (n, line) in csv.lines().enumerate().skip(1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [opcode, mnemonic, mode, bytes, cycles] = fields[..] else {
+            panic!("{}:{}: expected 5 fields, found {}", source, n + 1, fields.len());
+        };
+        let opcode = u8::from_str_radix(opcode, 16)
+            .unwrap_or_else(|_| panic!("{}:{}: invalid opcode {:?}", source, n + 1, opcode));
+        let bytes: u8 = bytes.parse()
+            .unwrap_or_else(|_| panic!("{}:{}: invalid length {:?}", source, n + 1, bytes));
+        let cycles: u8 = cycles.parse()
+            .unwrap_or_else(|_| panic!("{}:{}: invalid cycle count {:?}", source, n + 1, cycles));
+        assert!(seen.insert(opcode), "{}:{}: opcode {:02X} is defined twice", source, n + 1, opcode);
+        assert!((1..=3).contains(&bytes), "{}:{}: instructions are 1 to 3 bytes long", source, n + 1);
+
+        entries[opcode as usize] = Some(format!(
+            "    Opcode {{ mnemonic: {:?}, mode: AdressingMode::{}, bytes: {}, cycles: {} }}, // {:02X}\n",
+            mnemonic, mode, bytes, cycles, opcode
+        ));
+    }
+
+    let mut out = String::from("pub static OPCODES: [Opcode; 256] = [\n");
+    for (opcode, entry) in entries.iter().enumerate() {
+        let entry = entry.as_ref().unwrap_or_else(|| panic!("{}: opcode {:02X} is missing", source, opcode));
+        out.push_str(entry);
+    }
+    writeln!(out, "];").unwrap();
+
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("opcodes.rs");
+    fs::write(dest, out).expect("could not write the opcode table");
+}
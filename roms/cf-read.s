@@ -0,0 +1,35 @@
+; Reads sector 1 of the CF card and prints it on the serial card up to the
+; first zero byte, then loops forever.
+;
+; 256 byte ROM mapped at $FF00. Assemble with any 65C02 assembler, for
+; example: vasm6502_oldstyle -Fbin -c02 -dotdir -o cf-read.bin cf-read.s
+
+SERIAL = $FFE0          ; serial card in slot 6
+CF_DATA = $FFD0         ; CF card in slot 5
+CF_LBA0 = $FFD3
+CF_LBA1 = $FFD4
+CF_LBA2 = $FFD5
+CF_LBA3 = $FFD6
+CF_COMMAND = $FFD7      ; status when read
+CF_READ = $20
+CF_DRQ = $08
+
+        .org $FF00
+reset:  lda #1
+        sta CF_LBA0
+        stz CF_LBA1
+        stz CF_LBA2
+        stz CF_LBA3
+        lda #CF_READ
+        sta CF_COMMAND
+ready:  lda CF_COMMAND
+        and #CF_DRQ     ; wait for data
+        beq ready
+print:  lda CF_DATA
+        beq done
+        sta SERIAL
+        bra print
+done:   bra done
+
+        .org $FFFA
+        .word reset, reset, reset
@@ -0,0 +1,21 @@
+; Prints a greeting on the serial card, then loops forever.
+;
+; 256 byte ROM mapped at $FF00. Assemble with any 65C02 assembler, for
+; example: vasm6502_oldstyle -Fbin -c02 -dotdir -o hello-serial.bin hello-serial.s
+
+SERIAL = $FFE0          ; serial card in slot 6
+
+        .org $FF00
+reset:  ldx #0
+print:  lda message,x
+        beq done
+        sta SERIAL
+        inx
+        bra print
+done:   bra done
+
+message:
+        .byte "Hello from the Planck 6502!", 13, 0
+
+        .org $FFFA
+        .word reset, reset, reset
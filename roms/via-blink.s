@@ -0,0 +1,41 @@
+; Blinks bit 0 of port B of the VIA with timer 1, echoing the LED on the
+; serial card as '*' (on) and '.' (off). The timer runs freely with a
+; 50000 cycle period, 20 changes per second at 1 MHz.
+;
+; 256 byte ROM mapped at $FF00. Assemble with any 65C02 assembler, for
+; example: vasm6502_oldstyle -Fbin -c02 -dotdir -o via-blink.bin via-blink.s
+
+SERIAL = $FFE0          ; serial card in slot 6
+VIA_ORB = $FFC0         ; VIA on the IO card in slot 4
+VIA_DDRB = $FFC2
+VIA_T1CL = $FFC4
+VIA_T1CH = $FFC5
+VIA_ACR = $FFCB
+VIA_IFR = $FFCD
+PERIOD = 50000
+
+        .org $FF00
+reset:  lda #$FF
+        sta VIA_DDRB    ; port B as outputs
+        lda #$40
+        sta VIA_ACR     ; timer 1 free running
+        lda #<PERIOD
+        sta VIA_T1CL
+        lda #>PERIOD
+        sta VIA_T1CH    ; starts the timer
+wait:   bit VIA_IFR     ; timer 1 flag in V
+        bvc wait
+        lda VIA_T1CL    ; acknowledges the timer
+        lda VIA_ORB
+        eor #1
+        sta VIA_ORB
+        and #1
+        tax
+        lda glyphs,x
+        sta SERIAL
+        bra wait
+
+glyphs: .byte ".*"
+
+        .org $FFFA
+        .word reset, reset, reset
@@ -1,18 +1,41 @@
 use std::collections::VecDeque;
-use std::time::{SystemTime};
-use std::{error, fs};
+use std::time::{Duration, Instant, SystemTime};
+use std::{error, panic};
 use ratatui::widgets::ScrollbarState;
 use std::thread::{self};
 use std::sync::mpsc::{Sender, Receiver};
 use std::sync::mpsc;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::path::{Path, PathBuf};
 
-use crate::computer::{self, Computer, ComputerMessage, Processor};
+use crate::console::Console;
+use crate::input::InputSource;
+use crate::computer::device::Device;
+use crate::computer::map::MemoryMap;
+use crate::computer::snapshot::{self, Snapshot};
+use crate::computer::{self, Card, CardType, Computer, ComputerMessage, ControllerMessage, InvalidOpcode, Metrics, Processor, Reply, Request, RequestId};
+use crate::error::{self as plu_error, Error};
+use crate::rom::{Rom, RomOptions, ADDRESS_SPACE};
+use crate::search::{self, Search};
+use crate::metrics::MetricsLog;
+use crate::project::Project;
+use crate::ring::RingBuffer;
+use crate::session::Session;
+use crate::symbols::Symbols;
+use crate::usage::UsageReport;
+
+/// Name of the thread running the computer, its panics are reported in the UI.
+pub const COMPUTER_THREAD: &str = "computer";
+/// Name of the threads running project builds.
+pub const BUILD_THREAD: &str = "build";
 
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Tab {
     Main,
     Memory,
+    Disassembly,
     Help,
 }
 
@@ -20,29 +43,44 @@ pub enum Tab {
 #[derive(Debug, Clone, PartialEq)]
 pub enum InputMode {
     Normal,
+    /// Typing a search pattern
     Editing,
+    /// Typing an expression to evaluate
+    Eval,
 }
 
 pub enum Message {
     ButtonPressed(String),
 }
 
+/// Sent back by the thread running a build started by [`App::rebuild`]
+pub enum BuildMessage {
+    Built { rom: Rom, disk: Vec<u8>, symbols: Symbols },
+    Failed(String),
+}
+
 pub struct InputState {
     pub mode: InputMode,
     pub value: String,
     pub cursor_position: u16,
 }
 
+/// How long fast forward stays active after the last key press or repeat
+/// when the terminal cannot report key releases.
+const FAST_FORWARD_HOLD: Duration = Duration::from_millis(600);
+
+/// Session file written by Ctrl+S when plu was not started with `--session`
+pub const DEFAULT_SESSION_FILE: &str = "session.json";
+
 /// Application result type.
 pub type AppResult<T> = std::result::Result<T, Box<dyn error::Error>>;
 
 /// Application.
-
 pub struct App {
     /// Is the application running?
     pub running: bool,
     pub current_tab: Tab,
-    pub output: VecDeque<String>,
+    pub output: Console,
     pub debug: VecDeque<String>,
     pub rx: Receiver<computer::ComputerMessage>,
     pub tx: Sender<computer::ControllerMessage>,
@@ -50,44 +88,125 @@ pub struct App {
     pub memory_scroll: usize,
     pub output_scroll_state: ScrollbarState,
     pub output_scroll: usize,
+    /// First opcode shown in the instruction reference
+    pub reference_scroll: usize,
+    /// Copy of the computer memory, updated with the ranges shown on screen
     pub mem: Vec<u8>,
+    /// Number of memory rows shown on screen, set when rendering
+    pub visible_rows: usize,
+    pub last_request_id: RequestId,
+    pub last_reply_id: RequestId,
     pub processor: Processor,
-    pub cursor_position: usize,
     pub tick_time: SystemTime,
-    pub old_clock: u128,
+    pub old_clock: u64,
     pub speed: f64,
+    /// Live counters shared with the computer thread
+    pub metrics: Arc<Metrics>,
+    /// Serial input typed or read from sources, drained by the computer thread
+    pub serial_rx: Arc<RingBuffer>,
     pub log_level: u8,
+    pub fast_forward: bool,
+    pub fast_forward_until: Instant,
+    /// Error reported by the computer, shown until dismissed
+    pub error: Option<String>,
+    /// Search pattern being typed
+    pub input: InputState,
+    /// Current search, highlighted in the Memory tab
+    pub search: Option<Search>,
+    pub metrics_log: Option<MetricsLog>,
+    /// Project rebuilt with F9, when started with `plu build-and-run`
+    pub project: Option<Project>,
+    pub symbols: Symbols,
+    /// Output of the last failed build, shown in a modal
+    pub build_error: Option<String>,
+    /// First line shown of [`App::build_error`]
+    pub build_error_scroll: u16,
+    /// Answer of the build running in the background, if any
+    pub build_rx: Option<Receiver<BuildMessage>>,
+    /// Address space usage, shown in a modal once the computer answers
+    pub usage: Option<UsageReport>,
+    /// Identifies the running ROM in snapshots
+    pub rom_hash: u64,
+    /// Identifies the CF image the computer was started with in snapshots
+    pub disk_hash: u64,
+    /// Cards in the backplane, shown in a modal once the computer answers
+    pub cards: Option<Vec<Card>>,
+    /// Card highlighted in the cards modal
+    pub card_selected: usize,
+    /// First line shown of the list of search matches on the CF card, when it is open
+    pub disk_matches_scroll: Option<usize>,
+    /// Regions of the address space, for coloring the memory tab
+    pub memory_map: MemoryMap,
+    /// Last expression evaluated and its value, shown in the Memory tab until dismissed
+    pub eval: Option<String>,
+    /// Address and message of the breakpoint the guest stopped on
+    pub breakpoint: Option<(u16, String)>,
+    /// Columns and rows inside the output pane, updated when it is drawn
+    pub output_size: (u16, u16),
+    /// Tell the guest about [`App::output_size`] through the serial card
+    pub report_size: bool,
+    /// Last size sent to the guest
+    pub reported_size: Option<(u16, u16)>,
+    /// Cards, symbols, project and debugger setup in use, saved by [`App::export_session`]
+    pub session: Session,
+    /// Where the session is saved
+    pub session_file: String,
+    /// Whether the computer was started, see [`App::boot`]
+    pub booted: bool,
 }
 
 
 impl App {
     /// Constructs a new instance of [`App`].
-    pub fn new(rom_file: String, cf_file: Option<String>) -> Self {
-        let data = fs::read(rom_file).expect("could not read file");
+    pub fn new(rom_file: String, cf_file: Option<String>, rom_options: RomOptions) -> Result<Self, Error> {
+        let rom = Rom::load(plu_error::read_file(&rom_file)?, &rom_options)?;
 
         let disk_data = match cf_file {
-            Some(d) => fs::read(d).expect("could not read file"),
+            Some(d) => plu_error::read_file(&d)?,
             None => vec![],
         };
+
+        Self::with_rom(rom, disk_data)
+    }
+
+    /// Constructs a new instance of [`App`] from ROM and disk images already loaded in memory.
+    ///
+    /// This does not need a terminal, which makes it usable from tests.
+    pub fn with_data(data: Vec<u8>, disk_data: Vec<u8>) -> Result<Self, Error> {
+        Self::with_rom(Rom::load(data, &RomOptions::default())?, disk_data)
+    }
+
+    /// Constructs a new instance of [`App`] running an already placed ROM.
+    pub fn with_rom(rom: Rom, disk_data: Vec<u8>) -> Result<Self, Error> {
+        plu_error::validate_disk(&disk_data)?;
+        let rom_hash = snapshot::rom_hash(&rom.data);
+        let disk_hash = snapshot::disk_hash(&disk_data);
+
         let (tx, rx) = mpsc::channel::<computer::ControllerMessage>();
         let (computer_tx, computer_rx) = mpsc::channel::<computer::ComputerMessage>();
-        let computer_data = data.clone();
-        let _ = thread::spawn(move || {
-            let mut computer = Computer::new(computer_tx, rx, computer_data, disk_data);
-            computer.reset();
+        let metrics = Arc::new(Metrics::default());
+        let computer_metrics = metrics.clone();
+        let serial_rx = Arc::new(RingBuffer::new(computer::SERIAL_RX_CAPACITY));
+        let computer_serial_rx = serial_rx.clone();
+        let _ = thread::Builder::new().name(COMPUTER_THREAD.to_string()).spawn(move || {
+            let mut computer = Computer::new(computer_tx, rx, computer_metrics, rom, disk_data);
+            computer.set_serial_input(computer_serial_rx);
+            computer.wait_for_boot();
 
             loop {
-                computer.step();
+                if let Err(e) = panic::catch_unwind(panic::AssertUnwindSafe(|| computer.step())) {
+                    let msg = e.downcast_ref::<&str>().map(|s| s.to_string())
+                        .or_else(|| e.downcast_ref::<String>().cloned())
+                        .unwrap_or_default();
+                    computer.fault(format!("Emulator crashed: {}", msg));
+                }
             }
         });
 
-        let mut output = VecDeque::new();
-        output.push_back(String::from(""));
-
-        Self {
+        Ok(Self {
             running: true,
             current_tab: Tab::Main,
-            output,
+            output: Console::default(),
             debug: VecDeque::new(),
             tx,
             rx: computer_rx,
@@ -95,7 +214,11 @@ impl App {
             memory_scroll: 0,
             output_scroll_state: ScrollbarState::default(),
             output_scroll: 0,
-            mem: vec![],
+            reference_scroll: 0,
+            mem: vec![0; ADDRESS_SPACE],
+            visible_rows: 64,
+            last_request_id: 0,
+            last_reply_id: 0,
             processor: Processor {
                 flags: 0b00110000,
                 acc: 0,
@@ -106,30 +229,117 @@ impl App {
                 clock: 0,
                 inst: 0xea,
             },
-            cursor_position: 0,
             tick_time: SystemTime::now(),
             old_clock: 0,
             speed: 0.0,
+            metrics,
+            serial_rx,
             log_level: 0,
+            fast_forward: false,
+            fast_forward_until: Instant::now(),
+            error: None,
+            input: InputState {
+                mode: InputMode::Normal,
+                value: String::new(),
+                cursor_position: 0,
+            },
+            search: None,
+            metrics_log: None,
+            project: None,
+            symbols: Symbols::default(),
+            build_error: None,
+            build_error_scroll: 0,
+            build_rx: None,
+            usage: None,
+            rom_hash,
+            disk_hash,
+            cards: None,
+            card_selected: 0,
+            disk_matches_scroll: None,
+            memory_map: MemoryMap::default(),
+            eval: None,
+            breakpoint: None,
+            output_size: (0, 0),
+            report_size: false,
+            reported_size: None,
+            session: Session::default(),
+            session_file: DEFAULT_SESSION_FILE.to_string(),
+            booted: false,
+        })
+    }
+
+    /// Builds the project and runs the result. When the build fails the ROM
+    /// left by the previous build runs, with the errors shown in a modal.
+    pub fn with_project(project: Project) -> Result<Self, Error> {
+        let built = project.build();
+        let rom = match project.rom() {
+            Ok(rom) => rom,
+            Err(e) => return Err(built.err().unwrap_or(e)),
+        };
+
+        let mut app = Self::with_rom(rom, project.disk()?)?;
+        app.symbols = project.symbols()?;
+        if let Some(source) = project.serial_input() {
+            app.add_serial_input(source)?;
+        }
+        for device in project.devices().map_err(Error::Argument)? {
+            app.insert_device(device);
+        }
+        app.build_error = built.err().map(|e| e.to_string());
+        app.session.project = Some(project.file().to_string()).filter(|f| !f.is_empty());
+        app.project = Some(project);
+        Ok(app)
+    }
+
+    /// Starts the computer, once the cards and options it needs from its first
+    /// instruction were sent. The first tick does it when nothing else did.
+    pub fn boot(&mut self) {
+        if !self.booted {
+            self.booted = true;
+            let _ = self.tx.send(ControllerMessage::Reset);
         }
     }
 
     /// Handles the tick event of the terminal.
     pub fn tick(&mut self) {
+        self.boot();
+        if self.fast_forward && Instant::now() > self.fast_forward_until {
+            self.set_fast_forward(false);
+        }
+
+        self.update_speed();
+        self.log_metrics();
+        self.receive_build();
+        self.report_output_size();
+
+        // Only ask for the memory that is on screen
         match self.current_tab {
-            Tab::Main => {
-                let _ = self.tx.send(computer::ControllerMessage::GetProc);
-            },
+            Tab::Main => { },
             Tab::Memory => {
-                let _ = self.tx.send(computer::ControllerMessage::GetMemory);
-                let _ = self.tx.send(computer::ControllerMessage::GetProc);
+                let start = (self.memory_scroll * 16).min(ADDRESS_SPACE - 16) as u16;
+                self.request(Request::ReadRange { start, len: self.visible_rows * 16 });
+                self.request(Request::ReadRegisters);
+                self.request(Request::Map);
+                for addr in self.session.watches.clone() {
+                    self.request(Request::ReadRange { start: addr, len: 1 });
+                }
+                if let Some(pattern) = self.search.as_ref().map(|s| s.pattern.clone()) {
+                    // Keep highlighting matches as the memory changes, the disk is
+                    // only searched once per pattern
+                    self.request(Request::Search(pattern));
+                }
+            },
+            Tab::Disassembly => {
+                // At most 3 bytes per instruction
+                self.request(Request::ReadRange { start: self.processor.pc, len: self.visible_rows * 3 });
+                self.request(Request::ReadRegisters);
             },
             Tab::Help => { },
         }
 
+        // Output of all the batches received during this tick, handled at once
+        let mut output = vec![];
 
-
-        
         while let Some(message) = self.rx.try_iter().next() {
             // Handle messages arriving from the UI.
             match message {
@@ -140,53 +350,490 @@ impl App {
                     }
                 }
 
-                ComputerMessage::Memory(mem) => {
-                    self.mem = mem;
+                ComputerMessage::Fault(msg) => {
+                    self.error = Some(msg);
                 }
 
-                ComputerMessage::Processor(proc) => {
-                    self.processor = proc;
-
-
-                    let t = SystemTime::now();
-
-                    let a = match t.duration_since(self.tick_time) {
-                        Ok(t) => t.as_millis(),
-                        Err(_) => 20,
-                    };
+                ComputerMessage::Breakpoint { pc, message } => {
+                    self.breakpoint = Some((pc, message));
+                }
 
-                    if a > 1000 {
-                        self.speed = (self.processor.clock as f64 - self.old_clock as f64) / a as f64;
+                ComputerMessage::Output(bytes) => output.extend(bytes),
 
-                        self.tick_time = SystemTime::now();
-                        self.old_clock = self.processor.clock;
-                    }
-                
-                    
-                }
-                ComputerMessage::Output(val) => {
-                    if val == 0x0D || val == 0x0A {
-                        self.cursor_position = 0;
-                        self.output.push_back(String::from(""));
-                        if self.output.len() > 22 {
-                            self.output_scroll = self.output.len() - 20;
+                ComputerMessage::Reply(id, reply) => {
+                    self.last_reply_id = id;
+                    match reply {
+                        Reply::Range { start, data } => {
+                            let start = start as usize;
+                            if let Some(mem) = self.mem.get_mut(start..start + data.len()) {
+                                mem.copy_from_slice(&data);
+                            }
+                        }
+                        Reply::Written { .. } => {}
+                        Reply::Usage(report) => self.usage = Some(report),
+                        Reply::Snapshot(_) => {}
+                        Reply::Cards(cards) => self.cards = Some(cards),
+                        Reply::Map(map) => self.memory_map = map,
+                        Reply::Eval { source, result } => {
+                            let line = match result {
+                                Ok(value) => format!("{} = {}", source, radices(value)),
+                                Err(e) => format!("{}: {}", source, e),
+                            };
+                            self.debug.push_back(line.clone());
+                            if self.debug.len() > 10 {
+                                self.debug.pop_front();
+                            }
+                            self.eval = Some(line);
                         }
-                    }else if val == 0x08 {
-                        if let Some(mut l) = self.output.pop_back() {
-                            l.pop();
-                            self.output.push_back(l);
-                            self.cursor_position = self.cursor_position.saturating_sub(1);
+                        Reply::Registers(proc) => self.processor = proc,
+                        Reply::Matches { pattern, addresses } => {
+                            if let Some(search) = self.search.as_mut().filter(|s| s.pattern == pattern) {
+                                search.matches = addresses;
+                            }
                         }
-                    } else {
-                        if let Some(mut l) = self.output.pop_back() {
-                            l.push(val as char);
-                            self.output.push_back(l);
-                            self.cursor_position = self.cursor_position.saturating_add(1);
+                        Reply::DiskMatches { pattern, offsets } => {
+                            if let Some(search) = self.search.as_mut().filter(|s| s.pattern == pattern) {
+                                search.disk_matches = offsets;
+                            }
                         }
                     }
                 }
             };
         }
+
+        if !output.is_empty() {
+            self.handle_output(&output);
+            self.metrics.output_backlog.fetch_sub(output.len() as u64, Ordering::Relaxed);
+        }
+    }
+
+    fn update_speed(&mut self) {
+        let t = SystemTime::now();
+
+        let a = match t.duration_since(self.tick_time) {
+            Ok(t) => t.as_millis(),
+            Err(_) => 20,
+        };
+
+        if a > 1000 {
+            let clock = self.metrics.cycles.load(Ordering::Relaxed);
+            self.speed = clock.saturating_sub(self.old_clock) as f64 / a as f64;
+
+            self.tick_time = SystemTime::now();
+            self.old_clock = clock;
+        }
+    }
+
+    /// Host time spent on the last frame, in percent of the time the frame lasts.
+    /// Over 100 the emulator cannot keep up with the clock speed.
+    pub fn frame_load(&self) -> f64 {
+        let busy = self.metrics.frame_busy_us.load(Ordering::Relaxed) as f64;
+        busy / computer::raster::FRAME_TIME.as_micros() as f64 * 100.0
+    }
+
+    /// Writes performance metrics to `path` every second.
+    pub fn record_metrics(&mut self, path: &str) -> Result<(), Error> {
+        self.metrics_log = Some(MetricsLog::create(path)?);
+        Ok(())
+    }
+
+    /// Sends the size of the output pane to the guest when it changes, so full screen programs can redraw
+    fn report_output_size(&mut self) {
+        if !self.report_size || self.output_size == (0, 0) || self.reported_size == Some(self.output_size) {
+            return;
+        }
+        let (columns, rows) = self.output_size;
+        let _ = self.tx.send(ControllerMessage::SetTerminalSize {
+            columns: columns.min(u8::MAX as u16) as u8,
+            rows: rows.min(u8::MAX as u16) as u8,
+        });
+        self.reported_size = Some(self.output_size);
+    }
+
+    fn log_metrics(&mut self) {
+        let Some(log) = self.metrics_log.as_mut().filter(|l| l.is_due()) else {
+            return;
+        };
+        let mut sample = log.sample(
+            self.metrics.cycles.load(Ordering::Relaxed),
+            self.metrics.instructions.load(Ordering::Relaxed),
+        );
+        sample.output_backlog = self.metrics.output_backlog.load(Ordering::Relaxed);
+        sample.requests_in_flight = self.last_request_id.wrapping_sub(self.last_reply_id);
+        if let Err(e) = log.write(&sample) {
+            // Stop logging rather than reporting the same error every second
+            self.metrics_log = None;
+            self.debug.push_back(e.to_string());
+        }
+    }
+
+    fn handle_output(&mut self, bytes: &[u8]) {
+        let rows = self.output.len();
+        let dropped = self.output.write(bytes);
+        self.output_scroll = self.output_scroll.saturating_sub(dropped);
+        if self.output.len() != rows && self.output.len() > 22 {
+            self.output_scroll = self.output.len() - 20;
+        }
+    }
+
+    /// Moves the RTC forward, to test date rollover and alarms without waiting.
+    pub fn advance_rtc(&mut self, by: chrono::Duration) {
+        let _ = self.tx.send(ControllerMessage::AdvanceRtc(by));
+        self.debug.push_back(format!("RTC moved forward by {} hours", by.num_hours()));
+        if self.debug.len() > 10 {
+            self.debug.pop_front();
+        }
+    }
+
+    /// Names addresses from the symbol file at `path`.
+    pub fn load_symbols(&mut self, path: &str) -> Result<(), Error> {
+        self.symbols = Symbols::parse(&String::from_utf8_lossy(&plu_error::read_file(path)?));
+        self.session.symbols = Some(path.to_string());
+        Ok(())
+    }
+
+    /// Saves the session to [`App::session_file`], to be restored with `--session`.
+    pub fn export_session(&mut self) {
+        let message = match self.session.save(&self.session_file) {
+            Ok(()) => format!("Session saved to {}", self.session_file),
+            Err(e) => format!("Session not saved, {}", e),
+        };
+        self.debug.push_back(message);
+        if self.debug.len() > 10 {
+            self.debug.pop_front();
+        }
+    }
+
+    /// Loads the session in [`App::session_file`] into the running computer. Its cards
+    /// and options are added to the ones in use, its project is only run by `--session`.
+    pub fn import_session(&mut self) {
+        let message = match Session::open(&self.session_file).and_then(|session| self.apply_session(&session)) {
+            Ok(()) => format!("Session loaded from {}", self.session_file),
+            Err(e) => format!("Session not loaded, {}", e),
+        };
+        self.debug.push_back(message);
+        if self.debug.len() > 10 {
+            self.debug.pop_front();
+        }
+    }
+
+    fn apply_session(&mut self, session: &Session) -> Result<(), Error> {
+        if let Some(path) = &session.symbols {
+            self.load_symbols(path)?;
+        }
+        if session.rtc {
+            self.insert_card(computer::RTC_SLOT, CardType::Rtc);
+        }
+        if session.trap {
+            self.insert_card(computer::TRAP_SLOT, CardType::Trap);
+        }
+        if session.strict_map {
+            self.set_strict_map(true);
+        }
+        if let Some(order) = session.irq_order.clone() {
+            self.insert_card(computer::IRQ_SLOT, CardType::Irq);
+            self.set_irq_order(order);
+        }
+        self.restore_debugger(session);
+        Ok(())
+    }
+
+    /// Sets the breakpoints, watchpoints and watches of a session, and writes its patches.
+    pub fn restore_debugger(&mut self, session: &Session) {
+        self.set_breakpoints(session.breakpoints.clone());
+        self.set_watchpoints(session.watchpoints.clone());
+        self.session.watches = session.watches.clone();
+        for patch in &session.patches {
+            for (i, &value) in patch.bytes.iter().enumerate() {
+                self.request(Request::WriteByte { addr: patch.address.wrapping_add(i as u16), value });
+            }
+        }
+        self.session.patches = session.patches.clone();
+    }
+
+    /// Stops the computer before it runs the instruction at one of `addresses`.
+    pub fn set_breakpoints(&mut self, addresses: Vec<u16>) {
+        self.session.breakpoints = addresses.clone();
+        let _ = self.tx.send(ControllerMessage::SetBreakpoints(addresses));
+    }
+
+    /// Stops the computer after an instruction writing to one of `addresses`.
+    pub fn set_watchpoints(&mut self, addresses: Vec<u16>) {
+        self.session.watchpoints = addresses.clone();
+        let _ = self.tx.send(ControllerMessage::SetWatchpoints(addresses));
+    }
+
+    /// Adds a breakpoint on the instruction at the PC, or removes the one there.
+    pub fn toggle_breakpoint(&mut self) {
+        let mut breakpoints = self.session.breakpoints.clone();
+        toggle(&mut breakpoints, self.processor.pc);
+        self.set_breakpoints(breakpoints);
+    }
+
+    /// Adds a watchpoint on the first byte of the top row of the Memory tab, or removes it.
+    pub fn toggle_watchpoint(&mut self) {
+        let mut watchpoints = self.session.watchpoints.clone();
+        toggle(&mut watchpoints, (self.memory_scroll * 16) as u16);
+        self.set_watchpoints(watchpoints);
+    }
+
+    /// Shows the first byte of the top row of the Memory tab above it, or stops showing it.
+    pub fn toggle_watch(&mut self) {
+        toggle(&mut self.session.watches, (self.memory_scroll * 16) as u16);
+    }
+
+    /// Feeds the serial card from `source` as well as the keyboard.
+    pub fn add_serial_input(&mut self, source: InputSource) -> Result<(), Error> {
+        source.spawn(self.serial_rx.clone())
+    }
+
+    /// Types a byte on the serial card, dropped when the guest is too far behind.
+    pub fn send_char(&mut self, byte: u8) {
+        if !self.serial_rx.push(byte) {
+            self.debug.push_back(String::from("Serial input full, key dropped"));
+            if self.debug.len() > 10 {
+                self.debug.pop_front();
+            }
+        }
+    }
+
+    /// Adds a card to the machine, replacing the one in `slot`.
+    pub fn insert_card(&mut self, slot: u16, card_type: CardType) {
+        match card_type {
+            CardType::Rtc => self.session.rtc = true,
+            CardType::Trap => self.session.trap = true,
+            _ => {}
+        }
+        let _ = self.tx.send(ControllerMessage::InsertCard { slot, card_type });
+    }
+
+    /// Adds a scripted device to the machine, replacing the card in its slot.
+    pub fn insert_device(&mut self, device: Device) {
+        let _ = self.tx.send(ControllerMessage::InsertDevice(Box::new(device)));
+    }
+
+    /// Writes a crash dump to `dir` each time the computer faults.
+    pub fn set_crash_dir(&mut self, dir: Option<PathBuf>) {
+        let _ = self.tx.send(ControllerMessage::SetCrashDir(dir));
+    }
+
+    /// Stops the computer on opcodes the 65C02 does not define, or runs them as NOPs.
+    pub fn set_invalid_opcode(&mut self, policy: InvalidOpcode) {
+        let _ = self.tx.send(ControllerMessage::SetInvalidOpcode(policy));
+    }
+
+    /// Stops the computer when a write hits both a card and ROM data, or two cards, instead of
+    /// only reporting the overlap when the machine is built.
+    pub fn set_strict_map(&mut self, strict: bool) {
+        self.session.strict_map = strict;
+        let _ = self.tx.send(ControllerMessage::SetStrictMap(strict));
+    }
+
+    /// Pulses the SO pin of the processor, setting the overflow flag.
+    pub fn set_overflow(&mut self) {
+        let _ = self.tx.send(ControllerMessage::SetOverflow);
+    }
+
+    /// Puts `slots` in front of the interrupt daisy chain, highest priority first.
+    pub fn set_irq_order(&mut self, slots: Vec<u16>) {
+        self.session.irq_order = Some(slots.clone());
+        let _ = self.tx.send(ControllerMessage::SetIrqOrder(slots));
+    }
+
+    /// Builds the project again and restarts the computer with the new ROM.
+    /// The build runs on its own thread, so the UI keeps drawing while it does,
+    /// and is picked up by [`App::tick`] when it is done.
+    pub fn rebuild(&mut self) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        if self.build_rx.is_some() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let spawned = thread::Builder::new().name(BUILD_THREAD.to_string()).spawn(move || {
+            let built = project.build()
+                .and_then(|_| Ok((project.rom()?, project.disk()?, project.symbols()?)));
+            let _ = tx.send(match built {
+                Ok((rom, disk, symbols)) => BuildMessage::Built { rom, disk, symbols },
+                Err(e) => BuildMessage::Failed(e.to_string()),
+            });
+        });
+        match spawned {
+            Ok(_) => {
+                self.build_rx = Some(rx);
+                self.build_error = None;
+                self.debug.push_back(String::from("Building..."));
+                if self.debug.len() > 10 {
+                    self.debug.pop_front();
+                }
+            }
+            Err(e) => self.build_error = Some(format!("could not start the build: {}", e)),
+        }
+    }
+
+    /// Restarts the computer with the result of the build, if it is done.
+    fn receive_build(&mut self) {
+        let Some(message) = self.build_rx.as_ref().and_then(|rx| rx.try_recv().ok()) else {
+            return;
+        };
+        self.build_rx = None;
+        match message {
+            BuildMessage::Built { rom, disk, symbols } => {
+                self.symbols = symbols;
+                self.error = None;
+                self.output = Console::default();
+                self.output_scroll = 0;
+                self.rom_hash = snapshot::rom_hash(&rom.data);
+                self.disk_hash = snapshot::disk_hash(&disk);
+                let _ = self.tx.send(ControllerMessage::Load { rom, disk });
+            }
+            BuildMessage::Failed(e) => {
+                self.build_error = Some(e);
+                self.build_error_scroll = 0;
+            }
+        }
+    }
+
+    /// Asks the computer for its usage report and waits for it, dropping other messages.
+    /// Meant for exit, when the UI is gone.
+    pub fn usage_report(&mut self) -> Option<UsageReport> {
+        match self.wait_for(Request::Usage)? {
+            Reply::Usage(report) => Some(report),
+            _ => None,
+        }
+    }
+
+    /// Saves the whole machine to `path`, to be picked up by [`App::resume`].
+    /// Meant for exit, like [`App::usage_report`].
+    pub fn hibernate(&mut self, path: &Path) -> Result<(), Error> {
+        let Some(Reply::Snapshot(mut snapshot)) = self.wait_for(Request::Snapshot) else {
+            return Err(Error::Snapshot("the computer did not answer".to_string()));
+        };
+        snapshot.rom_hash = self.rom_hash;
+        snapshot.disk_hash = self.disk_hash;
+        snapshot.output = self.output.lines().join(&b'\r');
+        std::fs::write(path, snapshot.to_bytes())
+            .map_err(|source| Error::Write { path: path.display().to_string(), source })
+    }
+
+    /// Carries on from the snapshot saved at `path` by [`App::hibernate`], with the cards it had.
+    /// Returns false, leaving the computer alone, when there is no snapshot.
+    ///
+    /// The snapshot replaces the cards in the backplane, insert cards asked
+    /// for on the command line after resuming.
+    pub fn resume(&mut self, path: &Path) -> Result<bool, Error> {
+        let data = match std::fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(Error::Snapshot(format!("{}: {}", path.display(), e))),
+        };
+        let snapshot = Snapshot::from_bytes(&data)?;
+        if snapshot.rom_hash != self.rom_hash {
+            return Err(Error::Snapshot("it was saved with another ROM".to_string()));
+        }
+        // The disk in the snapshot would silently undo changes made to the image since
+        if snapshot.disk_hash != self.disk_hash {
+            return Err(Error::Snapshot("it was saved with another CF image".to_string()));
+        }
+        self.session.rtc = snapshot.cards.iter().any(|c| c.card_type == CardType::Rtc);
+        self.session.trap = snapshot.cards.iter().any(|c| c.card_type == CardType::Trap);
+        self.output = Console::default();
+        self.output.write(&snapshot.output);
+        self.processor = snapshot.processor.clone();
+        self.booted = true;
+        let _ = self.tx.send(ControllerMessage::Restore(Box::new(snapshot)));
+        Ok(true)
+    }
+
+    /// Pulls the card selected in the cards modal out of the backplane, or puts it back.
+    pub fn toggle_card(&mut self) {
+        let Some(card) = self.cards.as_mut().and_then(|cards| cards.get_mut(self.card_selected)) else {
+            return;
+        };
+        card.enabled = !card.enabled;
+        let _ = self.tx.send(ControllerMessage::EnableCard { slot: card.slot, enabled: card.enabled });
+    }
+
+    /// Sends a request and waits for its reply, dropping other messages.
+    fn wait_for(&mut self, request: Request) -> Option<Reply> {
+        let id = self.request(request);
+        while let Ok(message) = self.rx.recv_timeout(Duration::from_secs(1)) {
+            if let ComputerMessage::Reply(reply_id, reply) = message {
+                if reply_id == id {
+                    return Some(reply);
+                }
+            }
+        }
+        None
+    }
+
+    /// Sends a request to the computer. Its reply will carry the returned id.
+    pub fn request(&mut self, request: Request) -> RequestId {
+        self.last_request_id = self.last_request_id.wrapping_add(1);
+        let _ = self.tx.send(ControllerMessage::Request(self.last_request_id, request));
+        self.last_request_id
+    }
+
+    /// Returns everything the computer has printed so far, one line per output line.
+    pub fn output_text(&self) -> String {
+        self.output.text()
+    }
+
+    /// Keeps fast forward running while the key is held down.
+    pub fn hold_fast_forward(&mut self) {
+        self.fast_forward_until = Instant::now() + FAST_FORWARD_HOLD;
+        if !self.fast_forward {
+            self.set_fast_forward(true);
+        }
+    }
+
+    pub fn set_fast_forward(&mut self, fast_forward: bool) {
+        self.fast_forward = fast_forward;
+        let _ = self.tx.send(computer::ControllerMessage::SetFastForward(fast_forward));
+    }
+
+    /// Starts typing a search pattern in the Memory tab.
+    pub fn start_search(&mut self) {
+        self.input.mode = InputMode::Editing;
+        self.input.value.clear();
+        self.input.cursor_position = 0;
+    }
+
+    /// Searches the pattern typed so far. Invalid patterns keep the input open.
+    pub fn submit_search(&mut self) {
+        let Some(pattern) = search::parse_pattern(&self.input.value) else {
+            return;
+        };
+        self.input.mode = InputMode::Normal;
+        self.search = Some(Search::new(pattern.clone()));
+        self.request(Request::Search(pattern.clone()));
+        self.request(Request::SearchDisk(pattern));
+    }
+
+    /// Starts typing an expression in the Memory tab.
+    pub fn start_eval(&mut self) {
+        self.start_search();
+        self.input.mode = InputMode::Eval;
+    }
+
+    /// Asks the computer to evaluate the expression typed, with the symbols as variables.
+    pub fn submit_eval(&mut self) {
+        self.input.mode = InputMode::Normal;
+        if self.input.value.trim().is_empty() {
+            return;
+        }
+        let vars = self.symbols.iter().map(|(addr, name)| (name.to_string(), addr as i64)).collect();
+        self.request(Request::Eval { source: self.input.value.clone(), vars });
+    }
+
+    /// Scrolls the Memory tab to the next match below the top row.
+    pub fn next_match(&mut self) {
+        let top = self.memory_scroll * 16;
+        if let Some(addr) = self.search.as_ref().and_then(|s| s.next_after(top + 15)) {
+            self.memory_scroll = addr / 16;
+            self.memory_scroll_state = self.memory_scroll_state.position(self.memory_scroll);
+        }
     }
 
     pub fn init(&mut self) {
@@ -200,3 +847,24 @@ impl App {
         self.running = false;
     }
 }
+
+/// Adds `addr` to `addresses`, or removes it when it is already there.
+fn toggle(addresses: &mut Vec<u16>, addr: u16) {
+    match addresses.iter().position(|&a| a == addr) {
+        Some(i) => {
+            addresses.remove(i);
+        }
+        None => addresses.push(addr),
+    }
+}
+
+/// Writes a value in decimal, hexadecimal and binary, with its character when
+/// printable. Small negative values are shown as the 16 bit two's complement.
+pub fn radices(value: i64) -> String {
+    let bits = if (i16::MIN as i64..0).contains(&value) { value as u16 as u64 } else { value as u64 };
+    let mut text = format!("{} ${:X} %{:b}", value, bits, bits);
+    if let Some(c) = u8::try_from(value).ok().filter(u8::is_ascii_graphic) {
+        text.push_str(&format!(" '{}'", c as char));
+    }
+    text
+}
@@ -0,0 +1,249 @@
+use crate::computer::InvalidOpcode;
+use crate::error::Error;
+use crate::input::InputSource;
+use crate::rom::{self, RomOptions};
+
+pub const USAGE: &str = "Usage: plu [--rom-base ADDR] [--rom-size SIZE] [--metrics FILE] [--crash-dir DIR] [--on-invalid-opcode halt|nop] [--usage-report] [--rtc] [--trap] [--report-size] [--serial-input FILE|-] [--hibernate] [--resume] [--strict-map] [--irq-order SLOTS] [--symbols FILE] [--session FILE] <rom.bin> [cfcard.img]
+       plu demo [NAME]
+       plu lockstep [--rom-base ADDR] [--rom-size SIZE] <rom.bin> [cfcard.img]
+       plu bisect --script FILE [--rom-base ADDR] [--rom-size SIZE] [--cf FILE] <oldest.bin> ... <newest.bin>
+       plu build-and-run <project.toml>";
+
+/// What the command line asks for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    Run(Args),
+    /// Run an example ROM, or list them without a name
+    Demo(Option<String>),
+    /// Assemble a project, then run it
+    BuildAndRun(String),
+    /// Compare the two CPU cores on a ROM, without the UI
+    Lockstep(Args),
+    /// Find the first build failing a test script
+    Bisect(BisectArgs),
+}
+
+/// Arguments of `plu bisect`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BisectArgs {
+    pub script_file: String,
+    /// ROM images or snapshots, from the oldest to the newest
+    pub builds: Vec<String>,
+    pub rom_options: RomOptions,
+    pub cf_file: Option<String>,
+}
+
+/// Command line arguments.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Args {
+    /// None when a session names the project to run instead
+    pub rom_file: Option<String>,
+    pub cf_file: Option<String>,
+    pub rom_options: RomOptions,
+    /// JSONL file receiving performance metrics every second
+    pub metrics_file: Option<String>,
+    /// Where crash dumps go, none are written without it
+    pub crash_dir: Option<String>,
+    pub on_invalid_opcode: InvalidOpcode,
+    /// Print which memory was used on exit
+    pub usage_report: bool,
+    /// Add the RTC card
+    pub rtc: bool,
+    /// Add the trap card, for breakpoints in guest code
+    pub trap: bool,
+    /// Show the size of the output pane to the guest
+    pub report_size: bool,
+    /// Serial input besides the keyboard
+    pub serial_input: Option<InputSource>,
+    /// Save the state of the machine next to the ROM on exit
+    pub hibernate: bool,
+    /// Carry on from the state saved on the last exit
+    pub resume: bool,
+    /// Stop on writes hitting both a card and the ROM, or two cards
+    pub strict_map: bool,
+    /// Slots first in the interrupt daisy chain, adding the acknowledge card
+    pub irq_order: Option<Vec<u16>>,
+    /// Symbol file of the ROM
+    pub symbols_file: Option<String>,
+    /// Debugger setup to restore, and where Ctrl+S saves it
+    pub session_file: Option<String>,
+}
+
+/// Parses the command line arguments, without the program name, including subcommands.
+pub fn parse_command(args: impl IntoIterator<Item = String>) -> Result<Command, Error> {
+    let mut args = args.into_iter().peekable();
+    let command = match args.peek().map(String::as_str) {
+        Some("demo") => {
+            args.next();
+            Command::Demo(args.next())
+        }
+        Some("build-and-run") => {
+            args.next();
+            let project = args.next().ok_or_else(|| Error::Argument("build-and-run needs a project file".to_string()))?;
+            Command::BuildAndRun(project)
+        }
+        Some("lockstep") => {
+            args.next();
+            return parse(args).map(Command::Lockstep);
+        }
+        Some("bisect") => {
+            args.next();
+            return parse_bisect(args).map(Command::Bisect);
+        }
+        _ => return parse(args).map(Command::Run),
+    };
+
+    if let Some(extra) = args.next() {
+        return Err(Error::Argument(format!("unexpected argument {}", extra)));
+    }
+    Ok(command)
+}
+
+/// Parses the command line arguments, without the program name.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, Error> {
+    let mut args = args.into_iter();
+    let mut files = vec![];
+    let mut rom_options = RomOptions::default();
+    let mut metrics_file = None;
+    let mut crash_dir = None;
+    let mut on_invalid_opcode = InvalidOpcode::default();
+    let mut usage_report = false;
+    let mut rtc = false;
+    let mut trap = false;
+    let mut report_size = false;
+    let mut serial_input = None;
+    let mut hibernate = false;
+    let mut resume = false;
+    let mut strict_map = false;
+    let mut irq_order = None;
+    let mut symbols_file = None;
+    let mut session_file = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rom-base" => {
+                let value = value(&mut args, &arg)?;
+                rom_options.base = Some(rom::parse_address(&value)
+                    .ok_or_else(|| Error::Argument(format!("invalid ROM base address {}", value)))?);
+            }
+            "--rom-size" => {
+                let value = value(&mut args, &arg)?;
+                rom_options.size = Some(rom::parse_size(&value)
+                    .ok_or_else(|| Error::Argument(format!("invalid ROM size {}", value)))?);
+            }
+            "--metrics" => {
+                metrics_file = Some(value(&mut args, &arg)?);
+            }
+            "--crash-dir" => {
+                crash_dir = Some(value(&mut args, &arg)?);
+            }
+            "--on-invalid-opcode" => {
+                on_invalid_opcode = match value(&mut args, &arg)?.as_str() {
+                    "halt" => InvalidOpcode::Halt,
+                    "nop" => InvalidOpcode::Nop,
+                    other => return Err(Error::Argument(format!("invalid opcode policy {}, expected halt or nop", other))),
+                };
+            }
+            "--usage-report" => usage_report = true,
+            "--rtc" => rtc = true,
+            "--trap" => trap = true,
+            "--report-size" => report_size = true,
+            "--serial-input" => {
+                serial_input = Some(InputSource::parse(&value(&mut args, &arg)?));
+            }
+            "--hibernate" => hibernate = true,
+            "--resume" => resume = true,
+            "--strict-map" => strict_map = true,
+            "--irq-order" => {
+                let value = value(&mut args, &arg)?;
+                irq_order = Some(parse_slots(&value)
+                    .ok_or_else(|| Error::Argument(format!("invalid interrupt order {}", value)))?);
+            }
+            "--symbols" => {
+                symbols_file = Some(value(&mut args, &arg)?);
+            }
+            "--session" => {
+                session_file = Some(value(&mut args, &arg)?);
+            }
+            a if a.starts_with("--") => return Err(Error::Argument(format!("unknown option {}", a))),
+            _ => files.push(arg),
+        }
+    }
+
+    let mut files = files.into_iter();
+    let rom_file = files.next();
+    // A session naming a project can be run without a ROM
+    if rom_file.is_none() && session_file.is_none() {
+        return Err(Error::Argument(USAGE.to_string()));
+    }
+    let cf_file = files.next();
+    if let Some(extra) = files.next() {
+        return Err(Error::Argument(format!("unexpected argument {}", extra)));
+    }
+
+    Ok(Args {
+        rom_file,
+        cf_file,
+        rom_options,
+        metrics_file,
+        crash_dir,
+        on_invalid_opcode,
+        usage_report,
+        rtc,
+        trap,
+        report_size,
+        serial_input,
+        hibernate,
+        resume,
+        strict_map,
+        irq_order,
+        symbols_file,
+        session_file,
+    })
+}
+
+fn parse_bisect(args: impl IntoIterator<Item = String>) -> Result<BisectArgs, Error> {
+    let mut args = args.into_iter();
+    let mut bisect = BisectArgs::default();
+    let mut script_file = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--script" => script_file = Some(value(&mut args, &arg)?),
+            "--cf" => bisect.cf_file = Some(value(&mut args, &arg)?),
+            "--rom-base" => {
+                let value = value(&mut args, &arg)?;
+                bisect.rom_options.base = Some(rom::parse_address(&value)
+                    .ok_or_else(|| Error::Argument(format!("invalid ROM base address {}", value)))?);
+            }
+            "--rom-size" => {
+                let value = value(&mut args, &arg)?;
+                bisect.rom_options.size = Some(rom::parse_size(&value)
+                    .ok_or_else(|| Error::Argument(format!("invalid ROM size {}", value)))?);
+            }
+            a if a.starts_with("--") => return Err(Error::Argument(format!("unknown option {}", a))),
+            _ => bisect.builds.push(arg),
+        }
+    }
+
+    bisect.script_file = script_file.ok_or_else(|| Error::Argument("bisect needs a test --script".to_string()))?;
+    if bisect.builds.len() < 2 {
+        return Err(Error::Argument("bisect needs at least two builds, from the oldest to the newest".to_string()));
+    }
+    Ok(bisect)
+}
+
+/// Comma separated list of slots, such as `6,4,5`
+fn parse_slots(s: &str) -> Option<Vec<u16>> {
+    let slots: Vec<u16> = s.split(',').map(|slot| slot.trim().parse().ok()).collect::<Option<_>>()?;
+    valid_slots(&slots).then_some(slots)
+}
+
+/// Slots exist and are only given once
+pub(crate) fn valid_slots(slots: &[u16]) -> bool {
+    slots.iter().enumerate().all(|(i, slot)| *slot <= 6 && !slots[..i].contains(slot))
+}
+
+fn value(args: &mut impl Iterator<Item = String>, option: &str) -> Result<String, Error> {
+    args.next().ok_or_else(|| Error::Argument(format!("{} needs a value", option)))
+}
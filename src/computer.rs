@@ -1,8 +1,41 @@
-use std::sync::mpsc;
+use std::collections::{BTreeSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
 use std::time;
 use std::thread;
 
+use memchr::memmem;
+
+use crate::error::SECTOR_SIZE;
+use crate::ring::RingBuffer;
+use crate::rom::{Rom, ADDRESS_SPACE};
+use crate::script::{self, Vars};
+use crate::search;
+use crate::usage::UsageReport;
+
+pub mod bisect;
+pub mod crash;
 mod decode;
+pub mod device;
+pub mod disassembler;
+pub mod lockstep;
+pub mod map;
+pub mod opcodes;
+pub mod raster;
+pub mod rtc;
+pub mod scheduler;
+pub mod snapshot;
+mod table;
+
+use crash::{CrashDump, TraceEntry, TRACE_LEN};
+use device::Device;
+use map::{Conflict, MemoryMap};
+use raster::{Position, Raster};
+use rtc::Rtc;
+use scheduler::{Event, Scheduler};
+use snapshot::Snapshot;
+
 #[derive(Clone, Debug)]
 pub struct Info {
     pub msg: String,
@@ -26,27 +59,105 @@ pub enum AdressingMode {
     ZeroPageY = 9,
     Accumulator = 10,
     ZeroPageIndirect = 11,
-    None = 12,   
+    None = 12,
+    /// No operand
+    Implied = 13,
+    /// Branch offset
+    Relative = 14,
+    /// Zero page address followed by a branch offset, used by BBR and BBS
+    ZeroPageRelative = 15,
+}
+
+/// Identifier of a [`Request`], sent back with its [`Reply`]
+pub type RequestId = u32;
+
+/// Requests are answered before the computer runs more than
+/// [`MESSAGE_INTERVAL`] instructions, so replies always describe
+/// the machine between two instructions.
+pub enum Request {
+    /// Read `len` bytes of memory starting at `start`, without side effects on cards
+    ReadRange { start: u16, len: usize },
+    WriteByte { addr: u16, value: u8 },
+    ReadRegisters,
+    /// Find all occurrences of a byte pattern in memory
+    Search(Vec<u8>),
+    /// Find all occurrences of a byte pattern in the CF card image
+    SearchDisk(Vec<u8>),
+    /// Memory written and ROM run since the ROM was loaded
+    Usage,
+    /// Cards in the backplane, enabled or not
+    Cards,
+    /// State of the whole machine, without the ROM hash and output known to the UI
+    Snapshot,
+    /// Where the ROM and the cards are
+    Map,
+    /// Evaluate a [`Script`](crate::script::Script) expression over memory and
+    /// the registers `A`, `X`, `Y`, `PC`, `SP` and `P`, with extra variables such as symbols
+    Eval { source: String, vars: Vars },
+}
+
+pub enum Reply {
+    Range { start: u16, data: Vec<u8> },
+    Written { addr: u16 },
+    Registers(Processor),
+    Matches { pattern: Vec<u8>, addresses: Vec<usize> },
+    DiskMatches { pattern: Vec<u8>, offsets: Vec<usize> },
+    Usage(UsageReport),
+    Snapshot(Box<Snapshot>),
+    Cards(Vec<Card>),
+    Map(MemoryMap),
+    Eval { source: String, result: Result<i64, String> },
 }
 
 pub enum ControllerMessage {
     ButtonPressed(String),
-    GetMemory,
-    GetProc,
+    Request(RequestId, Request),
     Reset,
     TogglePause,
-    SendChar(char),
     SetDebug(u8),
+    SetFastForward(bool),
+    /// Replace the ROM and CF card image, then reset
+    Load { rom: Rom, disk: Vec<u8> },
+    /// Directory receiving a crash dump on each fault, none to disable them
+    SetCrashDir(Option<PathBuf>),
+    /// Stop the computer when a write hits both a card and ROM data, or two cards
+    SetStrictMap(bool),
+    SetInvalidOpcode(InvalidOpcode),
+    /// Move the RTC forward while the program keeps running
+    AdvanceRtc(chrono::Duration),
+    /// Put a card in a slot, replacing the one already there
+    InsertCard { slot: u16, card_type: CardType },
+    /// Carry on from a saved state
+    Restore(Box<Snapshot>),
+    /// Size of the output pane, shown to the guest by the serial card
+    SetTerminalSize { columns: u8, rows: u8 },
+    /// Pull a card out of the backplane, or put it back
+    EnableCard { slot: u16, enabled: bool },
+    /// Slots in front of the others in the interrupt daisy chain, highest priority first
+    SetIrqOrder(Vec<u16>),
+    /// Put a scripted device in its slot, replacing the card already there
+    InsertDevice(Box<Device>),
+    /// Pulse the SO pin of the processor
+    SetOverflow,
+    /// Stop before running the instruction at one of these addresses
+    SetBreakpoints(Vec<u16>),
+    /// Stop after the instruction writing to one of these addresses
+    SetWatchpoints(Vec<u16>),
 }
 
 pub enum ComputerMessage {
     Info(String),
-    Output(u8),
-    Memory(Vec<u8>),
-    Processor(Processor)
+    /// Bytes written to the serial card since the last batch
+    Output(Vec<u8>),
+    Reply(RequestId, Reply),
+    /// The computer stopped because of an error
+    Fault(String),
+    /// The guest paused the computer through the trap card, or it reached a
+    /// breakpoint or watchpoint set by the UI
+    Breakpoint { pc: u16, message: String },
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DiskCommand {
     Read = 0x20,
     Write = 0x30,
@@ -69,7 +180,23 @@ impl TryFrom<u8> for DiskCommand {
 }
 
 
-#[derive(Clone, Debug)]
+/// Counters published by the computer thread, read by the UI without going through the channels
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub cycles: AtomicU64,
+    pub instructions: AtomicU64,
+    /// Interrupt requests taken from the IRQ line, BRK is not counted
+    pub irqs: AtomicU64,
+    /// Output bytes sent to the UI and not handled yet
+    pub output_backlog: AtomicU64,
+    /// Frames of the emulated display since start
+    pub frames: AtomicU64,
+    /// Host time spent emulating the last frame, in microseconds, to compare with
+    /// [`raster::FRAME_TIME`]
+    pub frame_busy_us: AtomicU64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Processor {
     pub flags: u8,
     pub acc: u8,
@@ -81,19 +208,48 @@ pub struct Processor {
     pub inst: u8,
 }
 
+/// Implementation of the instructions, the two are compared by `plu lockstep`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Core {
+    /// Matches the mnemonic of every instruction, each handler decoding its own operand
+    #[default]
+    Legacy,
+    /// Decodes the operand from the opcode table, then runs the operation of the opcode
+    Table,
+}
+
+/// What to do with the opcodes the 65C02 does not define
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InvalidOpcode {
+    /// Run them as one byte NOPs, like the processor
+    #[default]
+    Nop,
+    /// Stop the computer with a fault, as the program ran into data
+    Halt,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum CardType {
     CF,
     Serial,
     IO,
     Ram,
+    Rtc,
+    /// Breakpoints from guest code
+    Trap,
+    /// Acknowledge register of the interrupt daisy chain
+    Irq,
+    /// Registers run by scripts, see [`Device`]
+    Device,
     None,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Card {
-    slot: u16,
-    card_type: CardType,
+    pub slot: u16,
+    pub card_type: CardType,
+    /// A disabled card is out of the backplane, its window is open bus
+    pub enabled: bool,
 }
 
 
@@ -107,12 +263,60 @@ pub struct Computer {
     disk_cnt: u16,
     command: DiskCommand,
     speed: u64,
+    fast_forward: bool,
+    throttle_time: time::Instant,
+    throttle_clock: u128,
+    /// Time spent sleeping to keep to the clock speed, not counted as busy
+    slept: time::Duration,
+    /// Host time spent running instructions since the frame started
+    frame_busy: time::Duration,
+    raster: Raster,
+    scheduler: Scheduler,
+    /// Cycle of the earliest scheduled event, checked after every instruction
+    next_event: u128,
     data: Vec<u8>,
     disk: Vec<u8>,
     tx: mpsc::Sender<ComputerMessage>,
     rx: mpsc::Receiver<ControllerMessage>,
+    metrics: Arc<Metrics>,
+    output: Vec<u8>,
+    /// Searcher for the last pattern, kept since the UI repeats its search every tick
+    finder: Option<memmem::Finder<'static>>,
+    /// Last instructions run, for crash dumps
+    trace: VecDeque<TraceEntry>,
+    /// Sectors of the CF card written since the disk was loaded
+    dirty_sectors: BTreeSet<u32>,
+    crash_dir: Option<PathBuf>,
+    /// Addresses written by the program, for the usage report
+    written: Vec<bool>,
+    /// Addresses run as opcodes or operands
+    executed: Vec<bool>,
+    rom: Rom,
+    core: Core,
+    /// Found by [`map::check`] whenever the cards or the ROM change
+    conflicts: Vec<Conflict>,
+    strict_map: bool,
+    invalid_opcode: InvalidOpcode,
+    rtc: Rtc,
     pub info: Vec<Info>,
     pub cards: Vec<Card>,
+    /// Input waiting for the serial card to be read, filled by other threads
+    serial_rx: Arc<RingBuffer>,
+    /// Columns and rows of the output pane, when the UI reports them
+    terminal_size: Option<(u8, u8)>,
+    /// Slots in front of the daisy chain, the others follow in slot order
+    irq_order: Vec<u16>,
+    /// Highest priority slot asserting IRQ, worked out again only when a VIA flag or
+    /// enable register changes, a device handler runs or the cards change
+    irq_line: Option<u16>,
+    /// Scripts of the [`CardType::Device`] cards
+    devices: Vec<Device>,
+    /// A device pulled SO during the instruction, the flag is set once it completes
+    so_pending: bool,
+    breakpoints: BTreeSet<u16>,
+    watchpoints: BTreeSet<u16>,
+    /// Breakpoint the computer stopped on, its instruction runs when it carries on
+    stopped_at: Option<u16>,
 }
 
 
@@ -120,23 +324,89 @@ const FLAG_C: u8 = 1;
 const FLAG_Z: u8 = 2;
 const FLAG_I: u8 = 4;
 const FLAG_D: u8 = 8;
+/// Pushed with the flags by BRK, not by interrupts
+const FLAG_B: u8 = 0x10;
 const FLAG_O: u8 = 0x40;
 const FLAG_N: u8 = 0x80;
 
-const CF_ADDRESS: u16 = 0xFFD0;
-
-const IO_BASE: u16 = 0xFF80;
+pub const IO_BASE: u16 = 0xFF80;
 const IO_TOP: u16 = 0xFFEF;
+/// Read from the window of a disabled card, nothing drives the data bus
+const OPEN_BUS: u8 = 0xFF;
+
+/// Registers of the 6522 VIA on the IO card, relative to its slot address
+const VIA_T1CL: u16 = 4;
+const VIA_T1CH: u16 = 5;
+const VIA_ACR: u16 = 0xB;
+const VIA_IFR: u16 = 0xD;
+const VIA_IER: u16 = 0xE;
+/// Timer 1 bit of the interrupt flag register
+const VIA_IFR_T1: u8 = 0x40;
+/// Any flag set, in the interrupt flag register
+const VIA_IFR_ANY: u8 = 0x80;
+/// Timer 1 reloads itself from its latches when this ACR bit is set
+const VIA_ACR_FREE_RUN: u8 = 0x40;
+/// Writing the IER with this bit set enables the other bits given, clearing it disables them
+const VIA_IER_SET: u8 = 0x80;
+
+/// Registers of the serial card, relative to its slot address
+const SERIAL_STATUS: u16 = 1;
+/// Size of the output pane, for full screen programs
+const SERIAL_COLUMNS: u16 = 4;
+const SERIAL_ROWS: u16 = 5;
+/// Status bit set when a received character is waiting
+const SERIAL_RX_FULL: u8 = 0x08;
+/// Status bit set when the output pane changed size, cleared by reading the size
+const SERIAL_RESIZED: u8 = 0x20;
+
+/// Registers of the trap card. Writing the high byte of the address of a zero
+/// terminated message pauses the computer and shows the message.
+const TRAP_MESSAGE_LO: u16 = 0;
+const TRAP_MESSAGE_HI: u16 = 1;
+/// Writing a code here pauses the computer without a message
+const TRAP_CODE: u16 = 2;
+/// Longest message shown for a trap
+const TRAP_MESSAGE_LEN: usize = 80;
+
+/// Registers of the interrupt acknowledge card. They hold the slot of the highest
+/// priority card asserting IRQ, and twice that for indexing a table of vectors,
+/// or [`IRQ_NONE`] when no card is interrupting.
+const IRQ_ACK: u16 = 0;
+const IRQ_ACK_VECTOR: u16 = 1;
+pub const IRQ_NONE: u8 = 0xFF;
+
+/// Maximum number of instructions run between two checks for controller messages
+/// and serial input
+pub const MESSAGE_INTERVAL: usize = 100;
+
+/// Bytes of serial input queued before the sources have to wait for the guest
+pub const SERIAL_RX_CAPACITY: usize = 4096;
+
+/// Number of output bytes the UI can fall behind before the computer waits for it
+pub const OUTPUT_BACKLOG: u64 = 64 * 1024;
+
+/// Slot of the optional RTC card
+pub const RTC_SLOT: u16 = 3;
+/// Slot of the optional trap card
+pub const TRAP_SLOT: u16 = 2;
+/// Slot of the optional interrupt acknowledge card
+pub const IRQ_SLOT: u16 = 1;
+/// Timer used by scripted devices for their tick handler
+const DEVICE_TICK: u8 = 0;
+
+/// Emulated clock speed in Hz
+const CLOCK_SPEED: u128 = 1_000_000;
+/// Number of cycles to run between two real-time synchronisations
+const THROTTLE_CYCLES: u128 = 10_000;
 
 impl Computer {
-    pub fn new(tx: mpsc::Sender<ComputerMessage>, rx:  mpsc::Receiver<ControllerMessage>, mut data: Vec<u8>, disk: Vec<u8>) -> Computer {
-        let rom_size = data.len();
-        let mut ram: Vec<u8> = vec![0; 0x10000-rom_size];
-        ram.fill(0);
-        ram.append(&mut data);
+    pub fn new(tx: mpsc::Sender<ComputerMessage>, rx:  mpsc::Receiver<ControllerMessage>, metrics: Arc<Metrics>, rom: Rom, disk: Vec<u8>) -> Computer {
+        let mut ram: Vec<u8> = vec![0; ADDRESS_SPACE];
+        let base = rom.base as usize;
+        ram[base..base + rom.data.len()].copy_from_slice(&rom.data);
 
 
-        Self {
+        let mut computer = Self {
             log_level: 0,
             data: ram,
             disk,
@@ -145,9 +415,31 @@ impl Computer {
             command: DiskCommand::None,
             tx,
             rx,
+            metrics,
+            output: vec![],
+            finder: None,
+            trace: VecDeque::with_capacity(TRACE_LEN),
+            dirty_sectors: BTreeSet::new(),
+            crash_dir: None,
+            written: vec![false; ADDRESS_SPACE],
+            executed: vec![false; ADDRESS_SPACE],
+            rom,
+            core: Core::default(),
+            conflicts: vec![],
+            strict_map: false,
+            invalid_opcode: InvalidOpcode::default(),
+            rtc: Rtc::new(chrono::Local::now().naive_local()),
             paused: false,
             step: false,
             speed: 0,
+            fast_forward: false,
+            throttle_time: time::Instant::now(),
+            throttle_clock: 0,
+            slept: time::Duration::ZERO,
+            frame_busy: time::Duration::ZERO,
+            raster: Raster::new(CLOCK_SPEED),
+            scheduler: Scheduler::default(),
+            next_event: u128::MAX,
             info: vec![],
             processor: Processor {
                 flags: 0b00110000,
@@ -161,70 +453,304 @@ impl Computer {
                 inst: 0xea,
             },
             cards: vec![
+                Card {
+                    slot: 4,
+                    card_type: CardType::IO,
+                    enabled: true,
+                },
                 Card {
                     slot: 5,
                     card_type: CardType::CF,
+                    enabled: true,
                 },
                 Card {
                     slot: 6,
                     card_type: CardType::Serial,
+                    enabled: true,
                 }
             ],
-        }
+            serial_rx: Arc::new(RingBuffer::new(SERIAL_RX_CAPACITY)),
+            terminal_size: None,
+            irq_order: vec![],
+            irq_line: None,
+            so_pending: false,
+            devices: vec![],
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeSet::new(),
+            stopped_at: None,
+        };
+        computer.schedule(THROTTLE_CYCLES, Event::Throttle);
+        computer.schedule(computer.raster.cycles_per_frame, Event::Frame);
+        computer.check_map();
+        computer
     }
 
     pub fn step(&mut self) -> bool {
         while let Some(message) = self.rx.try_iter().next() {
-            // Handle messages arriving from the controller.
-            match message {
-                ControllerMessage::GetMemory => {
-                    let _ = self.tx.send(ComputerMessage::Memory(self.data.clone()));
-                }
-                ControllerMessage::GetProc => {
-                    let _ = self.tx.send(ComputerMessage::Processor(self.processor.clone()));
-                }
-                ControllerMessage::Reset => {
-                    self.reset();
-                }
-                ControllerMessage::SendChar(c) => {
-                    if let Some(serial) = self.cards.iter().find(|a| a.card_type == CardType::Serial) {
-                        let addr = IO_BASE + serial.slot * 0x10;
-                        if self.log_level > 2 {
-                            let _ = self.tx.send(ComputerMessage::Info(format!("serial out {:#x}", addr)));
-                        }
-                        
-                        self.data[addr as usize] = c as u8;
-                        self.data[addr as usize + 1] = 0x08;
-                    }
-                }
-                ControllerMessage::SetDebug(c) => {
-                    self.log_level = c;
-                }
-                ControllerMessage::TogglePause => {
-                    self.paused = !self.paused;
-                }
-                _ => {},
-            };
+            self.handle_message(message);
         }
+        self.receive_serial();
 
         if self.paused && !self.step {
-            thread::sleep(time::Duration::from_millis(100));
+            // Keep answering requests while paused
+            if let Ok(message) = self.rx.recv_timeout(time::Duration::from_millis(100)) {
+                self.handle_message(message);
+            }
+            return true;
+        }
+
+        if self.metrics.output_backlog.load(Ordering::Relaxed) > OUTPUT_BACKLOG {
+            // Let the UI catch up with the output
+            if let Ok(message) = self.rx.recv_timeout(time::Duration::from_millis(1)) {
+                self.handle_message(message);
+            }
             return true;
         }
 
-        if (self.paused && self.step) || !self.paused {
+        if self.step {
             self.step = false;
-            let _ = self.run_instruction();
+            self.run_instruction();
+            self.publish_metrics(1);
+            self.flush_output();
+            return true;
+        }
+
+        let start = time::Instant::now();
+        let slept = self.slept;
+        let mut count = 0;
+        while count < MESSAGE_INTERVAL {
+            self.run_instruction();
+            count += 1;
+            if self.paused {
+                break;
+            }
+            if self.fast_forward {
+                continue;
+            }
             if self.speed > 0 {
                 thread::sleep(time::Duration::from_millis(self.speed));
+                self.slept += time::Duration::from_millis(self.speed);
             }
         }
+        self.frame_busy += start.elapsed().saturating_sub(self.slept - slept);
+        self.publish_metrics(count as u64);
+        self.flush_output();
 
         true
     }
 
+    /// Handles messages until the first one that resets the computer, so cards
+    /// and options sent right after it was built are there from the first instruction
+    pub fn wait_for_boot(&mut self) {
+        while let Ok(message) = self.rx.recv() {
+            let boots = matches!(message, ControllerMessage::Reset | ControllerMessage::Restore(_) | ControllerMessage::Load { .. });
+            self.handle_message(message);
+            if boots {
+                return;
+            }
+        }
+    }
+
+    /// Runs a single instruction, for harnesses driving the computer without its thread
+    pub fn run_one(&mut self) {
+        self.run_instruction();
+    }
+
+    /// Runs at full speed instead of keeping to the clock speed
+    pub fn set_fast_forward(&mut self, fast_forward: bool) {
+        self.fast_forward = fast_forward;
+        // Do not try to make up for the time spent running at full speed
+        self.reset_throttle();
+    }
+
+    /// Queue the serial card reads from, replacing the one it was built with
+    pub fn set_serial_input(&mut self, input: Arc<RingBuffer>) {
+        self.serial_rx = input;
+    }
+
+    pub fn serial_input(&self) -> &RingBuffer {
+        &self.serial_rx
+    }
+
+    pub fn set_core(&mut self, core: Core) {
+        self.core = core;
+    }
+
+    pub fn processor(&self) -> &Processor {
+        &self.processor
+    }
+
+    /// Where the beam of the emulated display is, for cards that draw or interrupt on a line
+    pub fn raster_position(&self) -> Position {
+        self.raster.position(self.processor.clock)
+    }
+
+    pub fn memory(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Serial output not sent to the UI yet
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+
+    /// Stopped by a fault, a guest breakpoint or the UI
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Sends the output of the last batch in a single message
+    fn flush_output(&mut self) {
+        if self.output.is_empty() {
+            return;
+        }
+        self.metrics.output_backlog.fetch_add(self.output.len() as u64, Ordering::Relaxed);
+        let _ = self.tx.send(ComputerMessage::Output(std::mem::take(&mut self.output)));
+    }
+
+    fn publish_metrics(&self, instructions: u64) {
+        self.metrics.cycles.store(self.processor.clock as u64, Ordering::Relaxed);
+        self.metrics.instructions.fetch_add(instructions, Ordering::Relaxed);
+    }
+
+    fn handle_message(&mut self, message: ControllerMessage) {
+        // Handle messages arriving from the controller.
+        match message {
+            ControllerMessage::Request(id, request) => {
+                let reply = self.answer(request);
+                let _ = self.tx.send(ComputerMessage::Reply(id, reply));
+            }
+            ControllerMessage::Reset => {
+                self.reset();
+            }
+            ControllerMessage::SetTerminalSize { columns, rows } => {
+                self.terminal_size = Some((columns, rows));
+                self.show_terminal_size();
+            }
+            ControllerMessage::SetDebug(c) => {
+                self.log_level = c;
+            }
+            ControllerMessage::TogglePause => {
+                self.paused = !self.paused;
+            }
+            ControllerMessage::Load { rom, disk } => {
+                self.data.fill(0);
+                let base = rom.base as usize;
+                self.data[base..base + rom.data.len()].copy_from_slice(&rom.data);
+                self.disk = disk;
+                self.dirty_sectors.clear();
+                self.written.fill(false);
+                self.executed.fill(false);
+                self.rom = rom;
+                self.check_map();
+                self.reset();
+            }
+            ControllerMessage::SetCrashDir(dir) => {
+                self.crash_dir = dir;
+            }
+            ControllerMessage::SetStrictMap(strict) => {
+                self.strict_map = strict;
+            }
+            ControllerMessage::SetInvalidOpcode(policy) => {
+                self.invalid_opcode = policy;
+            }
+            ControllerMessage::AdvanceRtc(by) => {
+                self.rtc.advance(by);
+            }
+            ControllerMessage::Restore(snapshot) => self.restore(*snapshot),
+            ControllerMessage::EnableCard { slot, enabled } => {
+                if let Some(card) = self.cards.iter_mut().find(|c| c.slot == slot) {
+                    card.enabled = enabled;
+                }
+                self.check_map();
+                self.update_irq_line();
+            }
+            ControllerMessage::InsertCard { slot, card_type } => {
+                self.cards.retain(|c| c.slot != slot);
+                self.cards.push(Card { slot, card_type, enabled: true });
+                self.cards.sort_by_key(|c| c.slot);
+                self.check_map();
+                self.update_irq_line();
+            }
+            ControllerMessage::SetOverflow => self.set_overflow(),
+            ControllerMessage::SetIrqOrder(order) => {
+                self.irq_order = order;
+                self.update_irq_line();
+            }
+            ControllerMessage::InsertDevice(device) => {
+                let slot = device.slot;
+                if let Some(line) = self.devices.iter().find(|d| d.slot == slot).and_then(|d| d.scheduled_line) {
+                    self.scheduler.cancel(Event::Scanline { slot, line });
+                }
+                self.cards.retain(|c| c.slot != slot);
+                self.cards.push(Card { slot, card_type: CardType::Device, enabled: true });
+                self.cards.sort_by_key(|c| c.slot);
+                self.devices.retain(|d| d.slot != slot);
+                self.devices.push(*device);
+                self.schedule_tick(slot);
+                self.check_map();
+                self.update_irq_line();
+            }
+            ControllerMessage::SetBreakpoints(addresses) => {
+                self.breakpoints = addresses.into_iter().collect();
+            }
+            ControllerMessage::SetWatchpoints(addresses) => {
+                self.watchpoints = addresses.into_iter().collect();
+            }
+            ControllerMessage::SetFastForward(f) => self.set_fast_forward(f),
+            _ => {},
+        };
+    }
+
+    fn answer(&mut self, request: Request) -> Reply {
+        match request {
+            Request::ReadRange { start, len } => {
+                let start_index = start as usize;
+                let end = start_index.saturating_add(len).min(self.data.len());
+                Reply::Range { start, data: self.data[start_index..end].to_vec() }
+            }
+            Request::WriteByte { addr, value } => {
+                self.data[addr as usize] = value;
+                // The byte may be a VIA register
+                self.update_irq_line();
+                Reply::Written { addr }
+            }
+            Request::ReadRegisters => Reply::Registers(self.processor.clone()),
+            Request::Usage => Reply::Usage(UsageReport::new(&self.written, &self.executed, self.rom.range())),
+            Request::Snapshot => Reply::Snapshot(Box::new(self.snapshot())),
+            Request::Cards => Reply::Cards(self.cards.clone()),
+            Request::Map => Reply::Map(MemoryMap::new(&self.cards, &self.devices, &self.rom)),
+            Request::Eval { source, mut vars } => {
+                let p = &self.processor;
+                let registers = [("A", p.acc), ("X", p.rx), ("Y", p.ry), ("SP", p.sp), ("P", p.flags)];
+                for (name, value) in registers {
+                    vars.insert(name.to_string(), value as i64);
+                }
+                vars.insert(String::from("PC"), p.pc as i64);
+                let result = script::eval(&source, &vars, &self.data);
+                Reply::Eval { source, result }
+            }
+            Request::Search(pattern) => {
+                let addresses = self.search(&pattern, false);
+                Reply::Matches { pattern, addresses }
+            }
+            Request::SearchDisk(pattern) => {
+                let offsets = self.search(&pattern, true);
+                Reply::DiskMatches { pattern, offsets }
+            }
+        }
+    }
+
+    fn search(&mut self, pattern: &[u8], disk: bool) -> Vec<usize> {
+        if self.finder.as_ref().is_none_or(|f| f.needle() != pattern) {
+            self.finder = Some(memmem::Finder::new(pattern).into_owned());
+        }
+        let haystack = if disk { &self.disk } else { &self.data };
+        self.finder.as_ref().map_or(vec![], |f| search::find_all(f, haystack))
+    }
+
     fn read(&mut self, addr: u16) -> u8 {
-        if addr >= IO_BASE && addr <= IO_TOP {
+        if (IO_BASE..=IO_TOP).contains(&addr) {
             // Get card type at this address
             let slot = ((addr & 0xF0) >> 4) - 8;
             if self.log_level > 2 {
@@ -234,12 +760,19 @@ impl Computer {
                 if self.log_level > 2 {
                     let _ = self.tx.send(ComputerMessage::Info(format!("card type {:?}", card.card_type)));
                 }
-                if card.card_type == CardType::CF && self.disk.len() > 0 {
+                if !card.enabled {
+                    return OPEN_BUS;
+                }
+                if card.card_type == CardType::CF && !self.disk.is_empty() {
                     let reg = addr & 7;
                     // let _ = self.tx.send(ComputerMessage::Info(format!("disk read reg {:?}", reg)));
                     if reg == 0 {
                         if self.command == DiskCommand::Read {
-                            let v = self.disk[(self.lba * 512 + self.disk_cnt as u32) as usize];
+                            let pos = (self.lba * 512 + self.disk_cnt as u32) as usize;
+                            let Some(&v) = self.disk.get(pos) else {
+                                self.fault(format!("CF card read beyond the end of the disk at LBA {}", self.lba));
+                                return 0;
+                            };
                             //let _ = self.tx.send(ComputerMessage::Info(format!("read disk {:?} {:?} {:?}, {:#x}", self.lba, self.disk_cnt, (self.lba * 512 + self.disk_cnt as u32), v)));
         
                             self.disk_cnt += 1;
@@ -257,31 +790,79 @@ impl Computer {
                     }
                 } else if card.card_type == CardType::Serial {
                     let reg = addr & 7;
+                    let a = IO_BASE + card.slot * 0x10;
                     if reg == 0 {
-                        let a = IO_BASE + card.slot * 0x10;
-                        self.data[a as usize +1] = 0;
+                        self.data[(a + SERIAL_STATUS) as usize] &= !SERIAL_RX_FULL;
                         let v = self.data[a as usize];
                         self.data[a as usize] = 0;
+                        self.receive_serial();
                         return v;
+                    } else if reg == SERIAL_COLUMNS || reg == SERIAL_ROWS {
+                        self.data[(a + SERIAL_STATUS) as usize] &= !SERIAL_RESIZED;
                     }
-                    
+                } else if card.card_type == CardType::Rtc {
+                    return self.rtc.read(addr & 0xF, self.seconds());
+                } else if card.card_type == CardType::Irq {
+                    let slot = self.irq_line;
+                    return match addr & 0xF {
+                        IRQ_ACK => slot.map_or(IRQ_NONE, |s| s as u8),
+                        IRQ_ACK_VECTOR => slot.map_or(IRQ_NONE, |s| s as u8 * 2),
+                        _ => OPEN_BUS,
+                    };
+                } else if card.card_type == CardType::Device {
+                    if let Some(v) = self.device_read(card.slot, addr & 0xF) {
+                        return v;
+                    }
+                } else if card.card_type == CardType::IO && addr & 0xF == VIA_T1CL {
+                    // Reading the low counter byte acknowledges the timer
+                    let ifr = (IO_BASE + card.slot * 0x10 + VIA_IFR) as usize;
+                    self.data[ifr] &= !VIA_IFR_T1;
+                    self.update_via_ifr(ifr);
                 }
             }
         }
 
-        return self.data[addr as usize];
+        self.data[addr as usize]
     }
 
     fn write(&mut self, addr: u16, value: u8) {
-        if addr >= IO_BASE && addr <= IO_TOP {
+        self.written[addr as usize] = true;
+        if self.watchpoints.contains(&addr) {
+            self.stop(format!("Wrote ${:02X} to ${:04X}", value, addr));
+        }
+        if (IO_BASE..=IO_TOP).contains(&addr) {
             let slot = ((addr & 0xF0) >> 4) - 8;
             if let Some(card) = self.cards.iter().find(|a| a.slot == slot) {
-                if card.card_type == CardType::CF && self.disk.len() > 0 {
+                if !card.enabled {
+                    return;
+                }
+                if self.strict_map {
+                    let fault = self.conflicts.iter().find_map(|c| match c {
+                        Conflict::RomShadowed { slot: s, card_type } if *s == slot => {
+                            Some(format!("Write to ${:04X} hits both the {:?} card in slot {} and the ROM", addr, card_type, slot))
+                        }
+                        Conflict::Slot { slot: s, cards: [a, b] } if *s == slot => {
+                            Some(format!("Write to ${:04X} hits both the {:?} and {:?} cards in slot {}", addr, a, b, slot))
+                        }
+                        _ => None,
+                    });
+                    if let Some(msg) = fault {
+                        self.fault(msg);
+                        return;
+                    }
+                }
+                if card.card_type == CardType::CF && !self.disk.is_empty() {
                     let reg = addr & 7;
 
                     if reg == 0 {
                         if self.command == DiskCommand::Write {
-                            self.disk[(self.lba * 512 + self.disk_cnt as u32) as usize] = value;
+                            let pos = (self.lba * 512 + self.disk_cnt as u32) as usize;
+                            let Some(v) = self.disk.get_mut(pos) else {
+                                self.fault(format!("CF card write beyond the end of the disk at LBA {}", self.lba));
+                                return;
+                            };
+                            *v = value;
+                            self.dirty_sectors.insert(self.lba);
                             self.disk_cnt += 1;
                             if self.disk_cnt > 512 {
                                 self.command = DiskCommand::None;
@@ -314,7 +895,50 @@ impl Computer {
                 } else if card.card_type == CardType::Serial {
                     let reg = addr & 7;
                     if reg == 0 {
-                        let _ = self.tx.send(ComputerMessage::Output(value));
+                        // Transmitted bytes do not replace the received one
+                        self.output.push(value);
+                        return;
+                    }
+                } else if card.card_type == CardType::Rtc {
+                    let seconds = self.seconds();
+                    self.rtc.write(addr & 0xF, value, seconds);
+                    return;
+                } else if card.card_type == CardType::Device {
+                    // Also kept in memory, for devices without a read handler
+                    self.device_write(card.slot, addr & 0xF, value);
+                } else if card.card_type == CardType::Trap && matches!(addr & 0xF, TRAP_MESSAGE_HI | TRAP_CODE) {
+                    let lo = self.data[(addr & 0xFFF0 | TRAP_MESSAGE_LO) as usize];
+                    self.trap(addr & 0xF, lo, value);
+                    return;
+                } else if card.card_type == CardType::IO {
+                    let slot = card.slot;
+                    let base = IO_BASE + slot * 0x10;
+                    match addr & 0xF {
+                        VIA_T1CH => {
+                            // Writing the high byte loads the counter from the latches and starts timer 1
+                            let latch = u16::from_le_bytes([self.data[(base + VIA_T1CL) as usize], value]);
+                            self.data[(base + VIA_IFR) as usize] &= !VIA_IFR_T1;
+                            self.update_via_ifr((base + VIA_IFR) as usize);
+                            self.scheduler.cancel(Event::Card { slot, timer: 1 });
+                            self.schedule(self.processor.clock + latch.max(1) as u128, Event::Card { slot, timer: 1 });
+                        }
+                        VIA_IFR => {
+                            // Writing ones clears the flags
+                            self.data[addr as usize] &= !value;
+                            self.update_via_ifr(addr as usize);
+                            return;
+                        }
+                        VIA_IER => {
+                            let ier = &mut self.data[addr as usize];
+                            if value & VIA_IER_SET != 0 {
+                                *ier |= value & !VIA_IER_SET;
+                            } else {
+                                *ier &= !value;
+                            }
+                            self.update_irq_line();
+                            return;
+                        }
+                        _ => {}
                     }
                 }
             }
@@ -324,25 +948,465 @@ impl Computer {
         
     }
 
+    /// Shows the size of the output pane in the serial card registers, flagged as changed
+    fn show_terminal_size(&mut self) {
+        let Some((columns, rows)) = self.terminal_size else {
+            return;
+        };
+        if let Some(serial) = self.cards.iter().find(|a| a.card_type == CardType::Serial && a.enabled) {
+            let base = (IO_BASE + serial.slot * 0x10) as usize;
+            self.data[base + SERIAL_COLUMNS as usize] = columns;
+            self.data[base + SERIAL_ROWS as usize] = rows;
+            self.data[base + SERIAL_STATUS as usize] |= SERIAL_RESIZED;
+        }
+    }
+
+    /// Moves the next queued input byte to the serial card once the last one was read
+    fn receive_serial(&mut self) {
+        let Some(serial) = self.cards.iter().find(|a| a.card_type == CardType::Serial && a.enabled) else {
+            return;
+        };
+        let addr = (IO_BASE + serial.slot * 0x10) as usize;
+        if self.data[addr + SERIAL_STATUS as usize] & SERIAL_RX_FULL != 0 {
+            return;
+        }
+        if let Some(b) = self.serial_rx.pop() {
+            if self.log_level > 2 {
+                let _ = self.tx.send(ComputerMessage::Info(format!("serial out {:#x}", addr)));
+            }
+            self.data[addr] = b;
+            self.data[addr + SERIAL_STATUS as usize] |= SERIAL_RX_FULL;
+        }
+    }
+
+    /// Pauses on a breakpoint written to the trap card by the guest
+    fn trap(&mut self, reg: u16, lo: u8, value: u8) {
+        let message = if reg == TRAP_MESSAGE_HI {
+            let start = u16::from_le_bytes([lo, value]) as usize;
+            self.data[start..].iter().chain(&self.data[..start])
+                .take(TRAP_MESSAGE_LEN)
+                .take_while(|&&b| b != 0)
+                .map(|&b| b as char)
+                .collect()
+        } else {
+            format!("Code ${:02X}", value)
+        };
+        self.stop(message);
+    }
+
+    /// Pauses after the instruction being run, showing `message` in the UI
+    fn stop(&mut self, message: String) {
+        // The trace holds the instruction being run, the PC has moved past it
+        let pc = self.trace.back().map_or(self.processor.pc, |t| t.pc);
+        self.paused = true;
+        let _ = self.tx.send(ComputerMessage::Breakpoint { pc, message });
+    }
+
+    /// Schedules `event` at cycle `at`. Cards use [`Event::Card`] for their timers.
+    fn schedule(&mut self, at: u128, event: Event) {
+        self.scheduler.schedule(at, event);
+        self.next_event = self.next_event.min(at);
+    }
+
+    fn run_events(&mut self) {
+        while let Some((at, event)) = self.scheduler.pop_due(self.processor.clock) {
+            match event {
+                Event::Throttle => {
+                    if !self.fast_forward {
+                        self.throttle();
+                    }
+                    self.schedule(self.processor.clock + THROTTLE_CYCLES, Event::Throttle);
+                }
+                Event::Card { slot, timer } => self.card_timer(slot, timer, at),
+                Event::Frame => self.frame(),
+                Event::Scanline { slot, line } => self.scanline(slot, line),
+            }
+        }
+        self.next_event = self.scheduler.next().unwrap_or(u128::MAX);
+    }
+
+    /// Called when a timer scheduled by a card expires, `at` being the cycle it was due
+    fn card_timer(&mut self, slot: u16, timer: u8, at: u128) {
+        if self.log_level > 2 {
+            let _ = self.tx.send(ComputerMessage::Info(format!("timer {} of card in slot {} expired", timer, slot)));
+        }
+        if timer == DEVICE_TICK && self.cards.iter().any(|c| c.slot == slot && c.card_type == CardType::Device) {
+            self.device_tick(slot);
+            return;
+        }
+        if !self.cards.iter().any(|c| c.slot == slot && c.card_type == CardType::IO && c.enabled) {
+            return;
+        }
+
+        // VIA timer 1, interrupting when enabled in the IER
+        let base = (IO_BASE + slot * 0x10) as usize;
+        self.data[base + VIA_IFR as usize] |= VIA_IFR_T1;
+        self.update_via_ifr(base + VIA_IFR as usize);
+        if self.data[base + VIA_ACR as usize] & VIA_ACR_FREE_RUN != 0 {
+            // From when the timer expired rather than the end of the instruction, so
+            // the periods do not stretch
+            let latch = u16::from_le_bytes([self.data[base + VIA_T1CL as usize], self.data[base + VIA_T1CH as usize]]);
+            self.schedule(at + latch.max(1) as u128, Event::Card { slot, timer });
+        }
+    }
+
+    /// Sets bit 7 of a VIA interrupt flag register when any other flag is set, clears it otherwise
+    fn update_via_ifr(&mut self, ifr: usize) {
+        if self.data[ifr] & !VIA_IFR_ANY == 0 {
+            self.data[ifr] = 0;
+        } else {
+            self.data[ifr] |= VIA_IFR_ANY;
+        }
+        self.update_irq_line();
+    }
+
+    fn update_irq_line(&mut self) {
+        self.irq_line = self.irq_slot();
+    }
+
+    fn schedule_tick(&mut self, slot: u16) {
+        let Some(device) = self.devices.iter().find(|d| d.slot == slot && d.ticks()) else {
+            return;
+        };
+        let at = self.processor.clock + device.tick_cycles as u128;
+        self.scheduler.cancel(Event::Card { slot, timer: DEVICE_TICK });
+        self.schedule(at, Event::Card { slot, timer: DEVICE_TICK });
+    }
+
+    /// Runs the tick handler of a scripted device, then schedules the next tick.
+    /// Ticks keep coming while the card is disabled, without running the handler.
+    fn device_tick(&mut self, slot: u16) {
+        self.schedule_tick(slot);
+        if !self.cards.iter().any(|c| c.slot == slot && c.enabled) {
+            return;
+        }
+        self.run_device(slot, "Tick", |d| d.tick());
+    }
+
+    fn device_read(&mut self, slot: u16, reg: u16) -> Option<u8> {
+        self.run_device(slot, "Read", |d| d.read(reg)).flatten()
+    }
+
+    fn device_write(&mut self, slot: u16, reg: u16, value: u8) {
+        self.run_device(slot, "Write", |d| d.write(reg, value));
+    }
+
+    /// Runs a handler of the scripted device in `slot`, faulting when it fails. The
+    /// handler may have moved the raster line of the device, which is rescheduled.
+    fn run_device<T>(&mut self, slot: u16, handler: &str, run: impl FnOnce(&mut Device) -> Result<T, String>) -> Option<T> {
+        let device = self.devices.iter_mut().find(|d| d.slot == slot)?;
+        match run(device) {
+            Ok(v) => {
+                if device.so_edge() {
+                    self.so_pending = true;
+                }
+                self.schedule_raster(slot);
+                self.update_irq_line();
+                Some(v)
+            }
+            Err(e) => {
+                let msg = format!("{} handler of device {} failed: {}", handler, device.name, e);
+                self.fault(msg);
+                None
+            }
+        }
+    }
+
+    /// Schedules the scanline handler of a device on the line it asks for, if it changed.
+    fn schedule_raster(&mut self, slot: u16) {
+        let Some(device) = self.devices.iter_mut().find(|d| d.slot == slot) else {
+            return;
+        };
+        let line = device.raster();
+        if line == device.scheduled_line {
+            return;
+        }
+        let previous = std::mem::replace(&mut device.scheduled_line, line);
+        if let Some(previous) = previous {
+            self.scheduler.cancel(Event::Scanline { slot, line: previous });
+        }
+        if let Some(line) = line {
+            let at = self.raster.next_line(self.processor.clock, line);
+            self.schedule(at, Event::Scanline { slot, line });
+        }
+    }
+
+    /// Publishes the frame counters and runs the frame handlers of the scripted devices
+    fn frame(&mut self) {
+        let number = self.metrics.frames.fetch_add(1, Ordering::Relaxed) + 1;
+        self.metrics.frame_busy_us.store(self.frame_busy.as_micros() as u64, Ordering::Relaxed);
+        self.frame_busy = time::Duration::ZERO;
+        let at = self.raster.next_frame(self.processor.clock);
+        self.schedule(at, Event::Frame);
+        let slots: Vec<u16> = self.devices.iter().map(|d| d.slot).collect();
+        for slot in slots {
+            if self.cards.iter().any(|c| c.slot == slot && c.enabled) {
+                self.run_device(slot, "Frame", |d| d.frame(number));
+            }
+        }
+    }
+
+    /// Runs the scanline handler of the device that asked for `line`, which comes
+    /// again on the next frame unless the handler moves it.
+    fn scanline(&mut self, slot: u16, line: u16) {
+        let Some(device) = self.devices.iter_mut().find(|d| d.slot == slot) else {
+            return;
+        };
+        device.scheduled_line = None;
+        if self.cards.iter().any(|c| c.slot == slot && c.enabled) {
+            self.run_device(slot, "Scanline", |d| d.scanline(line));
+        } else {
+            self.schedule_raster(slot);
+        }
+    }
+
+    /// Sleep if the emulated clock is running ahead of real time
+    fn throttle(&mut self) {
+        let cycles = self.processor.clock.wrapping_sub(self.throttle_clock);
+        if cycles < THROTTLE_CYCLES {
+            return;
+        }
+
+        let expected = time::Duration::from_nanos((cycles * 1_000_000_000 / CLOCK_SPEED) as u64);
+        let elapsed = self.throttle_time.elapsed();
+        if expected > elapsed {
+            thread::sleep(expected - elapsed);
+            self.slept += expected - elapsed;
+        }
+        self.reset_throttle();
+    }
+
+    fn reset_throttle(&mut self) {
+        self.throttle_time = time::Instant::now();
+        self.throttle_clock = self.processor.clock;
+    }
+
+
+    /// Reports address conflicts between the cards and the ROM in the debug pane
+    fn check_map(&mut self) {
+        self.conflicts = map::check(&self.cards, &self.rom);
+        for conflict in &self.conflicts {
+            let _ = self.tx.send(ComputerMessage::Info(format!("Address conflict: {}", conflict)));
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let (rtc_offset, rtc_registers) = self.rtc.save(self.seconds(), chrono::Local::now().naive_local());
+        let timers = self.scheduler.events().into_iter().filter_map(|(at, event)| match event {
+            Event::Card { slot, timer } => Some((at, slot, timer)),
+            Event::Throttle | Event::Frame | Event::Scanline { .. } => None,
+        }).collect();
+        let devices = self.devices.iter().filter_map(|device| match device.save() {
+            Ok(variables) => Some((device.slot, variables)),
+            Err(e) => {
+                let _ = self.tx.send(ComputerMessage::Info(format!("Variables of device {} not saved: {}", device.name, e)));
+                None
+            }
+        }).collect();
+        Snapshot {
+            rom_hash: 0,
+            disk_hash: 0,
+            processor: self.processor.clone(),
+            memory: self.data.clone(),
+            disk: self.disk.clone(),
+            lba: self.lba,
+            disk_cnt: self.disk_cnt,
+            command: self.command as u8,
+            cards: self.cards.clone(),
+            timers,
+            rtc_offset,
+            rtc_registers,
+            devices,
+            output: vec![],
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.processor = snapshot.processor;
+        self.data = snapshot.memory;
+        self.disk = snapshot.disk;
+        self.lba = snapshot.lba;
+        self.disk_cnt = snapshot.disk_cnt;
+        self.command = snapshot.command.try_into().unwrap_or(DiskCommand::None);
+        self.cards = snapshot.cards;
+        self.check_map();
+        self.rtc.restore(snapshot.rtc_offset, snapshot.rtc_registers, self.seconds(), chrono::Local::now().naive_local());
+        self.trace.clear();
+        self.dirty_sectors.clear();
+
+        self.scheduler.clear();
+        self.next_event = u128::MAX;
+        self.schedule(self.processor.clock + THROTTLE_CYCLES, Event::Throttle);
+        self.schedule(self.raster.next_frame(self.processor.clock), Event::Frame);
+        for (at, slot, timer) in snapshot.timers {
+            self.schedule(at, Event::Card { slot, timer });
+        }
+        for device in self.devices.iter_mut() {
+            let failure = match snapshot.devices.iter().find(|(slot, _)| *slot == device.slot) {
+                Some((_, variables)) => device.restore(variables).err().map(|e| format!("its variables could not be restored: {}", e)),
+                None => Some(String::from("the snapshot does not have its variables")),
+            };
+            if let Some(why) = failure {
+                device.reset();
+                let _ = self.tx.send(ComputerMessage::Info(format!("Device {} starts over, {}", device.name, why)));
+            }
+        }
+        for slot in self.devices.iter().map(|d| d.slot).collect::<Vec<_>>() {
+            self.schedule_raster(slot);
+        }
+        self.reset_throttle();
+        self.update_irq_line();
+        self.paused = false;
+    }
+
+    /// Whole seconds of emulated time since reset
+    fn seconds(&self) -> u64 {
+        (self.processor.clock / CLOCK_SPEED) as u64
+    }
 
     pub fn reset(&mut self) {
         self.paused = true;
         self.lba = 0;
+        self.rtc.reset(self.seconds());
         self.processor.clock = 0;
         self.disk_cnt = 0;
         self.command = DiskCommand::None;
+        for card in self.cards.iter().filter(|c| c.card_type == CardType::IO || c.card_type == CardType::Serial) {
+            // The VIA comes out of reset with its timers stopped and all registers cleared,
+            // the serial card with nothing received
+            let base = (IO_BASE + card.slot * 0x10) as usize;
+            self.data[base..base + 0x10].fill(0);
+        }
+        self.show_terminal_size();
+        self.receive_serial();
         self.processor.pc = self.get_word(0xfffc);
+        self.reset_throttle();
+        self.scheduler.clear();
+        self.next_event = u128::MAX;
+        self.schedule(THROTTLE_CYCLES, Event::Throttle);
+        self.schedule(self.raster.cycles_per_frame, Event::Frame);
+        for device in self.devices.iter_mut() {
+            device.reset();
+        }
+        for slot in self.devices.iter().map(|d| d.slot).collect::<Vec<_>>() {
+            self.schedule_tick(slot);
+        }
+        self.update_irq_line();
         self.paused = false;
     }
 
     fn run_instruction(&mut self) {
+        let pc = self.processor.pc;
+        if self.breakpoints.contains(&pc) && self.stopped_at.take() != Some(pc) {
+            self.stopped_at = Some(pc);
+            self.paused = true;
+            let _ = self.tx.send(ComputerMessage::Breakpoint { pc, message: String::from("Breakpoint") });
+            return;
+        }
+        self.stopped_at = None;
+        self.record_trace();
         let inst = self.read(self.processor.pc);
+        if self.invalid_opcode == InvalidOpcode::Halt && opcodes::OPCODES[inst as usize].mnemonic.is_empty() {
+            self.fault(format!("Invalid opcode ${:02X} at ${:04X}", inst, self.processor.pc));
+            return;
+        }
         self.processor.inst = inst;
         let opcode = decode::get_opcode_name(self.processor.inst);
+        self.processor.clock = self.processor.clock.wrapping_add(opcodes::OPCODES[inst as usize].cycles as u128);
+        for offset in 0..opcodes::OPCODES[inst as usize].bytes as u16 {
+            self.executed[self.processor.pc.wrapping_add(offset) as usize] = true;
+        }
 
         //self.add_info(format!("{:#x} - running instruction {} ({:#x})", self.processor.pc, opcode, inst));
 
-        match opcode {
+        let registers = self.registers();
+        match self.core {
+            Core::Legacy => self.execute(opcode),
+            Core::Table => table::execute(self, inst),
+        }
+
+        // Only a reset gets out of an instruction jumping to itself with interrupts masked,
+        // BVC and BVS wait for SO and WAI for the IRQ line whatever the I flag
+        if self.processor.flags & FLAG_I != 0 && !self.paused && self.registers() == registers
+            && !matches!(opcode, "BVC" | "BVS" | "WAI") {
+            self.fault(format!("Trap loop at ${:04X} with interrupts disabled", self.processor.pc));
+            return;
+        }
+
+        if self.processor.clock >= self.next_event {
+            self.run_events();
+        }
+
+        // Left to now so the instruction that pulled SO does not overwrite the flag
+        if std::mem::take(&mut self.so_pending) {
+            self.set_overflow();
+        }
+
+        if self.processor.flags & FLAG_I == 0 && self.irq_line.is_some() {
+            self.irq();
+        }
+    }
+
+    /// Program counter and registers, without the clock
+    fn registers(&self) -> (u16, u8, u8, u8, u8, u8) {
+        let p = &self.processor;
+        (p.pc, p.acc, p.rx, p.ry, p.sp, p.flags)
+    }
+
+    /// Whether the card asserts the shared IRQ line
+    fn asserts_irq(&self, card: &Card) -> bool {
+        if !card.enabled {
+            return false;
+        }
+        if card.card_type == CardType::Device {
+            return self.devices.iter().any(|d| d.slot == card.slot && d.irq());
+        }
+        if card.card_type != CardType::IO {
+            return false;
+        }
+        let base = (IO_BASE + card.slot * 0x10) as usize;
+        self.data[base + VIA_IFR as usize] & self.data[base + VIA_IER as usize] & !VIA_IER_SET != 0
+    }
+
+    /// Highest priority slot asserting IRQ. The slots given to [`ControllerMessage::SetIrqOrder`]
+    /// come first in the daisy chain, then the others from the lowest slot up.
+    fn irq_slot(&self) -> Option<u16> {
+        let first = self.irq_order.iter()
+            .filter_map(|&slot| self.cards.iter().find(|c| c.slot == slot))
+            .find(|c| self.asserts_irq(c));
+        first.or_else(|| self.cards.iter()
+            .filter(|c| !self.irq_order.contains(&c.slot))
+            .find(|c| self.asserts_irq(c)))
+            .map(|c| c.slot)
+    }
+
+    /// Takes an interrupt request, like BRK but returning to the next instruction and without the B flag
+    fn irq(&mut self) {
+        let sp: u16 = self.processor.sp as u16 + 0x100;
+        let sp1: u16 = self.processor.sp.wrapping_sub(1) as u16 + 0x100;
+        let sp2: u16 = self.processor.sp.wrapping_sub(2) as u16 + 0x100;
+
+        let pc = self.processor.pc;
+        self.write(sp, (pc >> 8) as u8);
+        self.write(sp1, (pc & 0xff) as u8);
+        self.write(sp2, (self.processor.flags & !FLAG_B) | 0x20);
+
+        self.processor.flags |= FLAG_I;
+        self.processor.flags &= !FLAG_D;
+        self.processor.sp = self.processor.sp.wrapping_sub(3);
+        self.processor.clock = self.processor.clock.wrapping_add(7);
+        self.metrics.irqs.fetch_add(1, Ordering::Relaxed);
+        self.processor.pc = self.get_word(0xfffe);
+    }
+
+    /// The SO pin going low sets the overflow flag, between two instructions
+    fn set_overflow(&mut self) {
+        self.processor.flags |= FLAG_O;
+    }
+
+    /// Runs an instruction by matching its mnemonic, the legacy core
+    fn execute(&mut self, mnemonic: &str) {
+        match mnemonic {
             "ADC" => self.adc(),
             "AND" => self.and(),
             "ASL" => self.asl(),
@@ -424,18 +1488,29 @@ impl Computer {
             "BBR7" => self.bbr(7),
 
             "STZ" => self.stz(),
+            "TSB" => self.tsb(),
+            "TRB" => self.trb(),
+            "WAI" => self.wai(),
+            "STP" => self.stp(),
+
+            "RMB0" => self.rmb(0),
+            "RMB1" => self.rmb(1),
+            "RMB2" => self.rmb(2),
+            "RMB3" => self.rmb(3),
+            "RMB4" => self.rmb(4),
+            "RMB5" => self.rmb(5),
+            "RMB6" => self.rmb(6),
+            "RMB7" => self.rmb(7),
+
+            "SMB0" => self.smb(0),
+            "SMB1" => self.smb(1),
+            "SMB2" => self.smb(2),
+            "SMB3" => self.smb(3),
+            "SMB4" => self.smb(4),
+            "SMB5" => self.smb(5),
+            "SMB6" => self.smb(6),
+            "SMB7" => self.smb(7),
 
-            "NOP2" => {
-                self.nop();
-                self.nop()
-            },
-            "NOP3" => {
-                self.nop();
-                self.nop();
-                self.nop();
-
-            }
-            
             _ => {
                 //panic!("Running instruction nop : {:x?}", inst);
                 self.nop();
@@ -443,6 +1518,53 @@ impl Computer {
         };
     }
 
+    fn record_trace(&mut self) {
+        if self.trace.len() == TRACE_LEN {
+            self.trace.pop_front();
+        }
+        let p = &self.processor;
+        let byte = |offset: u16| self.data[p.pc.wrapping_add(offset) as usize];
+        self.trace.push_back(TraceEntry {
+            clock: p.clock,
+            pc: p.pc,
+            bytes: [byte(0), byte(1), byte(2)],
+            acc: p.acc,
+            rx: p.rx,
+            ry: p.ry,
+            sp: p.sp,
+            flags: p.flags,
+        });
+    }
+
+    /// Pauses the computer and reports the error to the UI, writing a crash dump if enabled
+    pub fn fault(&mut self, mut msg: String) {
+        self.paused = true;
+        self.flush_output();
+        if let Some(dir) = &self.crash_dir {
+            match self.crash_dump(&msg).write(dir) {
+                Ok(path) => msg.push_str(&format!("\nCrash dump written to {}", path.display())),
+                Err(e) => msg.push_str(&format!("\nCould not write a crash dump: {}", e)),
+            }
+        }
+        let _ = self.tx.send(ComputerMessage::Fault(msg));
+    }
+
+    fn crash_dump(&self, reason: &str) -> CrashDump {
+        let dirty_sectors = self.dirty_sectors.iter().map(|&lba| {
+            let start = lba as usize * SECTOR_SIZE;
+            let sector = self.disk.get(start..start + SECTOR_SIZE).unwrap_or_default();
+            (lba, sector.to_vec())
+        }).collect();
+        CrashDump {
+            reason: reason.to_string(),
+            processor: self.processor.clone(),
+            trace: self.trace.iter().copied().collect(),
+            zero_page: self.data[..0x100].to_vec(),
+            stack: self.data[0x100..0x200].to_vec(),
+            dirty_sectors,
+        }
+    }
+
     fn add_info(&mut self, info: String) {
         let _ = self.tx.send(ComputerMessage::Info(info.clone()));
         let len = self.info.len();
@@ -462,8 +1584,7 @@ impl Computer {
             self.add_info(format!("{:#x} - Running instruction cld: {:#x}", self.processor.pc, self.data[(self.processor.pc) as usize]));
         }
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.flags = self.processor.flags & !FLAG_D;
-        self.processor.clock = self.processor.clock.wrapping_add(2);
+        self.processor.flags &= !FLAG_D;
     }
 
     fn txs(&mut self) {
@@ -471,7 +1592,6 @@ impl Computer {
             self.add_info(format!("{:#x} - Running instruction txs: {:#x}", self.processor.pc, self.data[(self.processor.pc) as usize]));
         }
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(2);
         self.processor.sp = self.processor.rx;
     }
 
@@ -481,7 +1601,6 @@ impl Computer {
             self.add_info(format!("{:#x} - Running instruction tsx: {:#x} val: {:#x} flags:{:#x} ", self.processor.pc, self.data[(self.processor.pc) as usize], self.processor.sp, self.processor.flags));
         }
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(2);
         self.processor.rx = self.processor.sp;
     }
 
@@ -490,7 +1609,6 @@ impl Computer {
             self.add_info(format!("{:#x} - Running instruction tya: {:#x}", self.processor.pc, self.data[(self.processor.pc) as usize]));
         }
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(2);
         self.processor.acc = self.processor.ry;
         self.processor.flags = Self::set_flags(self.processor.flags, self.processor.acc);
     }
@@ -500,7 +1618,6 @@ impl Computer {
             self.add_info(format!("{:#x} - Running instruction tay: {:#x}", self.processor.pc, self.data[(self.processor.pc) as usize]));
         }
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(2);
         self.processor.ry = self.processor.acc;
         self.processor.flags = Self::set_flags(self.processor.flags, self.processor.ry);
     }
@@ -510,7 +1627,6 @@ impl Computer {
             self.add_info(format!("{:#x} - Running instruction tax: {:#x}", self.processor.pc, self.data[(self.processor.pc) as usize]));
         }
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(2);
         self.processor.rx = self.processor.acc;
         self.processor.flags = Self::set_flags(self.processor.flags, self.processor.rx);
     }
@@ -521,15 +1637,14 @@ impl Computer {
         }
         self.processor.flags = Self::set_flags(self.processor.flags, self.processor.rx);
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(2);
         self.processor.acc = self.processor.rx;
     }
 
     /// Jump to subroutine
     fn jsr(&mut self) {
         // Place current address on stack
-        let sp: u16 = (self.processor.sp as u16 + 0x100 as u16).into();
-        let sp1: u16 = (self.processor.sp.wrapping_sub(1) as u16 + 0x100 as u16).into();
+        let sp: u16 = self.processor.sp as u16 + 0x100;
+        let sp1: u16 = self.processor.sp.wrapping_sub(1) as u16 + 0x100;
         
         let this_pc = self.processor.pc + 2;
 
@@ -541,14 +1656,13 @@ impl Computer {
             self.add_info(format!("{:#x} - Running instruction jsr to: {:#x}", self.processor.pc, addr));
         }
         self.processor.sp = self.processor.sp.wrapping_sub(2);
-        self.processor.clock  = self.processor.clock.wrapping_add(6);
         self.processor.pc = addr;
     }
 
     fn brk(&mut self) {
-        let sp: u16 = (self.processor.sp as u16 + 0x100 as u16).into();
-        let sp1: u16 = (self.processor.sp.wrapping_sub(1) as u16 + 0x100 as u16).into();
-        let sp2: u16 = (self.processor.sp.wrapping_sub(2) as u16 + 0x100 as u16).into();
+        let sp: u16 = self.processor.sp as u16 + 0x100;
+        let sp1: u16 = self.processor.sp.wrapping_sub(1) as u16 + 0x100;
+        let sp2: u16 = self.processor.sp.wrapping_sub(2) as u16 + 0x100;
 
         let this_pc = self.processor.pc + 2;
 
@@ -565,43 +1679,40 @@ impl Computer {
         }
         self.processor.pc = new_addr;
 
-        self.processor.clock  = self.processor.clock.wrapping_add(7);
     }
 
     fn rti(&mut self) {
         // Place current address on stack
-        let sp1: u16 = (self.processor.sp.wrapping_add(1) as u16 + 0x100 as u16).into();
-        let sp2: u16 = (self.processor.sp.wrapping_add(2) as u16 + 0x100 as u16).into();
-        let sp3: u16 = (self.processor.sp.wrapping_add(3) as u16 + 0x100 as u16).into();
+        let sp1: u16 = self.processor.sp.wrapping_add(1) as u16 + 0x100;
+        let sp2: u16 = self.processor.sp.wrapping_add(2) as u16 + 0x100;
+        let sp3: u16 = self.processor.sp.wrapping_add(3) as u16 + 0x100;
         let high_byte = self.read(sp3);
         let low_byte = self.read(sp2);
         let flags = self.read(sp1);
         // Unset interrupt disabled flag
         self.processor.flags = flags;
-        let addr: u16 = low_byte as u16 | ((high_byte as u16) << 8) as u16;
+        let addr: u16 = low_byte as u16 | ((high_byte as u16) << 8);
         // Send to new address
         if self.log_level > 0 {
             self.add_info(format!("{:#x} - Running instruction rti to: {:#x} flags: {:#x}", self.processor.pc, addr, self.processor.flags));
         }
         self.processor.sp = self.processor.sp.wrapping_add(3);
         self.processor.pc = addr;
-        self.processor.clock  = self.processor.clock.wrapping_add(7);
     }
 
     fn rts(&mut self) {
         // Place current address on stack
-        let sp1: u16 = (self.processor.sp.wrapping_add(1) as u16 + 0x100 as u16).into();
-        let sp2: u16 = (self.processor.sp.wrapping_add(2) as u16 + 0x100 as u16).into();
+        let sp1: u16 = self.processor.sp.wrapping_add(1) as u16 + 0x100;
+        let sp2: u16 = self.processor.sp.wrapping_add(2) as u16 + 0x100;
         let low_byte = self.read(sp1);
         let high_byte = self.read(sp2);
-        let addr: u16 = low_byte as u16 | ((high_byte as u16) << 8) as u16;
+        let addr: u16 = low_byte as u16 | ((high_byte as u16) << 8);
         // Send to new address
         if self.log_level > 0 {
             self.add_info(format!("{:#x} - Running instruction rts to: {:#x}", self.processor.pc, addr));
         }
         self.processor.sp = self.processor.sp.wrapping_add(2);
         self.processor.pc = addr.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(6);
     }
 
     /// Clear carry flag
@@ -611,7 +1722,6 @@ impl Computer {
             self.add_info(format!("{:#x} - Running instruction clc: {:#x}", self.processor.pc, self.data[(self.processor.pc) as usize]));
         }
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(2);
     }
 
     /// Set carry flag
@@ -621,7 +1731,6 @@ impl Computer {
             self.add_info(format!("{:#x} - Running instruction sec: {:#x}", self.processor.pc, self.data[(self.processor.pc) as usize]));
         }
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(2);
     }
 
     /// Set decimal flag
@@ -631,7 +1740,6 @@ impl Computer {
             self.add_info(format!("{:#x} - Running instruction sed: {:#x}", self.processor.pc, self.data[(self.processor.pc) as usize]));
         }
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(2);
     }
 
     /// Clear interrupt disabled flag
@@ -641,7 +1749,6 @@ impl Computer {
             self.add_info(format!("{:#x} - Running instruction cli: {:#x}", self.processor.pc, self.data[(self.processor.pc) as usize]));
         }
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(2);
     }
 
     /// Set interrupt disabled flag
@@ -651,7 +1758,6 @@ impl Computer {
             self.add_info(format!("{:#x} - Running instruction sei: {:#x}", self.processor.pc, self.data[(self.processor.pc) as usize]));
         }
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(2);
     }
 
     /// clear overflow flag
@@ -661,12 +1767,11 @@ impl Computer {
             self.add_info(format!("{:#x} - Running instruction clv: {:#x}", self.processor.pc, self.data[(self.processor.pc) as usize]));
         }
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(2);
     }
 
     /// Push accumulator to stack
     fn pha(&mut self) {
-        let addr: u16 = (self.processor.sp as u16 + 0x100 as u16).into();
+        let addr: u16 = self.processor.sp as u16 + 0x100;
         
         self.write(addr, self.processor.acc);
 
@@ -675,12 +1780,11 @@ impl Computer {
         }
         self.processor.sp = self.processor.sp.wrapping_sub(1);
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(3);
     }
 
     /// Push X to stack
     fn phx(&mut self) {
-        let addr: u16 = (self.processor.sp as u16 + 0x100 as u16).into();
+        let addr: u16 = self.processor.sp as u16 + 0x100;
         
         self.write(addr, self.processor.rx);
 
@@ -689,13 +1793,12 @@ impl Computer {
         }
         self.processor.sp = self.processor.sp.wrapping_sub(1);
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(3);
     }
     
 
     /// Push Y to stack
     fn phy(&mut self) {
-        let addr: u16 = (self.processor.sp as u16 + 0x100 as u16).into();
+        let addr: u16 = self.processor.sp as u16 + 0x100;
         
         self.write(addr, self.processor.ry);
 
@@ -704,12 +1807,11 @@ impl Computer {
         }
         self.processor.sp = self.processor.sp.wrapping_sub(1);
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(3);
     }
 
     /// Push flags to stack
     fn php(&mut self) {
-        let addr: u16 = (self.processor.sp as u16 + 0x100 as u16).into();
+        let addr: u16 = self.processor.sp as u16 + 0x100;
 
         self.write(addr, self.processor.flags | 0x30);
         if self.log_level > 0 {
@@ -717,13 +1819,12 @@ impl Computer {
         }
         self.processor.sp = self.processor.sp.wrapping_sub(1);
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(3);
     }
 
     /// Pull stack to accumulator
     fn pla(&mut self) {
         self.processor.sp = self.processor.sp.wrapping_add(1);
-        let addr: u16 = (self.processor.sp as u16 + 0x100 as u16).into();
+        let addr: u16 = self.processor.sp as u16 + 0x100;
         
         self.processor.acc = self.read(addr);
         let flags = self.processor.flags;
@@ -732,13 +1833,12 @@ impl Computer {
             self.add_info(format!("{:#x} - Running instruction pla at: {:#x} val: {:#x}", self.processor.pc, addr, self.processor.acc));
         }
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(4);
     }
 
     /// Pull stack to X
     fn plx(&mut self) {
         self.processor.sp = self.processor.sp.wrapping_add(1);
-        let addr: u16 = (self.processor.sp as u16 + 0x100 as u16).into();
+        let addr: u16 = self.processor.sp as u16 + 0x100;
         
         self.processor.rx = self.read(addr);
         let flags = self.processor.flags;
@@ -747,13 +1847,12 @@ impl Computer {
             self.add_info(format!("{:#x} - Running instruction plx at: {:#x} val: {:#x}", self.processor.pc, addr, self.processor.acc));
         }
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(4);
     }
 
     /// Pull stack to Y
     fn ply(&mut self) {
         self.processor.sp = self.processor.sp.wrapping_add(1);
-        let addr: u16 = (self.processor.sp as u16 + 0x100 as u16).into();
+        let addr: u16 = self.processor.sp as u16 + 0x100;
         
         self.processor.ry = self.read(addr);
         let flags = self.processor.flags;
@@ -762,20 +1861,18 @@ impl Computer {
             self.add_info(format!("{:#x} - Running instruction ply at: {:#x} val: {:#x}", self.processor.pc, addr, self.processor.acc));
         }
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(4);
     }
 
     // 0X28 Pull value from the stack into the processor registers
     fn plp(&mut self) {
         self.processor.sp = self.processor.sp.wrapping_add(1);
-        let addr: u16 = (self.processor.sp as u16 + 0x100 as u16).into();
+        let addr: u16 = self.processor.sp as u16 + 0x100;
         
         self.processor.flags = self.read(addr);
         if self.log_level > 0 {
             self.add_info(format!("{:#x} - Running instruction plp at: {:#x} flags: {:#x}", self.processor.pc, addr, self.processor.flags));
         }
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(4);
     }
 
 
@@ -879,7 +1976,6 @@ impl Computer {
         let addressing_mode = decode::get_adressing_mode(self.processor.inst);
         let mut value: u8 = self.processor.acc;
         let mode = addressing_mode;
-        self.processor.clock  = self.processor.clock.wrapping_add(2);
     
 
         let addr = self.get_ld_adddr(mode);
@@ -889,22 +1985,22 @@ impl Computer {
                 self.add_info(format!("{:#x} - Running instruction inc ZP with effective addr: {:#x} and val: {:#x}", self.processor.pc, addr, value));
             }
             self.processor.pc = self.processor.pc.wrapping_add(2);
-            self.processor.clock  = self.processor.clock.wrapping_add(3);
         } else if addressing_mode == AdressingMode::Absolute || addressing_mode == AdressingMode::AbsoluteX {
             value = self.read(addr);
             if self.log_level > 0 {
                 self.add_info(format!("{:#x} - Running instruction inc ABS with effective addr: {:#x} and val: {:#x}", self.processor.pc, addr, value));
             }
             self.processor.pc = self.processor.pc.wrapping_add(3);
-            self.processor.clock  = self.processor.clock.wrapping_add(4);
-            if addressing_mode == AdressingMode::AbsoluteX {
-                self.processor.clock  = self.processor.clock.wrapping_add(1);
-            }
         }
 
         let result = value.wrapping_add(1);
 
-        self.write(addr, result);
+        if addressing_mode == AdressingMode::Accumulator {
+            self.processor.acc = result;
+            self.processor.pc = self.processor.pc.wrapping_add(1);
+        } else {
+            self.write(addr, result);
+        }
 
         self.processor.flags = Self::set_flags(self.processor.flags, result);
     }
@@ -914,7 +2010,6 @@ impl Computer {
         let mut value: u8 = self.processor.acc;
         let mode = addressing_mode;
 
-        self.processor.clock  = self.processor.clock.wrapping_add(2);
 
         let addr = self.get_ld_adddr(mode);
         if addressing_mode == AdressingMode::ZeroPage || addressing_mode == AdressingMode::ZeroPageX {
@@ -923,22 +2018,22 @@ impl Computer {
                 self.add_info(format!("{:#x} - Running instruction dec ZP with effective addr: {:#x} and val: {:#x}", self.processor.pc, addr, value));
             }
             self.processor.pc = self.processor.pc.wrapping_add(2);
-            self.processor.clock  = self.processor.clock.wrapping_add(3);
         } else if addressing_mode == AdressingMode::Absolute || addressing_mode == AdressingMode::AbsoluteX {
             value = self.read(addr);
             if self.log_level > 0 {
                 self.add_info(format!("{:#x} - Running instruction dec ABS with effective addr: {:#x} and val: {:#x}", self.processor.pc, addr, value));
             }
             self.processor.pc = self.processor.pc.wrapping_add(3);
-            self.processor.clock  = self.processor.clock.wrapping_add(4);
-            if addressing_mode == AdressingMode::AbsoluteX {
-                self.processor.clock  = self.processor.clock.wrapping_add(1);
-            }
         }
 
         let result = value.wrapping_sub(1);
 
-        self.write(addr, result);
+        if addressing_mode == AdressingMode::Accumulator {
+            self.processor.acc = result;
+            self.processor.pc = self.processor.pc.wrapping_add(1);
+        } else {
+            self.write(addr, result);
+        }
 
         self.processor.flags = Self::set_flags(self.processor.flags, result);
     }
@@ -956,24 +2051,18 @@ impl Computer {
                 self.add_info(format!("{:#x} - Running instruction ldx val: {:#x}", self.processor.pc, value));
             }
             self.processor.pc = self.processor.pc.wrapping_add(2);
-            self.processor.clock  = self.processor.clock.wrapping_add(2);
         } else if addressing_mode == AdressingMode::Absolute || addressing_mode == AdressingMode::AbsoluteX || addressing_mode == AdressingMode::AbsoluteY {
             value = self.read(addr);
             if self.log_level > 0 {
                 self.add_info(format!("{:#x} - Running instruction ldx absolute with addr: {:#x} and val: {:#x}", self.processor.pc, addr, value));
             }
             self.processor.pc = self.processor.pc.wrapping_add(3);
-            self.processor.clock  = self.processor.clock.wrapping_add(4);
         }else if addressing_mode == AdressingMode::ZeroPage || addressing_mode == AdressingMode::ZeroPageY {
             value = self.read(addr);
             if self.log_level > 0 {
                 self.add_info(format!("{:#x} - Running instruction ldx ZP with effective addr: {:#x} and val: {:#x}", self.processor.pc, addr, value));
             }
             self.processor.pc = self.processor.pc.wrapping_add(2);
-            self.processor.clock  = self.processor.clock.wrapping_add(3);
-            if addressing_mode == AdressingMode::ZeroPageY {
-                self.processor.clock  = self.processor.clock.wrapping_add(1);
-            }
         }
         self.processor.rx = value;
         self.processor.flags = Self::set_flags(self.processor.flags, self.processor.rx);
@@ -991,24 +2080,18 @@ impl Computer {
                 self.add_info(format!("{:#x} - Running instruction ldy val: {:#x}", self.processor.pc, value));
             }
             self.processor.pc = self.processor.pc.wrapping_add(2);
-            self.processor.clock  = self.processor.clock.wrapping_add(2);
         } else if addressing_mode == AdressingMode::Absolute || addressing_mode == AdressingMode::AbsoluteX || addressing_mode == AdressingMode::AbsoluteY {
             value = self.read(addr);
             if self.log_level > 0 {
                 self.add_info(format!("{:#x} - Running instruction ldy absolute with addr: {:#x} and val: {:#x}", self.processor.pc, addr, value));
             }
             self.processor.pc = self.processor.pc.wrapping_add(3);
-            self.processor.clock  = self.processor.clock.wrapping_add(4);
         } else if addressing_mode == AdressingMode::ZeroPage || addressing_mode == AdressingMode::ZeroPageX {
             value = self.read(addr);
             if self.log_level > 0 {
                 self.add_info(format!("{:#x} - Running instruction ldy ZP with effective addr: {:#x} and val: {:#x}", self.processor.pc, addr, value));
             }
             self.processor.pc = self.processor.pc.wrapping_add(2);
-            self.processor.clock  = self.processor.clock.wrapping_add(3);
-            if addressing_mode == AdressingMode::ZeroPageY {
-                self.processor.clock  = self.processor.clock.wrapping_add(1);
-            }
         }
 
         self.processor.ry = value;
@@ -1025,25 +2108,17 @@ impl Computer {
             value = self.read(addr);
             
             self.processor.pc = self.processor.pc.wrapping_add(2);
-            self.processor.clock  = self.processor.clock.wrapping_add(2);
         } else if addressing_mode == AdressingMode::Absolute || addressing_mode == AdressingMode::AbsoluteX|| addressing_mode == AdressingMode::AbsoluteY {
             value = self.read(addr);
             self.processor.pc = self.processor.pc.wrapping_add(3);
-            self.processor.clock  = self.processor.clock.wrapping_add(4);
-        } else if addressing_mode == AdressingMode::ZeroPage || addressing_mode == AdressingMode::ZeroPageX {
-            value = self.read(addr);
-            self.processor.pc = self.processor.pc.wrapping_add(2);
-            self.processor.clock  = self.processor.clock.wrapping_add(3);
-        } else if addressing_mode == AdressingMode::IndirectY || addressing_mode == AdressingMode::IndirectX {
-            value = self.read(addr);
-            self.processor.pc = self.processor.pc.wrapping_add(2);
-            self.processor.clock  = self.processor.clock.wrapping_add(5);
-        } else if addressing_mode == AdressingMode::ZeroPageIndirect {
+        } else if addressing_mode == AdressingMode::ZeroPage || addressing_mode == AdressingMode::ZeroPageX
+            || addressing_mode == AdressingMode::IndirectY || addressing_mode == AdressingMode::IndirectX
+            || addressing_mode == AdressingMode::ZeroPageIndirect {
             value = self.read(addr);
             self.processor.pc = self.processor.pc.wrapping_add(2);
-            self.processor.clock  = self.processor.clock.wrapping_add(6);
         } else {
-            panic!("This adressing mode is not implemented yet, sorry");
+            self.fault(format!("Adressing mode {:?} not implemented for LDA", addressing_mode));
+            return;
         }
 
         if self.log_level > 0 {
@@ -1066,14 +2141,11 @@ impl Computer {
         if mode == AdressingMode::Accumulator {
             value = self.processor.acc;
             self.processor.pc = self.processor.pc.wrapping_add(1);
-            self.processor.clock  = self.processor.clock.wrapping_add(2);
         } else if mode == AdressingMode::Absolute || mode == AdressingMode::AbsoluteX {
             self.processor.pc = self.processor.pc.wrapping_add(3);
-            self.processor.clock  = self.processor.clock.wrapping_add(6);
             value = self.read(addr);
         } else {
             self.processor.pc = self.processor.pc.wrapping_add(2);
-            self.processor.clock  = self.processor.clock.wrapping_add(6);
             value = self.read(addr);
         }
         if value >> 7 & 1 == 1 {
@@ -1104,13 +2176,12 @@ impl Computer {
         let addressing_mode = decode::get_adressing_mode(self.processor.inst);
         let mode = addressing_mode;
 
-        let value;
         let addr = self.get_ld_adddr(mode);
-        if mode == AdressingMode::Accumulator {
-            value = self.processor.acc;
+        let value = if mode == AdressingMode::Accumulator {
+            self.processor.acc
         } else {
-            value = self.read(addr);
-        }
+            self.read(addr)
+        };
         let old_flags = self.processor.flags;
         if value & 1 == 1 {
             self.processor.flags |= FLAG_C;
@@ -1134,16 +2205,13 @@ impl Computer {
         }
         if mode == AdressingMode::Accumulator {
             self.processor.pc = self.processor.pc.wrapping_add(1);
-            self.processor.clock  = self.processor.clock.wrapping_add(2);
             self.processor.acc = result;
         } else if mode == AdressingMode::Absolute || mode == AdressingMode::AbsoluteX {
             self.processor.pc = self.processor.pc.wrapping_add(3);
-            self.processor.clock  = self.processor.clock.wrapping_add(6);
 
             self.write(addr, result);
         } else {
             self.processor.pc = self.processor.pc.wrapping_add(2);
-            self.processor.clock  = self.processor.clock.wrapping_add(5);
             self.write(addr, result);
         }
 
@@ -1158,14 +2226,11 @@ impl Computer {
         if mode == AdressingMode::Accumulator {
             value = self.processor.acc;
             self.processor.pc = self.processor.pc.wrapping_add(1);
-            self.processor.clock  = self.processor.clock.wrapping_add(2);
         } else if mode == AdressingMode::Absolute || mode == AdressingMode::AbsoluteX {
             value = self.processor.acc;
             self.processor.pc = self.processor.pc.wrapping_add(3);
-            self.processor.clock  = self.processor.clock.wrapping_add(6);
         } else {
             self.processor.pc = self.processor.pc.wrapping_add(2);
-            self.processor.clock  = self.processor.clock.wrapping_add(6);
             value = self.read(addr);
         }
         
@@ -1205,14 +2270,11 @@ impl Computer {
         if mode == AdressingMode::Accumulator {
             value = self.processor.acc;
             self.processor.pc = self.processor.pc.wrapping_add(1);
-            self.processor.clock  = self.processor.clock.wrapping_add(2);
         } else if mode == AdressingMode::Absolute || mode == AdressingMode::AbsoluteX {
             value = self.processor.acc;
             self.processor.pc = self.processor.pc.wrapping_add(3);
-            self.processor.clock  = self.processor.clock.wrapping_add(6);
         } else {
             self.processor.pc = self.processor.pc.wrapping_add(2);
-            self.processor.clock  = self.processor.clock.wrapping_add(6);
             value = self.read(addr);
         }
         
@@ -1257,12 +2319,11 @@ impl Computer {
         }
         if addressing_mode == AdressingMode::ZeroPage || addressing_mode == AdressingMode::Immediate || addressing_mode == AdressingMode::ZeroPageX {
             self.processor.pc = self.processor.pc.wrapping_add(2);
-            self.processor.clock  = self.processor.clock.wrapping_add(3);
         } else if addressing_mode == AdressingMode::Absolute || addressing_mode == AdressingMode::AbsoluteX{
             self.processor.pc = self.processor.pc.wrapping_add(3);
-            self.processor.clock  = self.processor.clock.wrapping_add(4);
         } else {
-            panic!("Sorry, the adressing mode {:?} does not exist for instruction {:#x}", addressing_mode, self.processor.inst)
+            self.fault(format!("Sorry, the adressing mode {:?} does not exist for instruction {:#x}", addressing_mode, self.processor.inst));
+            return;
         }
 
         if result == 0 {
@@ -1289,7 +2350,6 @@ impl Computer {
             self.add_info(format!("{:#x} - Running instruction inx: new val: {:#x} flags: {:#x}", self.processor.pc, self.processor.rx, self.processor.flags));
         }
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(2);
     }
 
     fn iny(&mut self) {
@@ -1299,7 +2359,6 @@ impl Computer {
             self.add_info(format!("{:#x} - Running instruction iny: new val: {:#x} flags: {:#x}", self.processor.pc, self.processor.ry, self.processor.flags));
         }
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(2);
     }
 
     fn dex(&mut self) {
@@ -1309,7 +2368,6 @@ impl Computer {
             self.add_info(format!("{:#x} - Running instruction dex: new val: {:#x} flags: {:#x}", self.processor.pc, self.processor.rx, self.processor.flags));
         }
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(2);
     }
 
     fn dey(&mut self) {
@@ -1319,7 +2377,6 @@ impl Computer {
             self.add_info(format!("{:#x} - Running instruction dey: {:#x} new val: {:#x}", self.processor.pc, self.data[(self.processor.pc) as usize], self.processor.ry));
         }
         self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(2);
     }
 
     fn cmp(&mut self) {
@@ -1354,8 +2411,6 @@ impl Computer {
 
         self.processor.flags = flags;
         self.processor.pc = pc;
-        // TODO fix clock counts
-        self.processor.clock  = self.processor.clock.wrapping_add(4);
         
     }
 
@@ -1373,7 +2428,8 @@ impl Computer {
         } else if addressing_mode == AdressingMode::ZeroPage {
             value = self.read(addr);
         } else {
-            panic!("Unknown address type {:?} {:#b}, {:#x}", addressing_mode, self.processor.inst, self.processor.inst);
+            self.fault(format!("Unknown address type {:?} {:#b}, {:#x}", addressing_mode, self.processor.inst, self.processor.inst));
+            return;
         }
         
         let mut flags = self.processor.flags;
@@ -1394,8 +2450,6 @@ impl Computer {
 
         self.processor.flags = flags;
         self.processor.pc = pc;
-        // TODO fix clock counts
-        self.processor.clock  = self.processor.clock.wrapping_add(4);
     }
 
     fn cpx(&mut self) {
@@ -1412,7 +2466,8 @@ impl Computer {
         } else if addressing_mode == AdressingMode::ZeroPage {
             value = self.read(addr);
         } else {
-            panic!("Unknown address type {:?} inst: {:#x}", addressing_mode, self.processor.inst);
+            self.fault(format!("Unknown address type {:?} inst: {:#x}", addressing_mode, self.processor.inst));
+            return;
         }
         
         let mut flags = self.processor.flags;
@@ -1433,8 +2488,6 @@ impl Computer {
 
         self.processor.flags = flags;
         self.processor.pc = pc;
-        // TODO fix clock counts
-        self.processor.clock  = self.processor.clock.wrapping_add(4);
     }
 
     fn sta(&mut self) {
@@ -1462,13 +2515,12 @@ impl Computer {
 
             pc += 2;
         } else {
-            panic!("Adressing mode {:?} not implemented for STA", addressing_mode);
+            self.fault(format!("Adressing mode {:?} not implemented for STA", addressing_mode));
+            return;
         }
         self.write(addr, self.processor.acc);
 
         self.processor.pc = pc;
-        // TODO fix clock counts
-        self.processor.clock  = self.processor.clock.wrapping_add(5);
     }
 
     fn stz(&mut self) {
@@ -1487,8 +2539,61 @@ impl Computer {
         self.write(addr, 0);
 
         self.processor.pc = pc;
-        // TODO fix clock counts
-        self.processor.clock  = self.processor.clock.wrapping_add(4);
+    }
+
+    /// Test and set bits: sets the bits of memory that are set in the accumulator
+    fn tsb(&mut self) {
+        self.test_bits(|value, acc| value | acc);
+    }
+
+    /// Test and reset bits: clears the bits of memory that are set in the accumulator
+    fn trb(&mut self) {
+        self.test_bits(|value, acc| value & !acc);
+    }
+
+    /// Sets Z like BIT does, then writes back the value changed by `change`
+    fn test_bits(&mut self, change: fn(u8, u8) -> u8) {
+        let addressing_mode = decode::get_adressing_mode(self.processor.inst);
+        let addr = self.get_ld_adddr(addressing_mode);
+        let value = self.read(addr);
+        if value & self.processor.acc == 0 {
+            self.processor.flags |= FLAG_Z;
+        } else {
+            self.processor.flags &= !FLAG_Z;
+        }
+        self.write(addr, change(value, self.processor.acc));
+        self.processor.pc = self.processor.pc.wrapping_add(opcodes::OPCODES[self.processor.inst as usize].bytes as u16);
+    }
+
+    /// Reset memory bit
+    fn rmb(&mut self, num: u8) {
+        let addr = self.read(self.processor.pc.wrapping_add(1)) as u16;
+        let value = self.read(addr);
+        self.write(addr, value & !(1 << num));
+        self.processor.pc = self.processor.pc.wrapping_add(2);
+    }
+
+    /// Set memory bit
+    fn smb(&mut self, num: u8) {
+        let addr = self.read(self.processor.pc.wrapping_add(1)) as u16;
+        let value = self.read(addr);
+        self.write(addr, value | (1 << num));
+        self.processor.pc = self.processor.pc.wrapping_add(2);
+    }
+
+    /// Wait for interrupt: runs again until a card asserts IRQ, which is then
+    /// taken after WAI, or execution carries on when interrupts are disabled
+    fn wai(&mut self) {
+        if self.irq_line.is_some() {
+            self.processor.pc = self.processor.pc.wrapping_add(1);
+        }
+    }
+
+    /// Stop the clock: pauses on STP, which runs again if the machine is resumed
+    /// without a reset
+    fn stp(&mut self) {
+        self.paused = true;
+        let _ = self.tx.send(ComputerMessage::Info(format!("Stopped by STP at ${:04X}, reset to go on", self.processor.pc)));
     }
 
     fn stx(&mut self) {
@@ -1501,10 +2606,8 @@ impl Computer {
                 self.add_info(format!("{:#x} - Running instruction stx ABS at: {:#x} val: {:#x}", self.processor.pc, addr, self.processor.rx));
             }
             pc = 3;
-        } else if addressing_mode == AdressingMode::ZeroPage || addressing_mode == AdressingMode::ZeroPageY {
-            if self.log_level > 0 {
-                self.add_info(format!("{:#x} - Running instruction stx ZP at: {:#x} val: {:#x}", self.processor.pc, addr, self.processor.rx));
-            }
+        } else if (addressing_mode == AdressingMode::ZeroPage || addressing_mode == AdressingMode::ZeroPageY) && self.log_level > 0 {
+            self.add_info(format!("{:#x} - Running instruction stx ZP at: {:#x} val: {:#x}", self.processor.pc, addr, self.processor.rx));
         }
         if addr == 0x200 {
             //self.paused = true;
@@ -1513,8 +2616,6 @@ impl Computer {
         self.write(addr, self.processor.rx);
 
         self.processor.pc += pc;
-        // TODO fix clock counts
-        self.processor.clock  = self.processor.clock.wrapping_add(4);
     }
 
     fn sty(&mut self) {
@@ -1528,10 +2629,8 @@ impl Computer {
                 self.add_info(format!("{:#x} - Running instruction sty ABS at: {:#x} val: {:#x}", self.processor.pc, addr, self.processor.rx));
             }
             pc = 3;
-        } else if addressing_mode == AdressingMode::ZeroPage || addressing_mode == AdressingMode::ZeroPageX {
-            if self.log_level > 0 {
-                self.add_info(format!("{:#x} - Running instruction sty ZP at: {:#x} val: {:#x}", self.processor.pc, addr, self.processor.rx));
-            }
+        } else if (addressing_mode == AdressingMode::ZeroPage || addressing_mode == AdressingMode::ZeroPageX) && self.log_level > 0 {
+            self.add_info(format!("{:#x} - Running instruction sty ZP at: {:#x} val: {:#x}", self.processor.pc, addr, self.processor.rx));
         }
         if addr == 0x200 {
             //self.paused = true;
@@ -1539,8 +2638,6 @@ impl Computer {
         self.write(addr, self.processor.ry);
 
         self.processor.pc += pc;
-        // TODO fix clock counts
-        self.processor.clock  = self.processor.clock.wrapping_add(4);
     }
 
     fn jmp(&mut self) {
@@ -1548,23 +2645,20 @@ impl Computer {
         let value: u16;
         if addressing_mode == AdressingMode::Absolute {
             value = self.get_word(self.processor.pc + 1);
-            self.processor.clock  = self.processor.clock.wrapping_add(5);
         } else if addressing_mode == AdressingMode::Indirect {
             let start = self.processor.pc + 1;
     
             let addr = self.get_word(start);
             value = self.get_word(addr);
 
-            self.processor.clock  = self.processor.clock.wrapping_add(3);
         } else if addressing_mode == AdressingMode::IndirectX {
             let start = self.processor.pc + 1;
             let addr = self.get_word(start).wrapping_add(self.processor.rx as u16);
             value = self.get_word(addr);
-            self.processor.clock  = self.processor.clock.wrapping_add(6);
         } else {
-            panic!("Adressing mode not implemented yet {:?} inst: {:#x}", addressing_mode, self.processor.inst);
+            self.fault(format!("Adressing mode not implemented yet {:?} inst: {:#x}", addressing_mode, self.processor.inst));
+            return;
         }
-        self.processor.clock += 5;
         if self.log_level > 0 {
             self.add_info(format!("{:#x} - Running instruction jmp: {:#x} to: {:#x}", self.processor.pc, self.data[(self.processor.pc) as usize], value));
         }
@@ -1592,7 +2686,6 @@ impl Computer {
             }
         }
 
-        self.processor.clock  = self.processor.clock.wrapping_add(3);
         self.processor.pc = new_addr;
 
         
@@ -1619,7 +2712,6 @@ impl Computer {
                 self.add_info(format!("{:#x} - Running instruction beq not jumping to: {:#x} flags: {:#x}", self.processor.pc, new_addr, self.processor.flags));
             }
         }
-        self.processor.clock  = self.processor.clock.wrapping_add(3);
         self.processor.pc = new_addr;
         
     }
@@ -1643,7 +2735,6 @@ impl Computer {
                 self.add_info(format!("{:#x} - Running instruction bcc NOT jumping to: {:#x} flags: {:#x} offset: {}", self.processor.pc, new_addr, self.processor.flags, offset as i8));
             }
         }
-        self.processor.clock  = self.processor.clock.wrapping_add(3);
         self.processor.pc = new_addr;
     }
 
@@ -1666,7 +2757,6 @@ impl Computer {
                 }
             }
         }
-        self.processor.clock  = self.processor.clock.wrapping_add(3);
         self.processor.pc = new_addr;
         
     }
@@ -1691,7 +2781,6 @@ impl Computer {
             }
         }
         
-        self.processor.clock  = self.processor.clock.wrapping_add(3);
         self.processor.pc = new_addr;
     }
 
@@ -1714,7 +2803,6 @@ impl Computer {
                 self.add_info(format!("{:#x} - Running instruction bvs {:#x} NOT jumping to: {:#x} flags: {:#x}", self.processor.pc, self.data[(self.processor.pc) as usize], new_addr, self.processor.flags));
             }
         }
-        self.processor.clock  = self.processor.clock.wrapping_add(3);
         self.processor.pc = new_addr;
     }
 
@@ -1730,7 +2818,6 @@ impl Computer {
             new_addr = ((new_addr as i32) + (rel_address as i32)) as u16;
         }
         self.processor.pc = new_addr;
-        self.processor.clock  = self.processor.clock.wrapping_add(3);
         
     }
     
@@ -1743,7 +2830,6 @@ impl Computer {
         // println!("BPL Jumping offset {:?}", rel_address);
         new_addr = ((new_addr as i32) + (rel_address as i32)) as u16;
         self.processor.pc = new_addr;
-        self.processor.clock  = self.processor.clock.wrapping_add(3);
         
     }
 
@@ -1758,7 +2844,6 @@ impl Computer {
             new_addr = ((new_addr as i32) + (rel_address as i32)) as u16;
         }
         self.processor.pc = new_addr;
-        self.processor.clock  = self.processor.clock.wrapping_add(4);
     }
 
     fn bbr(&mut self, num: u8) {
@@ -1771,7 +2856,6 @@ impl Computer {
             new_addr = ((new_addr as i32) + rel_address) as u16;
         }
         self.processor.pc = new_addr;
-        self.processor.clock  = self.processor.clock.wrapping_add(4);
     }
 
     /// Branch if negative flag is set
@@ -1787,30 +2871,23 @@ impl Computer {
             new_addr = ((new_addr as i32) + (rel_address as i32)) as u16;
         }
         self.processor.pc = new_addr;
-        self.processor.clock  = self.processor.clock.wrapping_add(3);
         
     }
 
     fn get_logical_op_value(&mut self) -> u8 {
         let addressing_mode = decode::get_adressing_mode(self.processor.inst);
         let addr = self.get_ld_adddr(addressing_mode);
-        return self.read(addr);
+        self.read(addr)
     }
 
     fn after_logical_op(&mut self) {
         let addressing_mode = decode::get_adressing_mode(self.processor.inst);
-        if addressing_mode == AdressingMode::Immediate {
-            self.processor.pc = self.processor.pc.wrapping_add(2);
-            self.processor.clock  = self.processor.clock.wrapping_add(2);
-        } else if addressing_mode == AdressingMode::ZeroPage || addressing_mode == AdressingMode::ZeroPageX {
-            self.processor.pc = self.processor.pc.wrapping_add(2);
-            self.processor.clock  = self.processor.clock.wrapping_add(3);
-        } else if addressing_mode == AdressingMode::IndirectX || addressing_mode == AdressingMode::IndirectY {
+        if addressing_mode == AdressingMode::Immediate
+            || addressing_mode == AdressingMode::ZeroPage || addressing_mode == AdressingMode::ZeroPageX
+            || addressing_mode == AdressingMode::IndirectX || addressing_mode == AdressingMode::IndirectY {
             self.processor.pc = self.processor.pc.wrapping_add(2);
-            self.processor.clock  = self.processor.clock.wrapping_add(6);
         } else if addressing_mode == AdressingMode::Absolute || addressing_mode == AdressingMode::AbsoluteX || addressing_mode == AdressingMode::AbsoluteY {
             self.processor.pc = self.processor.pc.wrapping_add(3);
-            self.processor.clock  = self.processor.clock.wrapping_add(4);
         } else {
             self.add_info(format!("{:#x} - this addressing mode not implemented for instruction {:?}", self.processor.pc, addressing_mode));
         }
@@ -1903,9 +2980,7 @@ impl Computer {
         let decimal = self.processor.flags & FLAG_D != 0;
         let acc= self.processor.acc;
 
-        let sum;
-
-        if decimal {
+        let sum = if decimal {
             let mut w: u16;
             let mut tmp = 0xf + (acc & 0xf) - (val & 0xf) + (self.processor.flags & FLAG_C);
             if tmp < 0x10 {
@@ -1925,15 +3000,15 @@ impl Computer {
               if (self.processor.flags & FLAG_O) != 0  && w >= 0x180 { self.processor.flags &= !FLAG_O; }
             }
             w += tmp as u16;
-            sum = w as u8
+            w as u8
         } else {
-            sum = self.do_add(!val);
-        }
+            self.do_add(!val)
+        };
 
         if self.log_level > 0 {
             self.add_info(format!("{:#x} - Running instruction sbc with acc: {:#x} memval: {:#x} flags: {:#x}", self.processor.pc, self.processor.acc, val, self.processor.flags));
         }
-        self.processor.acc = sum as u8;
+        self.processor.acc = sum;
         self.after_logical_op();
     }
 
@@ -1966,7 +3041,7 @@ impl Computer {
 
         
 
-        return sum;
+        sum
     }
 
     fn nop(&mut self) {
@@ -1977,8 +3052,8 @@ impl Computer {
             self.speed = 10;
         }
         
-        self.processor.pc = self.processor.pc.wrapping_add(1);
-        self.processor.clock  = self.processor.clock.wrapping_add(2);
+        // Also skips the operands of the multi-byte NOPs
+        self.processor.pc = self.processor.pc.wrapping_add(opcodes::OPCODES[self.processor.inst as usize].bytes as u16);
         
     }
 
@@ -1999,12 +3074,12 @@ impl Computer {
         _flags |= 0x30;
 
         // // println!("Setting flags to {:#b}", _flags);
-        return _flags;
+        _flags
     }
 
     pub fn get_word(&mut self, address: u16) -> u16 {
         let low_byte: u16 = self.read(address).into();
         let high_byte: u16 = self.read(address.wrapping_add(1)).into();
-        return low_byte + (high_byte << 8);
+        low_byte + (high_byte << 8)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,174 @@
+use std::fmt;
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
+
+use memchr::memmem;
+use rhai::{Engine, EvalAltResult, ImmutableString, Scope};
+
+use crate::computer::snapshot::{self, Snapshot};
+use crate::computer::{Computer, Metrics, Processor};
+use crate::error::{self, Error};
+use crate::rom::{Rom, RomOptions};
+use crate::script::{self, Script};
+
+/// Result of the test script on one build
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Pass,
+    /// The script ended on 0 or false, or failed with this error
+    Fail(Option<String>),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Pass => write!(f, "pass"),
+            Outcome::Fail(None) => write!(f, "fail"),
+            Outcome::Fail(Some(e)) => write!(f, "fail, {}", e),
+        }
+    }
+}
+
+/// Runs a test script against builds ordered from the oldest to the newest, looking
+/// for the first one that fails. Builds are ROM images, or snapshots saved on exit.
+///
+/// Tests are [`Script`]s, which besides the functions of Rhai can call:
+///
+/// - `run(cycles)` runs the machine, returning false if it stopped on a fault or breakpoint
+/// - `send("text")` or `send(byte)` queues serial input
+/// - `output("text")` is true when the serial output so far contains the text
+/// - `peek(addr)` and `word(addr)` read memory
+/// - `pc()`, `a()`, `x()` and `y()` read registers
+///
+/// A build passes when the script ends on anything but 0 or false.
+pub struct Bisect {
+    script: Script,
+    rom_options: RomOptions,
+    disk: Vec<u8>,
+}
+
+impl Bisect {
+    pub fn new(script: Script, rom_options: RomOptions, disk: Vec<u8>) -> Self {
+        Bisect { script, rom_options, disk }
+    }
+
+    /// Runs the test on the build in `path`, failing when the file cannot be loaded.
+    pub fn test(&self, path: &str) -> Result<Outcome, Error> {
+        let computer = self.computer(error::read_file(path)?)?;
+        let engine = test_engine(Arc::new(Mutex::new(TestHost { computer, output: vec![] })));
+        Ok(match self.script.run(&engine, &mut Scope::new()) {
+            Ok(0) => Outcome::Fail(None),
+            Ok(_) => Outcome::Pass,
+            Err(e) => Outcome::Fail(Some(e)),
+        })
+    }
+
+    fn computer(&self, data: Vec<u8>) -> Result<Computer, Error> {
+        let (_, rx) = mpsc::channel();
+        let (tx, _) = mpsc::channel();
+        if snapshot::is_snapshot(&data) {
+            let snapshot = Snapshot::from_bytes(&data)?;
+            let rom = Rom { base: 0, data: snapshot.memory.clone() };
+            let mut computer = Computer::new(tx, rx, Arc::new(Metrics::default()), rom, vec![]);
+            computer.set_fast_forward(true);
+            computer.restore(snapshot);
+            return Ok(computer);
+        }
+        let rom = Rom::load(data, &self.rom_options)?;
+        let mut computer = Computer::new(tx, rx, Arc::new(Metrics::default()), rom, self.disk.clone());
+        // Tests run as fast as the host can
+        computer.set_fast_forward(true);
+        computer.reset();
+        Ok(computer)
+    }
+
+    /// Index of the first failing build, None when the newest one passes. Only
+    /// the builds needed are tested, assuming that once a build fails all newer
+    /// ones do. `tested` is called after each test.
+    pub fn run(&self, builds: &[String], mut tested: impl FnMut(&str, &Outcome)) -> Result<Option<usize>, Error> {
+        let mut passes = |i: usize| -> Result<bool, Error> {
+            let outcome = self.test(&builds[i])?;
+            tested(&builds[i], &outcome);
+            Ok(outcome == Outcome::Pass)
+        };
+
+        let Some(newest) = builds.len().checked_sub(1) else {
+            return Ok(None);
+        };
+        if passes(newest)? {
+            return Ok(None);
+        }
+        if newest == 0 || !passes(0)? {
+            return Ok(Some(0));
+        }
+        // The build at `good` passes and the one at `bad` fails
+        let (mut good, mut bad) = (0, newest);
+        while bad - good > 1 {
+            let mid = good + (bad - good) / 2;
+            if passes(mid)? {
+                good = mid;
+            } else {
+                bad = mid;
+            }
+        }
+        Ok(Some(bad))
+    }
+}
+
+/// Machine under test, shared by the functions of the script
+struct TestHost {
+    computer: Computer,
+    /// Serial output since the start of the test
+    output: Vec<u8>,
+}
+
+impl TestHost {
+    fn run(&mut self, cycles: i64) -> bool {
+        let end = self.computer.processor().clock + cycles.max(0) as u128;
+        while self.computer.processor().clock < end && !self.computer.is_paused() {
+            self.computer.run_one();
+        }
+        self.output.extend(self.computer.take_output());
+        !self.computer.is_paused()
+    }
+
+    fn send(&mut self, bytes: &[u8]) -> Result<(), Box<EvalAltResult>> {
+        if self.computer.serial_rx.push_slice(bytes) < bytes.len() {
+            return Err("serial input full, run the machine to read it".into());
+        }
+        self.computer.receive_serial();
+        Ok(())
+    }
+}
+
+/// Reads a register for the script
+type Register = fn(&Processor) -> i64;
+
+/// Engine with the functions tests can call on `host`
+fn test_engine(host: Arc<Mutex<TestHost>>) -> Engine {
+    let mut engine = script::new_engine();
+    let registers: [(&str, Register); 4] = [
+        ("pc", |p| p.pc as i64),
+        ("a", |p| p.acc as i64),
+        ("x", |p| p.rx as i64),
+        ("y", |p| p.ry as i64),
+    ];
+    for (name, register) in registers {
+        let host = host.clone();
+        engine.register_fn(name, move || register(lock(&host).computer.processor()));
+    }
+    let h = host.clone();
+    engine.register_fn("run", move |cycles: i64| lock(&h).run(cycles));
+    let h = host.clone();
+    engine.register_fn("send", move |text: ImmutableString| lock(&h).send(text.as_bytes()));
+    let h = host.clone();
+    engine.register_fn("send", move |byte: i64| lock(&h).send(&[byte as u8]));
+    let h = host.clone();
+    engine.register_fn("output", move |text: ImmutableString| memmem::find(&lock(&h).output, text.as_bytes()).is_some());
+    script::register_memory(&mut engine, move |addr| lock(&host).computer.memory()[addr as usize]);
+    engine
+}
+
+fn lock(host: &Mutex<TestHost>) -> MutexGuard<'_, TestHost> {
+    // A function that panicked leaves nothing half done worth refusing the machine for
+    host.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,104 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+
+use crate::computer::disassembler;
+use crate::computer::Processor;
+use crate::error::SECTOR_SIZE;
+use crate::rom::ADDRESS_SPACE;
+
+/// Number of instructions kept for crash dumps
+pub const TRACE_LEN: usize = 256;
+
+/// State of the processor before running one instruction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub clock: u128,
+    pub pc: u16,
+    /// Opcode and the two bytes after it, enough for any instruction
+    pub bytes: [u8; 3],
+    pub acc: u8,
+    pub rx: u8,
+    pub ry: u8,
+    pub sp: u8,
+    pub flags: u8,
+}
+
+/// Everything needed to look into a fault after the emulator is gone.
+#[derive(Clone, Debug)]
+pub struct CrashDump {
+    pub reason: String,
+    pub processor: Processor,
+    /// Oldest first
+    pub trace: Vec<TraceEntry>,
+    pub zero_page: Vec<u8>,
+    pub stack: Vec<u8>,
+    /// Sectors written since the disk was loaded, with their current content
+    pub dirty_sectors: Vec<(u32, Vec<u8>)>,
+}
+
+impl CrashDump {
+    /// Writes the dump to a new timestamped file in `dir`, returning its path.
+    pub fn write(&self, dir: &Path) -> io::Result<PathBuf> {
+        let now = Local::now();
+        let path = dir.join(format!("plu-crash-{}.txt", now.format("%Y%m%d-%H%M%S%.3f")));
+        fs::write(&path, self.render(&now.to_rfc3339()))?;
+        Ok(path)
+    }
+
+    pub fn render(&self, time: &str) -> String {
+        let p = &self.processor;
+        let mut out = String::new();
+        let _ = writeln!(out, "Planck 6502 emulator crash dump");
+        let _ = writeln!(out, "Time: {}", time);
+        let _ = writeln!(out, "Reason: {}", self.reason);
+
+        let _ = writeln!(out, "\nRegisters");
+        let _ = writeln!(out, "PC: {:04X}  A: {:02X}  X: {:02X}  Y: {:02X}  SP: {:02X}  Flags: {}  Cycles: {}",
+            p.pc, p.acc, p.rx, p.ry, p.sp, flags(p.flags), p.clock);
+
+        let _ = writeln!(out, "\nLast {} instructions, oldest first", self.trace.len());
+        let _ = writeln!(out, "{:>12}  PC    Bytes     Instruction      A  X  Y  SP Flags", "Cycles");
+        // Disassemble from the recorded bytes, the code may have changed since
+        let mut mem = vec![0; ADDRESS_SPACE];
+        for entry in &self.trace {
+            for (i, b) in entry.bytes.iter().enumerate() {
+                mem[entry.pc.wrapping_add(i as u16) as usize] = *b;
+            }
+            let inst = disassembler::disassemble(&mem, entry.pc);
+            let bytes = inst.bytes.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ");
+            let _ = writeln!(out, "{:>12}  {:04X}  {:<9} {:<16} {:02X} {:02X} {:02X} {:02X} {}",
+                entry.clock, entry.pc, bytes, inst.text, entry.acc, entry.rx, entry.ry, entry.sp, flags(entry.flags));
+        }
+
+        let _ = writeln!(out, "\nZero page");
+        hex_dump(&mut out, 0, &self.zero_page);
+        let _ = writeln!(out, "\nStack page");
+        hex_dump(&mut out, 0x100, &self.stack);
+
+        let _ = writeln!(out, "\nDirty disk sectors: {}", self.dirty_sectors.len());
+        for (lba, data) in &self.dirty_sectors {
+            let _ = writeln!(out, "\nSector {} (offset ${:X})", lba, *lba as usize * SECTOR_SIZE);
+            hex_dump(&mut out, 0, data);
+        }
+        out
+    }
+}
+
+/// Flags as NV-BDIZC letters, dashes for the clear ones
+fn flags(flags: u8) -> String {
+    "NV-BDIZC".chars().enumerate()
+        .map(|(i, c)| if flags & (0x80 >> i) != 0 { c } else { '-' })
+        .collect()
+}
+
+fn hex_dump(out: &mut String, start: usize, data: &[u8]) {
+    for (i, row) in data.chunks(16).enumerate() {
+        let bytes = row.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ");
+        let text: String = row.iter().map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }).collect();
+        let _ = writeln!(out, "{:04X}  {:<47}  {}", start + i * 16, bytes, text);
+    }
+}
@@ -0,0 +1,172 @@
+use crate::computer::raster::SCANLINES;
+use rhai::{Dynamic, Scope};
+
+use crate::script::{self, Script};
+
+/// Card made of [`Script`]s from the project file, for trying hardware ideas without
+/// writing Rust. The read script gets the register in `reg` and returns the byte
+/// read, the write script also gets the byte in `value`. The tick script runs every
+/// `tick_cycles` cycles. The scripts of a device share one scope, kept between runs
+/// and cleared on reset, and setting `irq` to anything but 0 asserts the IRQ line.
+///
+/// The frame script runs at the start of every frame with its number in `frame`.
+/// Setting `raster` to a line runs the scanline script when the beam reaches it,
+/// with the line in `line`, on every frame until `raster` changes. It starts at -1,
+/// no line. Setting `so` to anything but 0 pulls the SO pin of the processor, which
+/// sets the overflow flag, and `so` has to go back to 0 before it can do it again.
+#[derive(Clone, Debug)]
+pub struct Device {
+    pub slot: u16,
+    pub name: String,
+    read: Option<Script>,
+    write: Option<Script>,
+    tick: Option<Script>,
+    frame: Option<Script>,
+    scanline: Option<Script>,
+    pub tick_cycles: u64,
+    scope: Scope<'static>,
+    /// Line the scanline handler is scheduled on
+    pub(super) scheduled_line: Option<u16>,
+    /// Level of the SO line when last checked
+    so: bool,
+}
+
+/// Sources of the handlers of a [`Device`], all optional
+#[derive(Clone, Debug, Default)]
+pub struct Handlers<'a> {
+    pub read: Option<&'a str>,
+    pub write: Option<&'a str>,
+    pub tick: Option<&'a str>,
+    pub frame: Option<&'a str>,
+    pub scanline: Option<&'a str>,
+}
+
+impl Device {
+    /// Compiles the handlers, naming the one that does not parse in the error.
+    pub fn new(slot: u16, name: &str, handlers: Handlers, tick_cycles: u64) -> Result<Device, String> {
+        let compile = |handler: &str, source: Option<&str>| {
+            source.map(Script::parse).transpose().map_err(|e| format!("{} handler of device {}: {}", handler, name, e))
+        };
+        Ok(Device {
+            slot,
+            name: name.to_string(),
+            read: compile("read", handlers.read)?,
+            write: compile("write", handlers.write)?,
+            tick: compile("tick", handlers.tick)?,
+            frame: compile("frame", handlers.frame)?,
+            scanline: compile("scanline", handlers.scanline)?,
+            tick_cycles,
+            scope: new_scope(),
+            scheduled_line: None,
+            so: false,
+        })
+    }
+
+    /// Byte read from register `reg`, None without a read handler.
+    pub fn read(&mut self, reg: u16) -> Result<Option<u8>, String> {
+        let Some(script) = &self.read else {
+            return Ok(None);
+        };
+        self.scope.set_value("reg", reg as i64);
+        script.run(script::engine(), &mut self.scope).map(|v| Some(v as u8))
+    }
+
+    pub fn write(&mut self, reg: u16, value: u8) -> Result<(), String> {
+        let Some(script) = &self.write else {
+            return Ok(());
+        };
+        self.scope.set_value("reg", reg as i64);
+        self.scope.set_value("value", value as i64);
+        script.run(script::engine(), &mut self.scope).map(|_| ())
+    }
+
+    /// Runs the tick handler, false when there is none and no need to schedule ticks.
+    pub fn tick(&mut self) -> Result<bool, String> {
+        let Some(script) = &self.tick else {
+            return Ok(false);
+        };
+        script.run(script::engine(), &mut self.scope).map(|_| true)
+    }
+
+    pub fn ticks(&self) -> bool {
+        self.tick.is_some() && self.tick_cycles > 0
+    }
+
+    pub fn frame(&mut self, number: u64) -> Result<(), String> {
+        let Some(script) = &self.frame else {
+            return Ok(());
+        };
+        self.scope.set_value("frame", number as i64);
+        script.run(script::engine(), &mut self.scope).map(|_| ())
+    }
+
+    pub fn scanline(&mut self, line: u16) -> Result<(), String> {
+        let Some(script) = &self.scanline else {
+            return Ok(());
+        };
+        self.scope.set_value("line", line as i64);
+        script.run(script::engine(), &mut self.scope).map(|_| ())
+    }
+
+    /// Line the scanline handler wants to run on, None when `raster` is negative or
+    /// past the last line.
+    pub fn raster(&self) -> Option<u16> {
+        self.scanline.as_ref()?;
+        let line = self.int("raster")?;
+        (0..SCANLINES as i64).contains(&line).then_some(line as u16)
+    }
+
+    /// Whether the device pulled the SO line since the last check. The overflow
+    /// flag is set on the edge, not while the line stays pulled.
+    pub fn so_edge(&mut self) -> bool {
+        let so = self.int("so").is_some_and(|v| v != 0);
+        let edge = so && !self.so;
+        self.so = so;
+        edge
+    }
+
+    pub fn irq(&self) -> bool {
+        self.int("irq").is_some_and(|v| v != 0)
+    }
+
+    /// Variables of the scripts as JSON, for snapshots
+    pub fn save(&self) -> Result<String, String> {
+        let variables: Vec<(&str, &Dynamic)> = self.scope.iter_raw().map(|(name, _, value)| (name, value)).collect();
+        serde_json::to_string(&variables).map_err(|e| e.to_string())
+    }
+
+    /// Carries on with the variables saved by [`Device::save`]
+    pub fn restore(&mut self, saved: &str) -> Result<(), String> {
+        let variables: Vec<(String, Dynamic)> = serde_json::from_str(saved).map_err(|e| e.to_string())?;
+        let mut scope = Scope::new();
+        for (name, value) in variables {
+            scope.push_dynamic(name, value);
+        }
+        self.scope = scope;
+        self.scheduled_line = None;
+        // Still pulled when saved, not a new edge
+        self.so = self.int("so").is_some_and(|v| v != 0);
+        Ok(())
+    }
+
+    pub fn reset(&mut self) {
+        self.scope = new_scope();
+        self.scheduled_line = None;
+        self.so = false;
+    }
+
+    /// Value of a variable of the scripts, None when it is not a number
+    fn int(&self, name: &str) -> Option<i64> {
+        self.scope.get(name).and_then(|value| script::int(value).ok())
+    }
+}
+
+/// Variables the computer sets or reads, defined for every script
+fn new_scope() -> Scope<'static> {
+    let mut scope = Scope::new();
+    for name in ["reg", "value", "frame", "line", "irq", "so"] {
+        scope.push(name, 0_i64);
+    }
+    scope.push("raster", -1_i64);
+    scope
+}
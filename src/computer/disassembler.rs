@@ -0,0 +1,67 @@
+use crate::computer::AdressingMode;
+use crate::computer::opcodes::OPCODES;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    pub text: String,
+}
+
+/// Disassembles the instruction found at `address` in `mem`.
+pub fn disassemble(mem: &[u8], address: u16) -> Instruction {
+    let byte = |offset: u16| mem.get(address.wrapping_add(offset) as usize).copied().unwrap_or(0);
+    let opcode = &OPCODES[byte(0) as usize];
+    let length = opcode.bytes as u16;
+
+    let bytes = (0..length).map(byte).collect::<Vec<u8>>();
+    let word = (byte(2) as u16) << 8 | byte(1) as u16;
+    let next = address.wrapping_add(length);
+
+    let args = match opcode.mode {
+        AdressingMode::Implied | AdressingMode::None => String::new(),
+        AdressingMode::Relative => format!("${:04X}", next.wrapping_add(byte(1) as i8 as u16)),
+        AdressingMode::ZeroPageRelative => format!("${:02X},${:04X}", byte(1), next.wrapping_add(byte(2) as i8 as u16)),
+        AdressingMode::Immediate => format!("#${:02X}", byte(1)),
+        AdressingMode::ZeroPage => format!("${:02X}", byte(1)),
+        AdressingMode::ZeroPageX => format!("${:02X},X", byte(1)),
+        AdressingMode::ZeroPageY => format!("${:02X},Y", byte(1)),
+        AdressingMode::Absolute => format!("${:04X}", word),
+        AdressingMode::AbsoluteX => format!("${:04X},X", word),
+        AdressingMode::AbsoluteY => format!("${:04X},Y", word),
+        // JMP (abs,X) is the only instruction using a 16 bit indexed indirect address
+        AdressingMode::IndirectX if length == 3 => format!("(${:04X},X)", word),
+        AdressingMode::IndirectX => format!("(${:02X},X)", byte(1)),
+        AdressingMode::IndirectY => format!("(${:02X}),Y", byte(1)),
+        AdressingMode::Indirect => format!("(${:04X})", word),
+        AdressingMode::ZeroPageIndirect => format!("(${:02X})", byte(1)),
+        AdressingMode::Accumulator => String::from("A"),
+    };
+
+    let mnemonic = match opcode.mnemonic {
+        "" => "???",
+        n => n,
+    };
+
+    let text = if args.is_empty() {
+        mnemonic.to_string()
+    } else {
+        format!("{} {}", mnemonic, args)
+    };
+
+    Instruction {
+        address,
+        bytes,
+        text,
+    }
+}
+
+/// Disassembles `count` consecutive instructions starting at `address`.
+pub fn disassemble_range(mem: &[u8], address: u16, count: usize) -> Vec<Instruction> {
+    let mut address = address;
+    (0..count).map(|_| {
+        let inst = disassemble(mem, address);
+        address = address.wrapping_add(inst.bytes.len() as u16);
+        inst
+    }).collect()
+}
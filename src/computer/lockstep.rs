@@ -0,0 +1,133 @@
+use std::fmt;
+use std::sync::{mpsc, Arc};
+
+use crate::computer::{Computer, Core, Metrics, Processor};
+use crate::rom::Rom;
+
+/// Instructions run by `plu lockstep`
+pub const INSTRUCTIONS: u64 = 10_000_000;
+/// Memory is compared every this many instructions, registers and output after each one
+pub const MEMORY_INTERVAL: u64 = 1024;
+
+/// What the two cores disagree on
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Difference {
+    Registers,
+    /// First differing byte, written during the last [`MEMORY_INTERVAL`] instructions
+    Memory { addr: u16, legacy: u8, table: u8 },
+    Output { legacy: Vec<u8>, table: Vec<u8> },
+    /// Only one of the cores stopped
+    Paused { legacy: bool, table: bool },
+}
+
+/// First point at which the cores stopped agreeing
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    /// Instructions run by each core, the last one included
+    pub instructions: u64,
+    /// Address of the last instruction run
+    pub pc: u16,
+    pub legacy: Processor,
+    pub table: Processor,
+    pub difference: Difference,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Cores diverged after {} instructions, the last one at ${:04X}", self.instructions, self.pc)?;
+        writeln!(f, "legacy: {}", registers(&self.legacy))?;
+        writeln!(f, "table:  {}", registers(&self.table))?;
+        match &self.difference {
+            Difference::Registers => Ok(()),
+            Difference::Memory { addr, legacy, table } => writeln!(
+                f, "memory at ${:04X}: legacy ${:02X}, table ${:02X}, written in the last {} instructions",
+                addr, legacy, table, MEMORY_INTERVAL,
+            ),
+            Difference::Output { legacy, table } => writeln!(f, "serial output: legacy {:?}, table {:?}", legacy, table),
+            Difference::Paused { legacy, table } => writeln!(f, "paused: legacy {}, table {}", legacy, table),
+        }
+    }
+}
+
+fn registers(p: &Processor) -> String {
+    format!("PC: {:04X}  A: {:02X}  X: {:02X}  Y: {:02X}  SP: {:02X}  Flags: {:08b}  Cycles: {}",
+        p.pc, p.acc, p.rx, p.ry, p.sp, p.flags, p.clock)
+}
+
+/// Runs the legacy and table driven cores side by side on the same ROM and disk.
+/// They only share the bus and the cards, so the first divergence points at
+/// an instruction one of them gets wrong.
+pub struct Lockstep {
+    legacy: Computer,
+    table: Computer,
+}
+
+impl Lockstep {
+    pub fn new(rom: Rom, disk: Vec<u8>) -> Self {
+        Self::with_computers(computer(rom.clone(), disk.clone()), computer(rom, disk))
+    }
+
+    /// Pairs computers built elsewhere, switching each one to its core and to full speed.
+    pub fn with_computers(mut legacy: Computer, mut table: Computer) -> Self {
+        legacy.set_core(Core::Legacy);
+        table.set_core(Core::Table);
+        legacy.set_fast_forward(true);
+        table.set_fast_forward(true);
+        Lockstep { legacy, table }
+    }
+
+    /// Runs up to `instructions` instructions on both cores, stopping early when
+    /// both pause, and returns how many ran or where the cores diverged.
+    pub fn run(&mut self, instructions: u64) -> Result<u64, Box<Divergence>> {
+        for count in 1..=instructions {
+            let pc = self.legacy.processor().pc;
+            self.legacy.run_one();
+            self.table.run_one();
+
+            let difference = self.compare(count.is_multiple_of(MEMORY_INTERVAL) || count == instructions);
+            if let Some(difference) = difference {
+                return Err(Box::new(Divergence {
+                    instructions: count,
+                    pc,
+                    legacy: self.legacy.processor().clone(),
+                    table: self.table.processor().clone(),
+                    difference,
+                }));
+            }
+            if self.legacy.is_paused() {
+                return Ok(count);
+            }
+        }
+        Ok(instructions)
+    }
+
+    fn compare(&mut self, memory: bool) -> Option<Difference> {
+        let (legacy, table) = (&mut self.legacy, &mut self.table);
+        if legacy.processor() != table.processor() {
+            return Some(Difference::Registers);
+        }
+        let (legacy_output, table_output) = (legacy.take_output(), table.take_output());
+        if legacy_output != table_output {
+            return Some(Difference::Output { legacy: legacy_output, table: table_output });
+        }
+        if legacy.is_paused() != table.is_paused() {
+            return Some(Difference::Paused { legacy: legacy.is_paused(), table: table.is_paused() });
+        }
+        if memory {
+            let (legacy, table) = (self.legacy.memory(), self.table.memory());
+            if let Some(addr) = legacy.iter().zip(table).position(|(a, b)| a != b) {
+                return Some(Difference::Memory { addr: addr as u16, legacy: legacy[addr], table: table[addr] });
+            }
+        }
+        None
+    }
+}
+
+/// Computer whose channels are closed, it carries on without a UI
+fn computer(rom: Rom, disk: Vec<u8>) -> Computer {
+    let (_, rx) = mpsc::channel();
+    let (tx, _) = mpsc::channel();
+    let mut computer = Computer::new(tx, rx, Arc::new(Metrics::default()), rom, disk);
+    computer.reset();
+    computer
+}
@@ -0,0 +1,130 @@
+use std::fmt;
+use std::ops::{Range, RangeInclusive};
+
+use crate::computer::device::Device;
+use crate::computer::{Card, CardType, IO_BASE, IO_TOP};
+use crate::rom::Rom;
+
+/// Zero page and stack, which the 6502 needs as RAM
+const RAM_NEEDED: RangeInclusive<u16> = 0x0000..=0x01FF;
+
+/// Two things answering at the same addresses.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Conflict {
+    /// Two enabled cards share a slot
+    Slot { slot: u16, cards: [CardType; 2] },
+    /// A card window hides part of the ROM image that is not padding
+    RomShadowed { slot: u16, card_type: CardType },
+    /// The ROM covers the zero page or the stack
+    RomOverRam(RangeInclusive<u16>),
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Conflict::Slot { slot, cards: [a, b] } => {
+                let w = window(*slot);
+                write!(f, "{:?} and {:?} cards both answer at ${:04X}-${:04X}", a, b, w.start(), w.end())
+            }
+            Conflict::RomShadowed { slot, card_type } => {
+                let w = window(*slot);
+                write!(f, "{:?} card in slot {} hides ROM data at ${:04X}-${:04X}", card_type, slot, w.start(), w.end())
+            }
+            Conflict::RomOverRam(range) => write!(f, "ROM covers RAM needed for the zero page and stack at ${:04X}-${:04X}", range.start(), range.end()),
+        }
+    }
+}
+
+/// What answers at an address
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Region<'a> {
+    Ram,
+    Rom,
+    Card(&'a Card),
+    /// Window of a disabled card, nothing drives the data bus
+    Unmapped,
+}
+
+/// Where the ROM and the cards sit, for the UI to tell regions apart without
+/// asking the computer about every address.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryMap {
+    pub rom: Range<usize>,
+    pub cards: Vec<Card>,
+    /// Slots and names of the scripted devices
+    pub devices: Vec<(u16, String)>,
+}
+
+impl MemoryMap {
+    pub fn new(cards: &[Card], devices: &[Device], rom: &Rom) -> Self {
+        let devices = devices.iter().map(|d| (d.slot, d.name.clone())).collect();
+        MemoryMap { rom: rom.range(), cards: cards.to_vec(), devices }
+    }
+
+    /// What to call `card` in the UI, scripted devices going by their name in the project
+    pub fn card_name(&self, card: &Card) -> String {
+        match self.devices.iter().find(|(slot, _)| *slot == card.slot) {
+            Some((_, name)) if card.card_type == CardType::Device => name.clone(),
+            _ => format!("{:?}", card.card_type),
+        }
+    }
+
+    /// Region of `addr`, with the same precedence as the computer: the card in the
+    /// slot when there is one, then the ROM, then RAM.
+    pub fn region(&self, addr: u16) -> Region<'_> {
+        if (IO_BASE..=IO_TOP).contains(&addr) {
+            let slot = ((addr & 0xF0) >> 4) - 8;
+            match self.cards.iter().find(|c| c.slot == slot) {
+                Some(card) if card.enabled => return Region::Card(card),
+                Some(_) => return Region::Unmapped,
+                None => {}
+            }
+        }
+        if self.rom.contains(&(addr as usize)) {
+            Region::Rom
+        } else {
+            Region::Ram
+        }
+    }
+}
+
+/// Addresses decoded by the card in `slot`
+pub fn window(slot: u16) -> RangeInclusive<u16> {
+    let base = IO_BASE + slot * 0x10;
+    base..=base + 0xF
+}
+
+/// Lists what the cards and ROM would silently shadow. Windows where the ROM only
+/// holds one repeated byte are padding left for the cards, and are not reported.
+pub fn check(cards: &[Card], rom: &Rom) -> Vec<Conflict> {
+    let mut conflicts = vec![];
+    let enabled: Vec<&Card> = cards.iter().filter(|c| c.enabled).collect();
+
+    for (i, a) in enabled.iter().enumerate() {
+        if let Some(b) = enabled[i + 1..].iter().find(|b| b.slot == a.slot) {
+            conflicts.push(Conflict::Slot { slot: a.slot, cards: [a.card_type.clone(), b.card_type.clone()] });
+        }
+    }
+
+    let rom_range = rom.range();
+    for card in &enabled {
+        let w = window(card.slot);
+        let start = (*w.start() as usize).max(rom_range.start);
+        let end = (*w.end() as usize + 1).min(rom_range.end);
+        if start >= end {
+            continue;
+        }
+        let bytes = &rom.data[start - rom_range.start..end - rom_range.start];
+        if bytes.iter().any(|&b| b != bytes[0]) {
+            conflicts.push(Conflict::RomShadowed { slot: card.slot, card_type: card.card_type.clone() });
+        }
+    }
+
+    let start = (*RAM_NEEDED.start() as usize).max(rom_range.start);
+    let end = (*RAM_NEEDED.end() as usize + 1).min(rom_range.end);
+    if start < end {
+        conflicts.push(Conflict::RomOverRam(start as u16..=(end - 1) as u16));
+    }
+
+    conflicts
+}
@@ -0,0 +1,14 @@
+use crate::computer::AdressingMode;
+
+/// Description of an opcode, generated at build time from `opcodes.csv`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Opcode {
+    /// Empty for opcodes the 65C02 does not define, which run as a one byte NOP
+    pub mnemonic: &'static str,
+    pub mode: AdressingMode,
+    /// Length of the instruction, operands included
+    pub bytes: u8,
+    pub cycles: u8,
+}
+
+include!(concat!(env!("OUT_DIR"), "/opcodes.rs"));
@@ -0,0 +1,61 @@
+use std::time::Duration;
+
+/// Frames per second of the emulated display
+pub const FRAME_RATE: u128 = 60;
+/// Lines per frame, blanking included, as NTSC
+pub const SCANLINES: u16 = 262;
+/// Real time taken by one frame, the budget the host has to emulate it
+pub const FRAME_TIME: Duration = Duration::from_nanos((1_000_000_000 / FRAME_RATE) as u64);
+
+/// Where the beam of the emulated display is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub frame: u64,
+    pub line: u16,
+    /// Cycles since the start of the line
+    pub cycle: u16,
+}
+
+/// Splits the emulated time into frames and scanlines, for video cards and
+/// anything else that needs to happen at a point of the frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Raster {
+    pub cycles_per_frame: u128,
+    pub scanlines: u16,
+}
+
+impl Raster {
+    pub fn new(clock_speed: u128) -> Self {
+        Raster { cycles_per_frame: clock_speed / FRAME_RATE, scanlines: SCANLINES }
+    }
+
+    /// The frame is cut in lines of whole cycles, the last one takes what is left
+    pub fn cycles_per_line(&self) -> u128 {
+        self.cycles_per_frame / self.scanlines as u128
+    }
+
+    pub fn position(&self, clock: u128) -> Position {
+        let cycle = clock % self.cycles_per_frame;
+        let line = (cycle / self.cycles_per_line()).min(self.scanlines as u128 - 1);
+        Position {
+            frame: (clock / self.cycles_per_frame) as u64,
+            line: line as u16,
+            cycle: (cycle - line * self.cycles_per_line()) as u16,
+        }
+    }
+
+    /// Cycle at which the frame after the one running at `clock` starts
+    pub fn next_frame(&self, clock: u128) -> u128 {
+        (clock / self.cycles_per_frame + 1) * self.cycles_per_frame
+    }
+
+    /// First cycle after `clock` at which `line` starts
+    pub fn next_line(&self, clock: u128, line: u16) -> u128 {
+        let start = clock / self.cycles_per_frame * self.cycles_per_frame + line as u128 * self.cycles_per_line();
+        if start > clock {
+            start
+        } else {
+            start + self.cycles_per_frame
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+/// Registers of the RTC card, all values in BCD
+pub const SECONDS: u16 = 0;
+pub const MINUTES: u16 = 1;
+pub const HOURS: u16 = 2;
+/// 1 for Sunday to 7 for Saturday
+pub const WEEKDAY: u16 = 3;
+pub const DATE: u16 = 4;
+pub const MONTH: u16 = 5;
+/// Year in the century
+pub const YEAR: u16 = 6;
+pub const CENTURY: u16 = 7;
+pub const ALARM_SECONDS: u16 = 8;
+pub const ALARM_MINUTES: u16 = 9;
+pub const ALARM_HOURS: u16 = 0xA;
+/// Bit 0 is set once the alarm time has passed, writing it back clears it
+pub const FLAGS: u16 = 0xB;
+pub const FLAG_ALARM: u8 = 1;
+
+/// Battery backed clock/calendar. It follows the emulated clock rather than
+/// the host, so it runs fast in fast forward and can be moved forward at will.
+#[derive(Clone, Debug)]
+pub struct Rtc {
+    /// Date and time at the first cycle
+    start: NaiveDateTime,
+    /// Time set by the program or skipped from the UI
+    offset: Duration,
+    alarm: [u8; 3],
+    flags: u8,
+    /// Time up to which the alarm was checked
+    alarm_checked: NaiveDateTime,
+}
+
+impl Rtc {
+    pub fn new(start: NaiveDateTime) -> Rtc {
+        Rtc {
+            start,
+            offset: Duration::zero(),
+            alarm: [0; 3],
+            flags: 0,
+            alarm_checked: start,
+        }
+    }
+
+    /// Date and time after `seconds` of emulated time
+    pub fn now(&self, seconds: u64) -> NaiveDateTime {
+        self.start + Duration::seconds(seconds as i64) + self.offset
+    }
+
+    /// Moves the clock forward without running the program in between.
+    /// An alarm due in the skipped time fires.
+    pub fn advance(&mut self, by: Duration) {
+        self.offset = self.offset + by;
+    }
+
+    /// Keeps the time when the emulated clock restarts from zero at reset
+    pub fn reset(&mut self, seconds: u64) {
+        self.offset = self.offset + Duration::seconds(seconds as i64);
+    }
+
+    /// Seconds between the RTC and `host`, with the alarm and flag registers, for snapshots
+    pub fn save(&self, seconds: u64, host: NaiveDateTime) -> (i64, [u8; 4]) {
+        let [s, m, h] = self.alarm;
+        ((self.now(seconds) - host).num_seconds(), [s, m, h, self.flags])
+    }
+
+    /// Restores a saved RTC. Like a battery backed clock, it kept running in between.
+    pub fn restore(&mut self, offset: i64, registers: [u8; 4], seconds: u64, host: NaiveDateTime) {
+        self.start = host - Duration::seconds(seconds as i64);
+        self.offset = Duration::seconds(offset);
+        let [s, m, h, flags] = registers;
+        self.alarm = [s, m, h];
+        self.flags = flags;
+        self.alarm_checked = self.now(seconds);
+    }
+
+    pub fn read(&mut self, reg: u16, seconds: u64) -> u8 {
+        let now = self.now(seconds);
+        match reg {
+            SECONDS => bcd(now.second()),
+            MINUTES => bcd(now.minute()),
+            HOURS => bcd(now.hour()),
+            WEEKDAY => bcd(now.weekday().number_from_sunday()),
+            DATE => bcd(now.day()),
+            MONTH => bcd(now.month()),
+            YEAR => bcd(now.year().rem_euclid(100) as u32),
+            CENTURY => bcd(now.year().div_euclid(100) as u32),
+            ALARM_SECONDS..=ALARM_HOURS => self.alarm[(reg - ALARM_SECONDS) as usize],
+            FLAGS => {
+                self.check_alarm(now);
+                self.flags
+            }
+            _ => 0,
+        }
+    }
+
+    pub fn write(&mut self, reg: u16, value: u8, seconds: u64) {
+        let now = self.now(seconds);
+        let v = from_bcd(value);
+        let set = match reg {
+            SECONDS => now.with_second(v),
+            MINUTES => now.with_minute(v),
+            HOURS => now.with_hour(v),
+            DATE => now.with_day(v),
+            MONTH => now.with_month(v),
+            YEAR => now.with_year(now.year() - now.year().rem_euclid(100) + v as i32),
+            CENTURY => now.with_year(v as i32 * 100 + now.year().rem_euclid(100)),
+            ALARM_SECONDS..=ALARM_HOURS => {
+                self.alarm[(reg - ALARM_SECONDS) as usize] = value;
+                self.alarm_checked = now;
+                None
+            }
+            FLAGS => {
+                self.flags &= !value;
+                None
+            }
+            // The weekday follows from the date
+            _ => None,
+        };
+        if let Some(time) = set {
+            self.offset = self.offset + (time - now);
+            self.alarm_checked = time;
+        }
+    }
+
+    /// Sets the alarm flag if the alarm time of day was reached since the last check
+    fn check_alarm(&mut self, now: NaiveDateTime) {
+        let [s, m, h] = self.alarm.map(from_bcd);
+        let Some(alarm) = NaiveTime::from_hms_opt(h, m, s) else {
+            return;
+        };
+        if now <= self.alarm_checked {
+            return;
+        }
+        let mut next = self.alarm_checked.date().and_time(alarm);
+        if next <= self.alarm_checked {
+            next = date_after(self.alarm_checked.date()).and_time(alarm);
+        }
+        if next <= now {
+            self.flags |= FLAG_ALARM;
+        }
+        self.alarm_checked = now;
+    }
+}
+
+fn date_after(date: NaiveDate) -> NaiveDate {
+    date.succ_opt().unwrap_or(date)
+}
+
+fn bcd(v: u32) -> u8 {
+    (((v / 10 % 10) << 4) | (v % 10)) as u8
+}
+
+fn from_bcd(v: u8) -> u32 {
+    (v >> 4) as u32 * 10 + (v & 0xF) as u32
+}
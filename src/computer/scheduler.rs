@@ -0,0 +1,61 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Something that happens at a given clock cycle
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Event {
+    /// Synchronise the emulated clock with real time
+    Throttle,
+    /// Timer `timer` of the card in `slot` expired
+    Card { slot: u16, timer: u8 },
+    /// Start of a frame of the emulated display
+    Frame,
+    /// The beam reached `line`, for the raster interrupt of the card in `slot`
+    Scanline { slot: u16, line: u16 },
+}
+
+/// Min-heap of the next events, so the CPU loop only compares the clock with
+/// the earliest one instead of asking every card after every instruction.
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    events: BinaryHeap<Reverse<(u128, u64, Event)>>,
+    /// Insertion counter, keeps events due on the same cycle in order
+    sequence: u64,
+}
+
+impl Scheduler {
+    /// Schedules `event` to happen at cycle `at`.
+    pub fn schedule(&mut self, at: u128, event: Event) {
+        self.sequence += 1;
+        self.events.push(Reverse((at, self.sequence, event)));
+    }
+
+    /// Removes all pending occurrences of `event`.
+    pub fn cancel(&mut self, event: Event) {
+        self.events.retain(|Reverse((_, _, e))| *e != event);
+    }
+
+    /// Cycle of the earliest pending event
+    pub fn next(&self) -> Option<u128> {
+        self.events.peek().map(|Reverse((at, _, _))| *at)
+    }
+
+    /// Takes the earliest event if it is due at `clock`, with the cycle it was due at.
+    pub fn pop_due(&mut self, clock: u128) -> Option<(u128, Event)> {
+        match self.events.peek() {
+            Some(Reverse((at, _, _))) if *at <= clock => self.events.pop().map(|Reverse((at, _, e))| (at, e)),
+            _ => None,
+        }
+    }
+
+    /// Pending events, earliest first
+    pub fn events(&self) -> Vec<(u128, Event)> {
+        let mut events: Vec<_> = self.events.iter().map(|Reverse(e)| *e).collect();
+        events.sort();
+        events.into_iter().map(|(at, _, e)| (at, e)).collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+}
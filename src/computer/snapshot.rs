@@ -0,0 +1,240 @@
+use crate::computer::{Card, CardType, Processor};
+use crate::error::Error;
+
+/// Start of every snapshot file, with the format version
+const MAGIC: &[u8; 8] = b"PLUSNAP4";
+
+/// State of the whole machine, enough to carry on where it stopped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    /// [`rom_hash`] of the ROM the machine was started with
+    pub rom_hash: u64,
+    /// [`disk_hash`] of the CF image file the machine was started with, the
+    /// snapshot carries the disk as the guest left it
+    pub disk_hash: u64,
+    pub processor: Processor,
+    pub memory: Vec<u8>,
+    pub disk: Vec<u8>,
+    pub lba: u32,
+    pub disk_cnt: u16,
+    pub command: u8,
+    pub cards: Vec<Card>,
+    /// Pending card timers as cycle, slot and timer
+    pub timers: Vec<(u128, u16, u8)>,
+    /// Seconds between the RTC and the host clock
+    pub rtc_offset: i64,
+    /// Alarm seconds, minutes, hours and flags of the RTC
+    pub rtc_registers: [u8; 4],
+    /// Variables of the scripted devices by slot, as saved by
+    /// [`Device::save`](crate::computer::device::Device::save). The scripts come from the project.
+    pub devices: Vec<(u16, String)>,
+    /// Serial output shown by the UI
+    pub output: Vec<u8>,
+}
+
+/// Whether `data` looks like a snapshot, from this version or another
+pub fn is_snapshot(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC[..MAGIC.len() - 1])
+}
+
+/// FNV-1a hash identifying a ROM image, stable across runs and builds
+pub fn rom_hash(data: &[u8]) -> u64 {
+    fnv1a(data)
+}
+
+/// Hash identifying a CF image, like [`rom_hash`]
+pub fn disk_hash(data: &[u8]) -> u64 {
+    fnv1a(data)
+}
+
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+impl Snapshot {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer(MAGIC.to_vec());
+        w.u64(self.rom_hash);
+        w.u64(self.disk_hash);
+        let p = &self.processor;
+        w.u8(p.flags);
+        w.u8(p.acc);
+        w.u8(p.rx);
+        w.u8(p.ry);
+        w.u16(p.pc);
+        w.u8(p.sp);
+        w.u128(p.clock);
+        w.u8(p.inst);
+        w.bytes(&self.memory);
+        w.bytes(&self.disk);
+        w.u32(self.lba);
+        w.u16(self.disk_cnt);
+        w.u8(self.command);
+        w.u32(self.cards.len() as u32);
+        for card in &self.cards {
+            w.u16(card.slot);
+            w.u8(card_code(&card.card_type));
+            w.u8(card.enabled as u8);
+        }
+        w.u32(self.timers.len() as u32);
+        for (at, slot, timer) in &self.timers {
+            w.u128(*at);
+            w.u16(*slot);
+            w.u8(*timer);
+        }
+        w.u64(self.rtc_offset as u64);
+        self.rtc_registers.iter().for_each(|&r| w.u8(r));
+        w.u32(self.devices.len() as u32);
+        for (slot, variables) in &self.devices {
+            w.u16(*slot);
+            w.bytes(variables.as_bytes());
+        }
+        w.bytes(&self.output);
+        w.0
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Snapshot, Error> {
+        let mut r = Reader(data);
+        if r.take(MAGIC.len())? != MAGIC {
+            return Err(Error::Snapshot("not a snapshot, or from another version".to_string()));
+        }
+        let rom_hash = r.u64()?;
+        let disk_hash = r.u64()?;
+        let processor = Processor {
+            flags: r.u8()?,
+            acc: r.u8()?,
+            rx: r.u8()?,
+            ry: r.u8()?,
+            pc: r.u16()?,
+            sp: r.u8()?,
+            clock: r.u128()?,
+            inst: r.u8()?,
+        };
+        let memory = r.bytes()?;
+        let disk = r.bytes()?;
+        let (lba, disk_cnt, command) = (r.u32()?, r.u16()?, r.u8()?);
+        let cards = (0..r.u32()?)
+            .map(|_| Ok(Card { slot: r.u16()?, card_type: card_type(r.u8()?)?, enabled: r.u8()? != 0 }))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let timers = (0..r.u32()?)
+            .map(|_| Ok((r.u128()?, r.u16()?, r.u8()?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let rtc_offset = r.u64()? as i64;
+        let rtc_registers = [r.u8()?, r.u8()?, r.u8()?, r.u8()?];
+        let devices = (0..r.u32()?)
+            .map(|_| {
+                let slot = r.u16()?;
+                let variables = String::from_utf8(r.bytes()?).map_err(|_| Error::Snapshot(format!("device variables in slot {} are not text", slot)))?;
+                Ok((slot, variables))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let output = r.bytes()?;
+
+        if memory.len() != crate::rom::ADDRESS_SPACE {
+            return Err(Error::Snapshot(format!("{} bytes of memory", memory.len())));
+        }
+        Ok(Snapshot { rom_hash, disk_hash, processor, memory, disk, lba, disk_cnt, command, cards, timers, rtc_offset, rtc_registers, devices, output })
+    }
+}
+
+fn card_code(card_type: &CardType) -> u8 {
+    match card_type {
+        CardType::CF => 0,
+        CardType::Serial => 1,
+        CardType::IO => 2,
+        CardType::Ram => 3,
+        CardType::None => 4,
+        CardType::Rtc => 5,
+        CardType::Trap => 6,
+        CardType::Irq => 7,
+        CardType::Device => 8,
+    }
+}
+
+fn card_type(code: u8) -> Result<CardType, Error> {
+    Ok(match code {
+        0 => CardType::CF,
+        1 => CardType::Serial,
+        2 => CardType::IO,
+        3 => CardType::Ram,
+        4 => CardType::None,
+        5 => CardType::Rtc,
+        6 => CardType::Trap,
+        7 => CardType::Irq,
+        8 => CardType::Device,
+        _ => return Err(Error::Snapshot(format!("unknown card type {}", code))),
+    })
+}
+
+/// Little endian encoder
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, v: u8) {
+        self.0.push(v);
+    }
+
+    fn u16(&mut self, v: u16) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn u64(&mut self, v: u64) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn u128(&mut self, v: u128) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    /// Length prefixed bytes
+    fn bytes(&mut self, v: &[u8]) {
+        self.u32(v.len() as u32);
+        self.0.extend_from_slice(v);
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < len {
+            return Err(Error::Snapshot("the file is truncated".to_string()));
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        Ok(self.take(N)?.try_into().unwrap_or([0; N]))
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, Error> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    fn u128(&mut self) -> Result<u128, Error> {
+        self.array().map(u128::from_le_bytes)
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, Error> {
+        let len = self.u32()? as usize;
+        Ok(self.take(len)?.to_vec())
+    }
+}
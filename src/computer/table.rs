@@ -0,0 +1,410 @@
+use std::sync::OnceLock;
+
+use crate::computer::opcodes::{Opcode, OPCODES};
+use crate::computer::{AdressingMode, Computer, ComputerMessage, Processor, FLAG_B, FLAG_C, FLAG_D, FLAG_I, FLAG_N, FLAG_O, FLAG_Z};
+
+/// Bit 5 of the status register, not stored by the processor and always read as set
+const FLAG_UNUSED: u8 = 0x20;
+
+/// What an instruction works on, decoded from its addressing mode before it runs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operand {
+    Implied,
+    Accumulator,
+    /// Effective address, the operand byte itself for immediate instructions
+    Address(u16),
+    /// Destination of the branch when it is taken
+    Branch(u16),
+    /// Zero page byte tested by BBR and BBS, and the destination of the branch
+    BitBranch { zp: u16, target: u16 },
+}
+
+/// Runs an instruction on its operand, with PC already past the instruction
+type Operation = fn(&mut Computer, Operand);
+
+/// Runs the instruction whose opcode was just fetched, the table driven core.
+///
+/// Unlike the legacy core, which decodes operands again in every handler, the
+/// operand is decoded once from the addressing mode in [`OPCODES`] and the
+/// operation is looked up by opcode. It shares nothing with the legacy handlers
+/// but the bus, so `plu lockstep` can find where the two disagree.
+pub fn execute(computer: &mut Computer, opcode: u8) {
+    let op = &OPCODES[opcode as usize];
+    let pc = computer.processor.pc;
+    let operand = decode(computer, op, pc);
+    computer.processor.pc = pc.wrapping_add(op.bytes as u16);
+    operations()[opcode as usize](computer, operand);
+}
+
+fn operations() -> &'static [Operation; 256] {
+    static OPERATIONS: OnceLock<[Operation; 256]> = OnceLock::new();
+    OPERATIONS.get_or_init(|| std::array::from_fn(|opcode| operation(OPCODES[opcode].mnemonic)))
+}
+
+fn decode(c: &mut Computer, op: &Opcode, pc: u16) -> Operand {
+    let operand = pc.wrapping_add(1);
+    let (x, y) = (c.processor.rx, c.processor.ry);
+    match op.mode {
+        AdressingMode::Implied | AdressingMode::None => Operand::Implied,
+        AdressingMode::Accumulator => Operand::Accumulator,
+        AdressingMode::Immediate => Operand::Address(operand),
+        AdressingMode::ZeroPage => Operand::Address(c.read(operand) as u16),
+        AdressingMode::ZeroPageX => Operand::Address(c.read(operand).wrapping_add(x) as u16),
+        AdressingMode::ZeroPageY => Operand::Address(c.read(operand).wrapping_add(y) as u16),
+        AdressingMode::Absolute => Operand::Address(word(c, operand)),
+        AdressingMode::AbsoluteX => Operand::Address(word(c, operand).wrapping_add(x as u16)),
+        AdressingMode::AbsoluteY => Operand::Address(word(c, operand).wrapping_add(y as u16)),
+        AdressingMode::Indirect => {
+            let pointer = word(c, operand);
+            Operand::Address(word(c, pointer))
+        }
+        // JMP (abs,X), the only three byte instruction of the mode
+        AdressingMode::IndirectX if op.bytes == 3 => {
+            let pointer = word(c, operand).wrapping_add(x as u16);
+            Operand::Address(word(c, pointer))
+        }
+        AdressingMode::IndirectX => {
+            let pointer = c.read(operand).wrapping_add(x);
+            Operand::Address(zero_page_word(c, pointer))
+        }
+        AdressingMode::IndirectY => {
+            let pointer = c.read(operand);
+            Operand::Address(zero_page_word(c, pointer).wrapping_add(y as u16))
+        }
+        AdressingMode::ZeroPageIndirect => {
+            let pointer = c.read(operand);
+            Operand::Address(zero_page_word(c, pointer))
+        }
+        AdressingMode::Relative => {
+            let offset = c.read(operand);
+            Operand::Branch(branch_target(pc.wrapping_add(2), offset))
+        }
+        AdressingMode::ZeroPageRelative => {
+            let zp = c.read(operand) as u16;
+            let offset = c.read(pc.wrapping_add(2));
+            Operand::BitBranch { zp, target: branch_target(pc.wrapping_add(3), offset) }
+        }
+    }
+}
+
+fn word(c: &mut Computer, addr: u16) -> u16 {
+    u16::from_le_bytes([c.read(addr), c.read(addr.wrapping_add(1))])
+}
+
+/// Pointers in the zero page wrap around to its start
+fn zero_page_word(c: &mut Computer, pointer: u8) -> u16 {
+    u16::from_le_bytes([c.read(pointer as u16), c.read(pointer.wrapping_add(1) as u16)])
+}
+
+fn branch_target(next: u16, offset: u8) -> u16 {
+    next.wrapping_add(offset as i8 as u16)
+}
+
+fn operation(mnemonic: &str) -> Operation {
+    match mnemonic {
+        "ADC" => adc,
+        "AND" => |c, o| logical(c, o, |a, v| a & v),
+        "ASL" => |c, o| modify(c, o, |flags, v| {
+            set(flags, FLAG_C, v & 0x80 != 0);
+            v << 1
+        }),
+        "BCC" => |c, o| branch_if(c, o, FLAG_C, false),
+        "BCS" => |c, o| branch_if(c, o, FLAG_C, true),
+        "BEQ" => |c, o| branch_if(c, o, FLAG_Z, true),
+        "BIT" => bit,
+        "BMI" => |c, o| branch_if(c, o, FLAG_N, true),
+        "BNE" => |c, o| branch_if(c, o, FLAG_Z, false),
+        "BPL" => |c, o| branch_if(c, o, FLAG_N, false),
+        "BRA" => |c, o| branch(c, o, true),
+        "BRK" => brk,
+        "BVC" => |c, o| branch_if(c, o, FLAG_O, false),
+        "BVS" => |c, o| branch_if(c, o, FLAG_O, true),
+        "CLC" => |c, _| c.processor.flags &= !FLAG_C,
+        "CLD" => |c, _| c.processor.flags &= !FLAG_D,
+        "CLI" => |c, _| c.processor.flags &= !FLAG_I,
+        "CLV" => |c, _| c.processor.flags &= !FLAG_O,
+        "CMP" => |c, o| compare(c, o, c.processor.acc),
+        "CPX" => |c, o| compare(c, o, c.processor.rx),
+        "CPY" => |c, o| compare(c, o, c.processor.ry),
+        "DEC" => |c, o| modify(c, o, |_, v| v.wrapping_sub(1)),
+        "DEX" => |c, _| c.processor.rx = nz(&mut c.processor.flags, c.processor.rx.wrapping_sub(1)),
+        "DEY" => |c, _| c.processor.ry = nz(&mut c.processor.flags, c.processor.ry.wrapping_sub(1)),
+        "EOR" => |c, o| logical(c, o, |a, v| a ^ v),
+        "INC" => |c, o| modify(c, o, |_, v| v.wrapping_add(1)),
+        "INX" => |c, _| c.processor.rx = nz(&mut c.processor.flags, c.processor.rx.wrapping_add(1)),
+        "INY" => |c, _| c.processor.ry = nz(&mut c.processor.flags, c.processor.ry.wrapping_add(1)),
+        "JMP" => |c, o| c.processor.pc = address(o),
+        "JSR" => jsr,
+        "LDA" => |c, o| c.processor.acc = load_nz(c, o),
+        "LDX" => |c, o| c.processor.rx = load_nz(c, o),
+        "LDY" => |c, o| c.processor.ry = load_nz(c, o),
+        "LSR" => |c, o| modify(c, o, |flags, v| {
+            set(flags, FLAG_C, v & 1 != 0);
+            v >> 1
+        }),
+        "NOP" | "" => |_, _| {},
+        "ORA" => |c, o| logical(c, o, |a, v| a | v),
+        "PHA" => |c, _| push(c, c.processor.acc),
+        "PHP" => |c, _| push(c, c.processor.flags | FLAG_B | FLAG_UNUSED),
+        "PHX" => |c, _| push(c, c.processor.rx),
+        "PHY" => |c, _| push(c, c.processor.ry),
+        "PLA" => |c, _| c.processor.acc = pull_nz(c),
+        "PLP" => |c, _| c.processor.flags = pull(c) | FLAG_B | FLAG_UNUSED,
+        "PLX" => |c, _| c.processor.rx = pull_nz(c),
+        "PLY" => |c, _| c.processor.ry = pull_nz(c),
+        "ROL" => |c, o| modify(c, o, |flags, v| {
+            let carry = *flags & FLAG_C;
+            set(flags, FLAG_C, v & 0x80 != 0);
+            v << 1 | carry
+        }),
+        "ROR" => |c, o| modify(c, o, |flags, v| {
+            let carry = *flags & FLAG_C;
+            set(flags, FLAG_C, v & 1 != 0);
+            v >> 1 | carry << 7
+        }),
+        "RTI" => rti,
+        "RTS" => |c, _| {
+            let addr = u16::from_le_bytes([pull(c), pull(c)]);
+            c.processor.pc = addr.wrapping_add(1);
+        },
+        "SBC" => sbc,
+        "SEC" => |c, _| c.processor.flags |= FLAG_C,
+        "SED" => |c, _| c.processor.flags |= FLAG_D,
+        "SEI" => |c, _| c.processor.flags |= FLAG_I,
+        "STA" => |c, o| c.write(address(o), c.processor.acc),
+        "STP" => stp,
+        "STX" => |c, o| c.write(address(o), c.processor.rx),
+        "STY" => |c, o| c.write(address(o), c.processor.ry),
+        "STZ" => |c, o| c.write(address(o), 0),
+        "TAX" => |c, _| c.processor.rx = nz(&mut c.processor.flags, c.processor.acc),
+        "TAY" => |c, _| c.processor.ry = nz(&mut c.processor.flags, c.processor.acc),
+        "TRB" => |c, o| test_bits(c, o, |v, a| v & !a),
+        "TSB" => |c, o| test_bits(c, o, |v, a| v | a),
+        "TSX" => |c, _| c.processor.rx = nz(&mut c.processor.flags, c.processor.sp),
+        "TXA" => |c, _| c.processor.acc = nz(&mut c.processor.flags, c.processor.rx),
+        "TXS" => |c, _| c.processor.sp = c.processor.rx,
+        "TYA" => |c, _| c.processor.acc = nz(&mut c.processor.flags, c.processor.ry),
+        "WAI" => wai,
+        m if m.starts_with("BBR") => |c, o| bit_branch(c, o, false),
+        m if m.starts_with("BBS") => |c, o| bit_branch(c, o, true),
+        m if m.starts_with("RMB") => |c, o| change_bit(c, o, false),
+        m if m.starts_with("SMB") => |c, o| change_bit(c, o, true),
+        // A mnemonic of opcodes.csv left out above, not something to paper over
+        _ => panic!("no operation for {}", mnemonic),
+    }
+}
+
+fn address(operand: Operand) -> u16 {
+    match operand {
+        Operand::Address(addr) => addr,
+        _ => unreachable!("{:?} has no address", operand),
+    }
+}
+
+fn set(flags: &mut u8, flag: u8, on: bool) {
+    if on {
+        *flags |= flag;
+    } else {
+        *flags &= !flag;
+    }
+}
+
+/// Sets N and Z from `value`, and returns it
+fn nz(flags: &mut u8, value: u8) -> u8 {
+    set(flags, FLAG_N, value & 0x80 != 0);
+    set(flags, FLAG_Z, value == 0);
+    value
+}
+
+fn load(c: &mut Computer, operand: Operand) -> u8 {
+    match operand {
+        Operand::Accumulator => c.processor.acc,
+        _ => c.read(address(operand)),
+    }
+}
+
+fn load_nz(c: &mut Computer, operand: Operand) -> u8 {
+    let value = load(c, operand);
+    nz(&mut c.processor.flags, value)
+}
+
+/// Read, modify and write back the accumulator or memory, setting N and Z from the result
+fn modify(c: &mut Computer, operand: Operand, change: fn(&mut u8, u8) -> u8) {
+    let value = load(c, operand);
+    let result = change(&mut c.processor.flags, value);
+    nz(&mut c.processor.flags, result);
+    match operand {
+        Operand::Accumulator => c.processor.acc = result,
+        _ => c.write(address(operand), result),
+    }
+}
+
+/// RMB and SMB read, modify and write back memory without touching the flags
+fn change_bit(c: &mut Computer, operand: Operand, set: bool) {
+    let (addr, bit) = (address(operand), memory_bit(c));
+    let value = c.read(addr);
+    c.write(addr, if set { value | bit } else { value & !bit });
+}
+
+fn logical(c: &mut Computer, operand: Operand, op: fn(u8, u8) -> u8) {
+    let value = load(c, operand);
+    let result = op(c.processor.acc, value);
+    c.processor.acc = nz(&mut c.processor.flags, result);
+}
+
+fn compare(c: &mut Computer, operand: Operand, register: u8) {
+    let value = load(c, operand);
+    set(&mut c.processor.flags, FLAG_C, register >= value);
+    nz(&mut c.processor.flags, register.wrapping_sub(value));
+}
+
+fn bit(c: &mut Computer, operand: Operand) {
+    let value = load(c, operand);
+    let p = &mut c.processor;
+    set(&mut p.flags, FLAG_Z, p.acc & value == 0);
+    // BIT #imm has no memory bits to copy
+    if OPCODES[p.inst as usize].mode != AdressingMode::Immediate {
+        set(&mut p.flags, FLAG_N, value & 0x80 != 0);
+        set(&mut p.flags, FLAG_O, value & 0x40 != 0);
+    }
+}
+
+/// Sets Z like BIT does, then writes back the value changed by `change`
+fn test_bits(c: &mut Computer, operand: Operand, change: fn(u8, u8) -> u8) {
+    let addr = address(operand);
+    let value = c.read(addr);
+    let acc = c.processor.acc;
+    set(&mut c.processor.flags, FLAG_Z, value & acc == 0);
+    c.write(addr, change(value, acc));
+}
+
+/// Bit of memory changed or tested by the RMB, SMB, BBR and BBS in the high nibble of the opcode
+fn memory_bit(c: &Computer) -> u8 {
+    1 << ((c.processor.inst >> 4) & 7)
+}
+
+fn adc(c: &mut Computer, operand: Operand) {
+    let value = load(c, operand);
+    if c.processor.flags & FLAG_D == 0 {
+        add(&mut c.processor, value);
+        return;
+    }
+    // Decimal mode of the 65C02, with N, V and Z valid
+    let p = &mut c.processor;
+    let (acc, carry) = (p.acc as u16, (p.flags & FLAG_C) as u16);
+    let mut low = (acc & 0x0F) + (value as u16 & 0x0F) + carry;
+    if low >= 0x0A {
+        low = ((low + 0x06) & 0x0F) + 0x10;
+    }
+    let signed = (acc & 0xF0) as u8 as i8 as i16 + (value & 0xF0) as i8 as i16 + low as i16;
+    set(&mut p.flags, FLAG_O, !(-128..=127).contains(&signed));
+    let mut sum = (acc & 0xF0) + (value as u16 & 0xF0) + low;
+    if sum >= 0xA0 {
+        sum += 0x60;
+    }
+    set(&mut p.flags, FLAG_C, sum >= 0x100);
+    p.acc = nz(&mut p.flags, sum as u8);
+}
+
+fn sbc(c: &mut Computer, operand: Operand) {
+    let value = load(c, operand);
+    let p = &mut c.processor;
+    let (acc, borrow) = (p.acc, 1 - (p.flags & FLAG_C) as i16);
+    // Carry and overflow are those of the binary subtraction in both modes
+    add(p, !value);
+    if p.flags & FLAG_D != 0 {
+        let low = (acc & 0x0F) as i16 - (value & 0x0F) as i16 - borrow;
+        let mut difference = acc as i16 - value as i16 - borrow;
+        if difference < 0 {
+            difference -= 0x60;
+        }
+        if low < 0 {
+            difference -= 0x06;
+        }
+        p.acc = nz(&mut p.flags, difference as u8);
+    }
+}
+
+/// Binary addition with carry into the accumulator
+fn add(p: &mut Processor, value: u8) {
+    let sum = p.acc as u16 + value as u16 + (p.flags & FLAG_C) as u16;
+    let result = sum as u8;
+    set(&mut p.flags, FLAG_C, sum > 0xFF);
+    set(&mut p.flags, FLAG_O, (p.acc ^ result) & (value ^ result) & 0x80 != 0);
+    p.acc = nz(&mut p.flags, result);
+}
+
+fn branch(c: &mut Computer, operand: Operand, taken: bool) {
+    if let (true, Operand::Branch(target)) = (taken, operand) {
+        c.processor.pc = target;
+    }
+}
+
+fn branch_if(c: &mut Computer, operand: Operand, flag: u8, set: bool) {
+    let taken = (c.processor.flags & flag != 0) == set;
+    branch(c, operand, taken);
+}
+
+fn bit_branch(c: &mut Computer, operand: Operand, set: bool) {
+    let Operand::BitBranch { zp, target } = operand else {
+        unreachable!("{:?} is not a bit branch", operand);
+    };
+    let bit = memory_bit(c);
+    if (c.read(zp) & bit != 0) == set {
+        c.processor.pc = target;
+    }
+}
+
+fn push(c: &mut Computer, value: u8) {
+    c.write(0x100 + c.processor.sp as u16, value);
+    c.processor.sp = c.processor.sp.wrapping_sub(1);
+}
+
+fn pull(c: &mut Computer) -> u8 {
+    c.processor.sp = c.processor.sp.wrapping_add(1);
+    c.read(0x100 + c.processor.sp as u16)
+}
+
+fn pull_nz(c: &mut Computer) -> u8 {
+    let value = pull(c);
+    nz(&mut c.processor.flags, value)
+}
+
+fn push_pc(c: &mut Computer, pc: u16) {
+    let [low, high] = pc.to_le_bytes();
+    push(c, high);
+    push(c, low);
+}
+
+fn jsr(c: &mut Computer, operand: Operand) {
+    // The return address pushed is the last byte of the JSR
+    push_pc(c, c.processor.pc.wrapping_sub(1));
+    c.processor.pc = address(operand);
+}
+
+fn brk(c: &mut Computer, _: Operand) {
+    // BRK skips the signature byte after it
+    push_pc(c, c.processor.pc.wrapping_add(1));
+    push(c, c.processor.flags | FLAG_B | FLAG_UNUSED);
+    c.processor.flags = (c.processor.flags | FLAG_I) & !FLAG_D;
+    c.processor.pc = word(c, 0xFFFE);
+}
+
+fn rti(c: &mut Computer, _: Operand) {
+    c.processor.flags = pull(c) | FLAG_B | FLAG_UNUSED;
+    c.processor.pc = u16::from_le_bytes([pull(c), pull(c)]);
+}
+
+/// Stays on WAI until a card asserts IRQ
+fn wai(c: &mut Computer, _: Operand) {
+    if c.irq_line.is_none() {
+        c.processor.pc = c.processor.pc.wrapping_sub(1);
+    }
+}
+
+/// Stays on STP and pauses the computer until it is reset
+fn stp(c: &mut Computer, _: Operand) {
+    c.processor.pc = c.processor.pc.wrapping_sub(1);
+    c.paused = true;
+    let _ = c.tx.send(ComputerMessage::Info(format!("Stopped by STP at ${:04X}, reset to go on", c.processor.pc)));
+}
@@ -0,0 +1,145 @@
+use std::collections::VecDeque;
+
+/// Width used until the output pane is drawn
+pub const COLUMNS: usize = 80;
+/// Number of rows kept before the oldest ones are dropped
+pub const SCROLLBACK: usize = 10_000;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Row {
+    bytes: Vec<u8>,
+    /// The row continues the line started on the previous row
+    wrapped: bool,
+}
+
+/// Output of the serial card, stored as a grid of bytes `columns` wide.
+///
+/// Long lines are wrapped when written, so drawing only touches the rows on screen.
+#[derive(Clone, Debug)]
+pub struct Console {
+    columns: usize,
+    rows: VecDeque<Row>,
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Self::new(COLUMNS)
+    }
+}
+
+impl Console {
+    pub fn new(columns: usize) -> Self {
+        Self {
+            columns: columns.max(1),
+            rows: VecDeque::from([Row::default()]),
+        }
+    }
+
+    /// Writes bytes received from the serial card. Returns the number of rows
+    /// dropped from the top of the scrollback.
+    pub fn write(&mut self, bytes: &[u8]) -> usize {
+        for &val in bytes {
+            match val {
+                0x0D | 0x0A => self.rows.push_back(Row::default()),
+                0x08 => self.backspace(),
+                _ => {
+                    if self.last().bytes.len() >= self.columns {
+                        self.rows.push_back(Row { bytes: vec![], wrapped: true });
+                    }
+                    self.last().bytes.push(val);
+                }
+            }
+        }
+        self.trim()
+    }
+
+    fn backspace(&mut self) {
+        if self.rows.len() > 1 {
+            let last = &self.rows[self.rows.len() - 1];
+            if last.wrapped && last.bytes.is_empty() {
+                self.rows.pop_back();
+            }
+        }
+        self.last().bytes.pop();
+    }
+
+    fn last(&mut self) -> &mut Row {
+        // There is always at least one row
+        let last = self.rows.len() - 1;
+        &mut self.rows[last]
+    }
+
+    fn trim(&mut self) -> usize {
+        let mut dropped = 0;
+        while self.rows.len() > SCROLLBACK {
+            self.rows.pop_front();
+            dropped += 1;
+        }
+        dropped
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Wraps the content again for a new width. Only costly when the width changes.
+    pub fn set_columns(&mut self, columns: usize) {
+        let columns = columns.max(1);
+        if columns == self.columns {
+            return;
+        }
+        let lines = self.lines();
+        self.columns = columns;
+        self.rows = VecDeque::from([Row::default()]);
+        let mut first = true;
+        for line in lines {
+            if !first {
+                self.rows.push_back(Row::default());
+            }
+            first = false;
+            for chunk in line.chunks(columns) {
+                if !self.last().bytes.is_empty() {
+                    self.rows.push_back(Row { bytes: vec![], wrapped: true });
+                }
+                self.last().bytes.extend_from_slice(chunk);
+            }
+        }
+        self.trim();
+    }
+
+    /// Number of rows, including the one holding the cursor
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.len() == 1 && self.rows[0].bytes.is_empty()
+    }
+
+    /// Returns up to `count` rows starting at row `start`.
+    pub fn rows(&self, start: usize, count: usize) -> impl Iterator<Item = &[u8]> {
+        self.rows.iter().skip(start).take(count).map(|r| r.bytes.as_slice())
+    }
+
+    /// Column of the cursor on the last row
+    pub fn cursor(&self) -> usize {
+        self.rows.back().map_or(0, |r| r.bytes.len())
+    }
+
+    /// Returns the content split at line breaks written by the guest, ignoring wrapping.
+    pub fn lines(&self) -> Vec<Vec<u8>> {
+        let mut lines: Vec<Vec<u8>> = vec![];
+        for row in &self.rows {
+            match lines.last_mut() {
+                Some(line) if row.wrapped => line.extend_from_slice(&row.bytes),
+                _ => lines.push(row.bytes.clone()),
+            }
+        }
+        lines
+    }
+
+    /// Returns the content as text, one line per line written by the guest.
+    pub fn text(&self) -> String {
+        self.lines().iter().map(|l| l.iter().map(|&b| b as char).collect::<String>()).collect::<Vec<_>>().join("\n")
+    }
+}
@@ -0,0 +1,47 @@
+use crate::error::SECTOR_SIZE;
+
+/// Example machine run by `plu demo`, also used as an end-to-end test of the cards.
+#[derive(Debug)]
+pub struct Demo {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// 256 byte ROM image, assembled from the source next to it in `roms/`
+    pub rom: &'static [u8],
+    /// Builds the CF card image the ROM expects
+    pub disk: fn() -> Vec<u8>,
+}
+
+/// Text stored in sector 1 of the disk of the `cf-read` demo
+pub const CF_MESSAGE: &[u8] = b"Read from sector 1 of the CF card\r";
+
+pub const DEMOS: [Demo; 3] = [
+    Demo {
+        name: "hello-serial",
+        description: "prints a greeting on the serial card",
+        rom: include_bytes!("../roms/hello-serial.bin"),
+        disk: Vec::new,
+    },
+    Demo {
+        name: "via-blink",
+        description: "blinks port B of the VIA with timer 1, echoed on the serial card",
+        rom: include_bytes!("../roms/via-blink.bin"),
+        disk: Vec::new,
+    },
+    Demo {
+        name: "cf-read",
+        description: "prints a sector read from the CF card",
+        rom: include_bytes!("../roms/cf-read.bin"),
+        disk: cf_disk,
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Demo> {
+    DEMOS.iter().find(|d| d.name == name)
+}
+
+/// Two sectors, the second one holding [`CF_MESSAGE`] followed by zeros
+fn cf_disk() -> Vec<u8> {
+    let mut disk = vec![0; 2 * SECTOR_SIZE];
+    disk[SECTOR_SIZE..SECTOR_SIZE + CF_MESSAGE.len()].copy_from_slice(CF_MESSAGE);
+    disk
+}
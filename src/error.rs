@@ -0,0 +1,73 @@
+use std::{error, fmt, io};
+
+use crate::rom::ADDRESS_SPACE;
+
+/// Size of a CF card sector.
+pub const SECTOR_SIZE: usize = 512;
+
+/// Errors reported to the user.
+#[derive(Debug)]
+pub enum Error {
+    /// A file could not be read or written.
+    File { path: String, source: io::Error },
+    /// A file could not be created or written.
+    Write { path: String, source: io::Error },
+    /// The ROM image is empty.
+    EmptyRom,
+    /// The ROM image does not fit in the address space.
+    RomTooLarge(usize),
+    /// The part of the ROM image to load is empty or larger than the image.
+    RomSize { size: usize, file: usize },
+    /// The ROM image goes past $FFFF when loaded at the requested base address.
+    RomDoesNotFit { base: u16, size: usize },
+    /// The command line could not be understood.
+    Argument(String),
+    /// The CF card image is not made of whole sectors.
+    DiskSize(usize),
+    /// The project file could not be understood.
+    Project { path: String, msg: String },
+    /// A build command could not be run or failed, with its output.
+    Build { command: String, output: String },
+    /// A saved machine state could not be restored.
+    Snapshot(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::File { path, source } => write!(f, "could not read {}: {}", path, source),
+            Error::Write { path, source } => write!(f, "could not write {}: {}", path, source),
+            Error::EmptyRom => write!(f, "the ROM image is empty"),
+            Error::RomTooLarge(size) => write!(f, "the ROM image is {} bytes, it must be at most {} bytes; use --rom-size to only load the top of the image", size, ADDRESS_SPACE),
+            Error::RomSize { size, file } => write!(f, "cannot load {} bytes from a {} byte ROM image", size, file),
+            Error::RomDoesNotFit { base, size } => write!(f, "a {} byte ROM image loaded at ${:04X} goes past the end of memory", size, base),
+            Error::Argument(msg) => write!(f, "{}", msg),
+            Error::DiskSize(size) => write!(f, "the CF card image is {} bytes, it must be a multiple of {} bytes", size, SECTOR_SIZE),
+            Error::Project { path, msg } => write!(f, "invalid project {}: {}", path, msg),
+            Error::Build { command, output } => write!(f, "{} failed:\n{}", command, output),
+            Error::Snapshot(msg) => write!(f, "invalid snapshot: {}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::File { source, .. } | Error::Write { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Checks that a CF card image is made of whole sectors. An empty image means no card.
+pub fn validate_disk(data: &[u8]) -> Result<(), Error> {
+    if !data.len().is_multiple_of(SECTOR_SIZE) {
+        return Err(Error::DiskSize(data.len()));
+    }
+    Ok(())
+}
+
+/// Reads a whole file, keeping its path in the error.
+pub fn read_file(path: &str) -> Result<Vec<u8>, Error> {
+    std::fs::read(path).map_err(|source| Error::File { path: path.to_string(), source })
+}
@@ -1,16 +1,109 @@
-use crate::{app::{App, AppResult, Tab}, computer};
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crate::{app::{App, AppResult, InputMode, Tab}, computer};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 
 /// Handles the key events and updates the state of [`App`].
 pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
+    if key_event.kind == KeyEventKind::Release {
+        // Only reported by terminals supporting keyboard enhancement
+        if key_event.code == KeyCode::F(8) {
+            app.set_fast_forward(false);
+        }
+        return Ok(());
+    }
+
+    if app.error.is_some() {
+        // The error modal only accepts its own buttons
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Enter => app.error = None,
+            KeyCode::F(2) => app.quit(),
+            KeyCode::F(4) => {
+                app.error = None;
+                let _ = app.tx.send(computer::ControllerMessage::Reset);
+            }
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    if app.breakpoint.is_some() {
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Enter => app.breakpoint = None,
+            KeyCode::F(2) => app.quit(),
+            KeyCode::F(7) => {
+                app.breakpoint = None;
+                let _ = app.tx.send(computer::ControllerMessage::TogglePause);
+            }
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    if let Some(error) = &app.build_error {
+        let last = error.lines().count().saturating_sub(1) as u16;
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Enter => app.build_error = None,
+            KeyCode::F(2) => app.quit(),
+            KeyCode::F(9) => app.rebuild(),
+            KeyCode::Up => app.build_error_scroll = app.build_error_scroll.saturating_sub(1),
+            KeyCode::Down => app.build_error_scroll = (app.build_error_scroll + 1).min(last),
+            KeyCode::PageUp => app.build_error_scroll = app.build_error_scroll.saturating_sub(8),
+            KeyCode::PageDown => app.build_error_scroll = (app.build_error_scroll + 8).min(last),
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    if let Some(cards) = &app.cards {
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Enter => app.cards = None,
+            KeyCode::Up => app.card_selected = app.card_selected.saturating_sub(1),
+            KeyCode::Down => app.card_selected = (app.card_selected + 1).min(cards.len().saturating_sub(1)),
+            KeyCode::Char(' ') => app.toggle_card(),
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    if let Some(scroll) = app.disk_matches_scroll {
+        let last = app.search.as_ref().map_or(0, |s| s.disk_matches.len().saturating_sub(1));
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Enter => app.disk_matches_scroll = None,
+            KeyCode::Up => app.disk_matches_scroll = Some(scroll.saturating_sub(1)),
+            KeyCode::Down => app.disk_matches_scroll = Some((scroll + 1).min(last)),
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    if app.usage.is_some() {
+        if matches!(key_event.code, KeyCode::Esc | KeyCode::Enter) {
+            app.usage = None;
+        }
+        return Ok(());
+    }
+
+    if app.input.mode != InputMode::Normal {
+        match key_event.code {
+            KeyCode::Enter if app.input.mode == InputMode::Eval => app.submit_eval(),
+            KeyCode::Enter => app.submit_search(),
+            KeyCode::Esc => app.input.mode = InputMode::Normal,
+            KeyCode::Backspace => {
+                app.input.value.pop();
+            }
+            KeyCode::Char(c) => app.input.value.push(c),
+            _ => {}
+        }
+        app.input.cursor_position = app.input.value.len() as u16;
+        return Ok(());
+    }
+
     match key_event.code {
-        KeyCode::Esc => {
-            match app.current_tab {
-                Tab::Main => {
-                    let _ = app.tx.send(computer::ControllerMessage::SendChar(0x1B as char));
-                },
-                _ => {},
-            };
+        KeyCode::Esc if app.current_tab == Tab::Main => {
+            app.send_char(0x1B);
+        }
+        KeyCode::Esc if app.current_tab == Tab::Memory => {
+            app.search = None;
+            app.eval = None;
         }
         
         // Counter handlers
@@ -18,68 +111,58 @@ pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
             app.current_tab = match app.current_tab {
                 Tab::Main => Tab::Help,
                 Tab::Memory => Tab::Help,
+                Tab::Disassembly => Tab::Help,
                 Tab::Help => Tab::Main,
             }
         }
         KeyCode::F(2) => {
             app.quit();
         }
+        KeyCode::F(9) => app.rebuild(),
+        KeyCode::F(10) if key_event.modifiers.contains(KeyModifiers::SHIFT) => app.advance_rtc(chrono::Duration::days(1)),
+        KeyCode::F(10) => app.advance_rtc(chrono::Duration::hours(1)),
+        KeyCode::F(12) => app.set_overflow(),
 
         KeyCode::F(3) => {
             app.current_tab = match app.current_tab {
                 Tab::Main => Tab::Memory,
-                Tab::Memory => Tab::Main,
+                Tab::Memory => Tab::Disassembly,
+                Tab::Disassembly => Tab::Main,
                 Tab::Help => Tab::Main,
             }
         }
 
         KeyCode::F(4) => {
             match app.current_tab {
-                Tab::Memory | Tab::Main => {
+                Tab::Memory | Tab::Main | Tab::Disassembly => {
                     let _ = app.tx.send(crate::computer::ControllerMessage::Reset);
                 },
                 _ => {}
             }
         }
 
-        KeyCode::F(5) => {
-            match app.current_tab {
-                Tab::Main => {
-                    app.log_level = app.log_level.saturating_sub(1);
-                    let _ = app.tx.send(crate::computer::ControllerMessage::SetDebug(app.log_level));
-                },
-                _ => {}
-            }
+        KeyCode::F(5) if app.current_tab == Tab::Main => {
+            app.log_level = app.log_level.saturating_sub(1);
+            let _ = app.tx.send(crate::computer::ControllerMessage::SetDebug(app.log_level));
         }
 
-        KeyCode::F(6) => {
-            match app.current_tab {
-                Tab::Main => {
-                    app.log_level = app.log_level.saturating_add(1);
-                    let _ = app.tx.send(crate::computer::ControllerMessage::SetDebug(app.log_level));
-                },
-                _ => {}
-            }
+        KeyCode::F(6) if app.current_tab == Tab::Main => {
+            app.log_level = app.log_level.saturating_add(1);
+            let _ = app.tx.send(crate::computer::ControllerMessage::SetDebug(app.log_level));
         }
 
 
-        KeyCode::F(7) => {
-            match app.current_tab {
-                Tab::Main => {
-                    let _ = app.tx.send(crate::computer::ControllerMessage::TogglePause);
-                },
-                _ => {}
-            }
+        KeyCode::F(7) if app.current_tab == Tab::Main || app.current_tab == Tab::Disassembly => {
+            let _ = app.tx.send(crate::computer::ControllerMessage::TogglePause);
+        }
+
+        KeyCode::F(8) => {
+            app.hold_fast_forward();
         }
         
-        KeyCode::Enter => {
-            match app.current_tab {
-                Tab::Main => {
-                    // Send data to computer
-                    let _ = app.tx.send(computer::ControllerMessage::SendChar(0x0D as char));
-                },
-                _ => {},
-            }
+        KeyCode::Enter if app.current_tab == Tab::Main => {
+            // Send data to computer
+            app.send_char(0x0D);
         }
         
         KeyCode::Up => {
@@ -92,6 +175,9 @@ pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
                     app.output_scroll = app.output_scroll.saturating_sub(1);
                     app.output_scroll_state = app.output_scroll_state.position(app.output_scroll);
                 },
+                Tab::Help => {
+                    app.reference_scroll = app.reference_scroll.saturating_sub(1).min(0xFF);
+                },
                 _ => {},
             }
         }
@@ -106,6 +192,9 @@ pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
                     app.output_scroll = app.output_scroll.saturating_add(1);
                     app.output_scroll_state = app.output_scroll_state.position(app.output_scroll);
                 },
+                Tab::Help => {
+                    app.reference_scroll = app.reference_scroll.saturating_add(1).min(0xFF);
+                },
                 _ => {},
             }
         }
@@ -120,6 +209,9 @@ pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
                     app.output_scroll = app.output_scroll.saturating_sub(16);
                     app.output_scroll_state = app.output_scroll_state.position(app.output_scroll);
                 },
+                Tab::Help => {
+                    app.reference_scroll = app.reference_scroll.saturating_sub(16).min(0xFF);
+                },
                 _ => {},
             }
         }
@@ -134,6 +226,9 @@ pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
                     app.output_scroll = app.output_scroll.saturating_add(16);
                     app.output_scroll_state = app.output_scroll_state.position(app.output_scroll);
                 },
+                Tab::Help => {
+                    app.reference_scroll = app.reference_scroll.saturating_add(16).min(0xFF);
+                },
                 _ => {},
             }
         }
@@ -142,26 +237,45 @@ pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
             if c == 'c' && key_event.modifiers == KeyModifiers::CONTROL {
                 app.quit();
             }
-            match app.current_tab {
-                Tab::Main => {
-                    let _ = app.tx.send(computer::ControllerMessage::SendChar(c));
-                    return Ok(()) ;
-                    //app.cursor_position = app.cursor_position.saturating_add(1);
-                },
-                _ => {
-                    
+            if c == 's' && key_event.modifiers == KeyModifiers::CONTROL {
+                app.export_session();
+                return Ok(());
+            }
+            if c == 'o' && key_event.modifiers == KeyModifiers::CONTROL {
+                app.import_session();
+                return Ok(());
+            }
+            if app.current_tab == Tab::Main {
+                app.send_char(c as u8);
+                return Ok(()) ;
+                //app.cursor_position = app.cursor_position.saturating_add(1);
+            }
+            if app.current_tab == Tab::Memory {
+                match c {
+                    '/' => app.start_search(),
+                    'e' => app.start_eval(),
+                    'n' => app.next_match(),
+                    'u' => {
+                        app.request(computer::Request::Usage);
+                    }
+                    'c' => {
+                        app.card_selected = 0;
+                        app.request(computer::Request::Cards);
+                    }
+                    'd' if app.search.is_some() => app.disk_matches_scroll = Some(0),
+                    'w' => app.toggle_watch(),
+                    'x' => app.toggle_watchpoint(),
+                    _ => {}
                 }
             }
-        },
-        KeyCode::Backspace => {
-            match app.current_tab {
-                Tab::Main => {
-                    let _ = app.tx.send(computer::ControllerMessage::SendChar(0x08 as char));
-                    // app.cursor_position = app.cursor_position.saturating_sub(1);
-                },
-                _ => {},
+            if app.current_tab == Tab::Disassembly && c == 'b' {
+                app.toggle_breakpoint();
             }
         },
+        KeyCode::Backspace if app.current_tab == Tab::Main => {
+            app.send_char(0x08);
+            // app.cursor_position = app.cursor_position.saturating_sub(1);
+        },
         
         _ => {}
     }
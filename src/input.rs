@@ -0,0 +1,93 @@
+use std::fs::{File, Metadata};
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::ring::RingBuffer;
+
+/// Name of the thread reading serial input.
+pub const INPUT_THREAD: &str = "serial-input";
+
+/// Where serial input comes from besides the keyboard.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InputSource {
+    Stdin,
+    /// Regular file, read once, or named pipe, opened again after each writer leaves
+    Path(PathBuf),
+}
+
+impl InputSource {
+    /// `-` is stdin, anything else a path.
+    pub fn parse(s: &str) -> InputSource {
+        if s == "-" {
+            InputSource::Stdin
+        } else {
+            InputSource::Path(PathBuf::from(s))
+        }
+    }
+
+    /// Reads the source on its own thread, queueing what arrives for the serial card.
+    pub fn spawn(self, rx: Arc<RingBuffer>) -> Result<(), Error> {
+        let fifo = match &self {
+            InputSource::Stdin => false,
+            InputSource::Path(path) => is_fifo(&path.metadata()
+                .map_err(|source| Error::File { path: path.display().to_string(), source })?),
+        };
+
+        let _ = thread::Builder::new().name(INPUT_THREAD.to_string()).spawn(move || loop {
+            // Opening a named pipe waits for a writer, so it is done here
+            let reader: io::Result<Box<dyn Read>> = match &self {
+                InputSource::Stdin => Ok(Box::new(io::stdin())),
+                InputSource::Path(path) => File::open(path).map(|f| Box::new(f) as Box<dyn Read>),
+            };
+            let Ok(reader) = reader else {
+                return;
+            };
+            if forward(reader, &rx).is_err() || !fifo {
+                return;
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn is_fifo(metadata: &Metadata) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    metadata.file_type().is_fifo()
+}
+
+/// Without named pipes every path is read once, like a regular file
+#[cfg(not(unix))]
+fn is_fifo(_metadata: &Metadata) -> bool {
+    false
+}
+
+/// Queues everything read until the end of the input, waiting for the guest
+/// when the queue is full. Fails once nothing else uses the queue.
+fn forward(mut reader: impl Read, rx: &Arc<RingBuffer>) -> Result<(), ()> {
+    let mut buf = [0; 4096];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => {
+                let mut rest = &buf[..n];
+                loop {
+                    rest = &rest[rx.push_slice(rest)..];
+                    if rest.is_empty() {
+                        break;
+                    }
+                    if Arc::strong_count(rx) == 1 {
+                        return Err(());
+                    }
+                    thread::sleep(Duration::from_millis(1));
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(_) => return Ok(()),
+        }
+    }
+}
@@ -13,4 +13,46 @@ pub mod tui;
 /// Event handler.
 pub mod handler;
 pub mod button;
-pub mod computer;
\ No newline at end of file
+pub mod computer;
+
+/// Serial output kept as a grid of bytes.
+pub mod console;
+
+/// Errors reported to the user.
+pub mod error;
+
+/// ROM image loading.
+pub mod rom;
+
+/// Command line arguments.
+pub mod args;
+
+/// Memory search.
+pub mod search;
+
+/// Performance metrics log.
+pub mod metrics;
+
+/// Example ROMs run by `plu demo`.
+pub mod demo;
+
+/// Assembler projects run by `plu build-and-run`.
+pub mod project;
+
+/// Symbol files produced by assemblers.
+pub mod symbols;
+
+/// Address space usage report.
+pub mod usage;
+
+/// Serial input from files, named pipes and stdin.
+pub mod input;
+
+/// Lock-free byte queue between threads.
+pub mod ring;
+
+/// Small scripting language for virtual devices.
+pub mod script;
+
+/// Debugger setup saved to and restored from a file.
+pub mod session;
@@ -1,32 +1,89 @@
 
 
 use plu::app::{App, AppResult};
+use plu::args::{self, BisectArgs, Command, USAGE};
+use plu::computer::{CardType, IRQ_SLOT, RTC_SLOT, TRAP_SLOT};
+use plu::computer::bisect::Bisect;
+use plu::computer::lockstep::{self, Lockstep};
+use plu::demo;
+use plu::error::{self, Error};
+use plu::project::Project;
+use plu::rom::Rom;
+use plu::script::Script;
+use plu::session::Session;
 use plu::event::{Event, EventHandler};
 use plu::handler::handle_key_events;
 use plu::tui::Tui;
 
 
-use std::{io, env};
+use std::{io, env, process};
+use std::path::PathBuf;
 
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 
 
 fn main() -> AppResult<()> {
-    let args: Vec<String> = env::args().collect();
-    // Create an application.
-    if args.len() < 2 {
-        println!("Usage: plu <rom.bin> [cfcard.img]");
+    if env::args().len() < 2 {
+        println!("{}", USAGE);
         return Ok(());
     }
 
-    let mut cf_file = None;
+    let command = match args::parse_command(env::args().skip(1)) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("plu: {}", e);
+            process::exit(2);
+        }
+    };
 
-    if args.len() > 2 {
-        cf_file = Some(args[2].clone());
-    }
+    let usage_report = matches!(&command, Command::Run(args) if args.usage_report);
+    // Only ROM files given on the command line have a place to hibernate to
+    let snapshot_file = match &command {
+        Command::Run(args) if args.hibernate => args.rom_file.as_deref().map(snapshot_path),
+        _ => None,
+    };
 
-    let mut app = App::new(args[1].clone(), cf_file);
+    // Create an application.
+    let app = match command {
+        Command::Run(args) => start(args),
+        Command::Demo(None) => {
+            println!("Demos, run with plu demo NAME:");
+            for demo in &demo::DEMOS {
+                println!("  {:14}{}", demo.name, demo.description);
+            }
+            return Ok(());
+        }
+        Command::Demo(Some(name)) => {
+            let Some(demo) = demo::find(&name) else {
+                eprintln!("plu: unknown demo {}, run plu demo for the list", name);
+                process::exit(2);
+            };
+            App::with_data(demo.rom.to_vec(), (demo.disk)())
+        }
+        Command::BuildAndRun(path) => Project::open(&path).and_then(App::with_project),
+        Command::Lockstep(args) => {
+            if let Err(e) = run_lockstep(args) {
+                eprintln!("plu: {}", e);
+                process::exit(1);
+            }
+            return Ok(());
+        }
+        Command::Bisect(args) => {
+            if let Err(e) = run_bisect(args) {
+                eprintln!("plu: {}", e);
+                process::exit(1);
+            }
+            return Ok(());
+        }
+    };
+    let mut app = match app {
+        Ok(app) => app,
+        Err(e) => {
+            eprintln!("plu: {}", e);
+            process::exit(1);
+        }
+    };
 
     // Initialize the terminal user interface.
     let backend = CrosstermBackend::new(io::stderr());
@@ -52,5 +109,115 @@ fn main() -> AppResult<()> {
     // Exit the user interface.
     tui.exit()?;
 
+    if let Some(path) = snapshot_file.filter(|_| app.error.is_none()) {
+        if let Err(e) = app.hibernate(&path) {
+            eprintln!("plu: could not save the machine state: {}", e);
+        }
+    }
+
+    if usage_report {
+        match app.usage_report() {
+            Some(report) => print!("{}", report),
+            None => eprintln!("plu: the computer did not report its memory usage"),
+        }
+    }
+
+    Ok(())
+}
+
+fn start(mut args: args::Args) -> Result<App, Error> {
+    let session = match &args.session_file {
+        Some(path) => Session::open(path)?,
+        None => Session::default(),
+    };
+    session.apply(&mut args);
+    // A project run from the session has no ROM file to hibernate next to
+    let snapshot_file = args.rom_file.as_deref().map(snapshot_path);
+    let mut app = match (args.rom_file, &session.project) {
+        (Some(rom_file), _) => App::new(rom_file, args.cf_file, args.rom_options)?,
+        (None, Some(project)) => Project::open(project).and_then(App::with_project)?,
+        (None, None) => return Err(Error::Argument(format!("the session has no project, give a ROM to run\n{}", USAGE))),
+    };
+    if let Some(path) = args.session_file {
+        app.session_file = path;
+    }
+    if let Some(path) = &args.symbols_file {
+        app.load_symbols(path)?;
+    }
+    app.restore_debugger(&session);
+    // The snapshot brings back its own cards, the ones asked for are added to them
+    if let Some(snapshot_file) = snapshot_file.filter(|_| args.resume) {
+        let message = match app.resume(&snapshot_file) {
+            Ok(true) => format!("Resumed from {}", snapshot_file.display()),
+            Ok(false) => String::from("No saved state, cold boot"),
+            Err(e) => format!("Cold boot, {}", e),
+        };
+        app.debug.push_back(message);
+    }
+    if args.rtc {
+        app.insert_card(RTC_SLOT, CardType::Rtc);
+    }
+    if args.trap {
+        app.insert_card(TRAP_SLOT, CardType::Trap);
+    }
+    if let Some(order) = args.irq_order {
+        app.insert_card(IRQ_SLOT, CardType::Irq);
+        app.set_irq_order(order);
+    }
+    app.set_strict_map(args.strict_map);
+    app.set_invalid_opcode(args.on_invalid_opcode);
+    app.set_crash_dir(args.crash_dir.map(PathBuf::from));
+    app.report_size = args.report_size;
+    if let Some(source) = args.serial_input {
+        app.add_serial_input(source)?;
+    }
+    if let Some(path) = args.metrics_file {
+        app.record_metrics(&path)?;
+    }
+    // Everything the program may touch from its first instruction is in place
+    app.boot();
+    Ok(app)
+}
+
+/// Runs both CPU cores on the ROM and exits with an error at the first divergence
+fn run_lockstep(args: args::Args) -> Result<(), Error> {
+    let rom_file = args.rom_file.ok_or_else(|| Error::Argument(format!("lockstep needs a ROM\n{}", USAGE)))?;
+    let rom = Rom::load(error::read_file(&rom_file)?, &args.rom_options)?;
+    let disk = match args.cf_file {
+        Some(d) => error::read_file(&d)?,
+        None => vec![],
+    };
+    error::validate_disk(&disk)?;
+
+    match Lockstep::new(rom, disk).run(lockstep::INSTRUCTIONS) {
+        Ok(count) => println!("{} instructions run in lockstep, the cores agree", count),
+        Err(divergence) => {
+            print!("{}", divergence);
+            process::exit(1);
+        }
+    }
     Ok(())
 }
+
+/// Tests builds until the first failing one is found
+fn run_bisect(args: BisectArgs) -> Result<(), Error> {
+    let source = String::from_utf8_lossy(&error::read_file(&args.script_file)?).into_owned();
+    let script = Script::parse(&source).map_err(|e| Error::Argument(format!("invalid test script {}: {}", args.script_file, e)))?;
+    let disk = match args.cf_file {
+        Some(d) => error::read_file(&d)?,
+        None => vec![],
+    };
+    error::validate_disk(&disk)?;
+
+    let bisect = Bisect::new(script, args.rom_options, disk);
+    match bisect.run(&args.builds, |build, outcome| println!("{}: {}", build, outcome))? {
+        Some(first) => println!("First failing build: {}", args.builds[first]),
+        None => println!("The newest build passes"),
+    }
+    Ok(())
+}
+
+/// Where the machine state is saved on exit, next to the ROM
+fn snapshot_path(rom_file: &str) -> PathBuf {
+    PathBuf::from(format!("{}.snapshot", rom_file))
+}
@@ -0,0 +1,103 @@
+use std::fs::File;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use chrono::{SecondsFormat, Utc};
+
+use crate::error::Error;
+
+/// Time between two lines of the metrics log
+pub const INTERVAL: Duration = Duration::from_secs(1);
+
+/// One line of the metrics log.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Sample {
+    pub mhz: f64,
+    /// Host CPU used by the emulator, 100 per busy core. Unknown outside of Linux.
+    pub host_cpu: Option<f64>,
+    pub instructions: u64,
+    /// Output bytes waiting for the UI
+    pub output_backlog: u64,
+    /// Requests sent to the computer and not answered yet
+    pub requests_in_flight: u32,
+}
+
+impl Sample {
+    pub fn to_json(&self, time: &str) -> String {
+        let host_cpu = self.host_cpu.map_or(String::from("null"), |c| format!("{:.1}", c));
+        format!(
+            "{{\"time\":\"{}\",\"mhz\":{:.3},\"host_cpu\":{},\"instructions\":{},\"output_backlog\":{},\"requests_in_flight\":{}}}",
+            time, self.mhz, host_cpu, self.instructions, self.output_backlog, self.requests_in_flight
+        )
+    }
+}
+
+/// Opt-in JSONL file with one [`Sample`] per second.
+#[derive(Debug)]
+pub struct MetricsLog {
+    path: String,
+    file: File,
+    last_time: Instant,
+    last_cycles: u64,
+    last_instructions: u64,
+    last_cpu: Option<Duration>,
+}
+
+impl MetricsLog {
+    pub fn create(path: &str) -> Result<Self, Error> {
+        let file = File::create(path).map_err(|source| Error::Write { path: path.to_string(), source })?;
+        Ok(Self {
+            path: path.to_string(),
+            file,
+            last_time: Instant::now(),
+            last_cycles: 0,
+            last_instructions: 0,
+            last_cpu: process_cpu_time(),
+        })
+    }
+
+    pub fn is_due(&self) -> bool {
+        self.last_time.elapsed() >= INTERVAL
+    }
+
+    /// Builds the sample for the time since the last one from the running totals.
+    pub fn sample(&mut self, cycles: u64, instructions: u64) -> Sample {
+        let elapsed = self.last_time.elapsed().as_secs_f64();
+        let cpu = process_cpu_time();
+        let host_cpu = match (cpu, self.last_cpu) {
+            (Some(now), Some(before)) if elapsed > 0.0 => Some(now.saturating_sub(before).as_secs_f64() / elapsed * 100.0),
+            _ => None,
+        };
+        let sample = Sample {
+            // The cycle count goes back to zero on reset
+            mhz: cycles.saturating_sub(self.last_cycles) as f64 / elapsed / 1_000_000.0,
+            host_cpu,
+            instructions: instructions.saturating_sub(self.last_instructions),
+            ..Default::default()
+        };
+
+        self.last_time = Instant::now();
+        self.last_cycles = cycles;
+        self.last_instructions = instructions;
+        self.last_cpu = cpu;
+        sample
+    }
+
+    pub fn write(&mut self, sample: &Sample) -> Result<(), Error> {
+        let time = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        writeln!(self.file, "{}", sample.to_json(&time))
+            .map_err(|source| Error::Write { path: self.path.clone(), source })
+    }
+}
+
+/// CPU time used by the emulator process so far, read from procfs.
+fn process_cpu_time() -> Option<Duration> {
+    let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+    // The command name in parentheses may contain spaces, utime and stime
+    // are the 12th and 13th fields after it
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    // Linux reports both in 1/100th of a second
+    Some(Duration::from_millis((utime + stime) * 10))
+}
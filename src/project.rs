@@ -0,0 +1,180 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::Deserialize;
+
+use crate::computer::device::{Device, Handlers};
+use crate::error::{self as plu_error, Error};
+use crate::input::InputSource;
+use crate::rom::{self, Rom, RomOptions};
+use crate::symbols::Symbols;
+
+/// Assembler project run by `plu build-and-run`, read from a TOML file such as:
+///
+/// ```toml
+/// build = [
+///     ["ca65", "--cpu", "65C02", "main.s"],
+///     ["ld65", "-C", "planck.cfg", "-Ln", "rom.sym", "-o", "rom.bin", "main.o"],
+/// ]
+/// rom = "rom.bin"
+/// symbols = "rom.sym"
+/// serial_input = "vectors.fifo"
+///
+/// [[device]]
+/// name = "dac"
+/// slot = 0
+/// write = "let level = if reg == 0 { value } else { level };"
+/// read = "level"
+/// ```
+///
+/// Commands run from the directory of the project file, and paths are relative to it.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Project {
+    /// Command lines run in order, stopping at the first failure
+    build: Vec<Vec<String>>,
+    /// ROM image written by the build
+    rom: String,
+    /// VICE label file or `name = $ADDR` list written by the build
+    symbols: Option<String>,
+    /// CF card image
+    cf: Option<String>,
+    rom_base: Option<String>,
+    rom_size: Option<String>,
+    /// File or named pipe read by the serial card, `-` for stdin
+    serial_input: Option<String>,
+    /// Cards made of scripts
+    #[serde(default)]
+    device: Vec<DeviceConfig>,
+    #[serde(skip)]
+    dir: PathBuf,
+    /// Path of the project file, empty for parsed ones
+    #[serde(skip)]
+    file: String,
+}
+
+/// Handlers of a scripted [`Device`], see [`crate::script::Script`] for their syntax.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+struct DeviceConfig {
+    name: String,
+    slot: u16,
+    read: Option<String>,
+    write: Option<String>,
+    tick: Option<String>,
+    /// Cycles between two runs of the tick handler
+    tick_cycles: Option<u64>,
+    /// Run at the start of every frame
+    frame: Option<String>,
+    /// Run when the beam reaches the line in the `raster` variable
+    scanline: Option<String>,
+}
+
+impl Project {
+    pub fn open(path: &str) -> Result<Project, Error> {
+        let text = String::from_utf8_lossy(&plu_error::read_file(path)?).into_owned();
+        let mut project = Project::parse(&text).map_err(|msg| Error::Project { path: path.to_string(), msg })?;
+        project.dir = Path::new(path).parent().map(Path::to_path_buf).unwrap_or_default();
+        project.file = path.to_string();
+        Ok(project)
+    }
+
+    /// Parses the project file, with paths relative to the current directory.
+    pub fn parse(text: &str) -> Result<Project, String> {
+        let project: Project = toml::from_str(text).map_err(|e| e.message().to_string())?;
+        if let Some(command) = project.build.iter().find(|c| c.is_empty()) {
+            return Err(format!("empty build command {:?}", command));
+        }
+        project.rom_options()?;
+        project.devices()?;
+        Ok(project)
+    }
+
+    fn rom_options(&self) -> Result<RomOptions, String> {
+        let base = match &self.rom_base {
+            Some(b) => Some(rom::parse_address(b).ok_or_else(|| format!("invalid ROM base address {}", b))?),
+            None => None,
+        };
+        let size = match &self.rom_size {
+            Some(s) => Some(rom::parse_size(s).ok_or_else(|| format!("invalid ROM size {}", s))?),
+            None => None,
+        };
+        Ok(RomOptions { base, size })
+    }
+
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    fn path(&self, file: &str) -> String {
+        self.dir.join(file).to_string_lossy().into_owned()
+    }
+
+    /// Runs the build commands, returning the output of the one that failed as the error.
+    pub fn build(&self) -> Result<(), Error> {
+        for command in &self.build {
+            let line = command.join(" ");
+            let output = Command::new(&command[0])
+                .args(&command[1..])
+                .current_dir(if self.dir.as_os_str().is_empty() { Path::new(".") } else { &self.dir })
+                .output()
+                .map_err(|e| Error::Build { command: line.clone(), output: e.to_string() })?;
+            if !output.status.success() {
+                let mut text = String::from_utf8_lossy(&output.stderr).into_owned();
+                text.push_str(&String::from_utf8_lossy(&output.stdout));
+                return Err(Error::Build { command: line, output: text.trim_end().to_string() });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn rom(&self) -> Result<Rom, Error> {
+        let options = self.rom_options().map_err(Error::Argument)?;
+        Rom::load(plu_error::read_file(&self.path(&self.rom))?, &options)
+    }
+
+    /// CF card image, empty without a card
+    pub fn disk(&self) -> Result<Vec<u8>, Error> {
+        let Some(cf) = &self.cf else {
+            return Ok(vec![]);
+        };
+        let disk = plu_error::read_file(&self.path(cf))?;
+        plu_error::validate_disk(&disk)?;
+        Ok(disk)
+    }
+
+    pub fn serial_input(&self) -> Option<InputSource> {
+        match self.serial_input.as_deref()? {
+            "-" => Some(InputSource::Stdin),
+            path => Some(InputSource::Path(self.path(path).into())),
+        }
+    }
+
+    /// Scripted devices, with their handlers compiled
+    pub fn devices(&self) -> Result<Vec<Device>, String> {
+        self.device.iter().map(|d| {
+            if d.slot > 6 {
+                return Err(format!("device {} is in slot {}, there are 7 slots from 0 to 6", d.name, d.slot));
+            }
+            if d.tick.is_some() && d.tick_cycles.unwrap_or(0) == 0 {
+                return Err(format!("device {} has a tick handler without tick_cycles", d.name));
+            }
+            let handlers = Handlers {
+                read: d.read.as_deref(),
+                write: d.write.as_deref(),
+                tick: d.tick.as_deref(),
+                frame: d.frame.as_deref(),
+                scanline: d.scanline.as_deref(),
+            };
+            Device::new(d.slot, &d.name, handlers, d.tick_cycles.unwrap_or(0))
+        }).collect()
+    }
+
+    pub fn symbols(&self) -> Result<Symbols, Error> {
+        let Some(symbols) = &self.symbols else {
+            return Ok(Symbols::default());
+        };
+        let text = plu_error::read_file(&self.path(symbols))?;
+        Ok(Symbols::parse(&String::from_utf8_lossy(&text)))
+    }
+}
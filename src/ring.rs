@@ -0,0 +1,99 @@
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+/// Bounded queue of bytes shared between threads without locks, after Dmitry
+/// Vyukov's bounded MPMC queue. Any thread can push, keystrokes from the UI and
+/// serial input sources alike, while the computer thread pops between instructions.
+#[derive(Debug)]
+pub struct RingBuffer {
+    cells: Box<[Cell]>,
+    mask: usize,
+    /// Position of the next push
+    head: AtomicUsize,
+    /// Position of the next pop
+    tail: AtomicUsize,
+}
+
+#[derive(Debug)]
+struct Cell {
+    /// Equals the position of the push that may fill the cell, and that position
+    /// plus one once the byte is written
+    sequence: AtomicUsize,
+    byte: AtomicU8,
+}
+
+impl RingBuffer {
+    /// Queue holding at least `capacity` bytes, rounded up to a power of two.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(2).next_power_of_two();
+        RingBuffer {
+            cells: (0..capacity).map(|i| Cell { sequence: AtomicUsize::new(i), byte: AtomicU8::new(0) }).collect(),
+            mask: capacity - 1,
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Queues a byte, returning false when the queue is full.
+    pub fn push(&self, byte: u8) -> bool {
+        let mut pos = self.head.load(Ordering::Relaxed);
+        loop {
+            let cell = &self.cells[pos & self.mask];
+            let sequence = cell.sequence.load(Ordering::Acquire);
+            match (sequence as isize).wrapping_sub(pos as isize) {
+                0 => match self.head.compare_exchange_weak(pos, pos.wrapping_add(1), Ordering::Relaxed, Ordering::Relaxed) {
+                    Ok(_) => {
+                        cell.byte.store(byte, Ordering::Relaxed);
+                        cell.sequence.store(pos.wrapping_add(1), Ordering::Release);
+                        return true;
+                    }
+                    Err(current) => pos = current,
+                },
+                // The cell still holds a byte from the previous lap
+                d if d < 0 => return false,
+                // Another thread pushed here first
+                _ => pos = self.head.load(Ordering::Relaxed),
+            }
+        }
+    }
+
+    /// Queues as many bytes as fit, returning how many were queued.
+    pub fn push_slice(&self, bytes: &[u8]) -> usize {
+        bytes.iter().take_while(|&&b| self.push(b)).count()
+    }
+
+    /// Takes the oldest byte.
+    pub fn pop(&self) -> Option<u8> {
+        let mut pos = self.tail.load(Ordering::Relaxed);
+        loop {
+            let cell = &self.cells[pos & self.mask];
+            let sequence = cell.sequence.load(Ordering::Acquire);
+            match (sequence as isize).wrapping_sub(pos.wrapping_add(1) as isize) {
+                0 => match self.tail.compare_exchange_weak(pos, pos.wrapping_add(1), Ordering::Relaxed, Ordering::Relaxed) {
+                    Ok(_) => {
+                        let byte = cell.byte.load(Ordering::Relaxed);
+                        // Free the cell for the push one lap ahead
+                        cell.sequence.store(pos.wrapping_add(self.mask + 1), Ordering::Release);
+                        return Some(byte);
+                    }
+                    Err(current) => pos = current,
+                },
+                // Nothing was written there yet
+                d if d < 0 => return None,
+                _ => pos = self.tail.load(Ordering::Relaxed),
+            }
+        }
+    }
+
+    /// Bytes waiting, only exact when no other thread is using the queue.
+    pub fn len(&self) -> usize {
+        self.head.load(Ordering::Relaxed).wrapping_sub(self.tail.load(Ordering::Relaxed)).min(self.capacity())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
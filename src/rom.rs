@@ -0,0 +1,78 @@
+use std::ops::Range;
+
+use crate::error::Error;
+
+/// Size of the 6502 address space.
+pub const ADDRESS_SPACE: usize = 0x10000;
+
+/// How a ROM image is placed in the address space.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RomOptions {
+    /// Address of the first ROM byte. Defaults to putting the end of the ROM at $FFFF.
+    pub base: Option<u16>,
+    /// Only load the last `size` bytes of the image, like an EPROM programmer
+    /// burning the top half or quarter of a larger file.
+    pub size: Option<usize>,
+}
+
+/// ROM image ready to be mapped in memory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rom {
+    pub base: u16,
+    pub data: Vec<u8>,
+}
+
+impl Rom {
+    /// Addresses covered by the image
+    pub fn range(&self) -> Range<usize> {
+        self.base as usize..self.base as usize + self.data.len()
+    }
+
+    /// Checks that the image fits in the address space and selects the part to load.
+    pub fn load(mut data: Vec<u8>, options: &RomOptions) -> Result<Rom, Error> {
+        if data.is_empty() {
+            return Err(Error::EmptyRom);
+        }
+
+        if let Some(size) = options.size {
+            if size == 0 || size > data.len() {
+                return Err(Error::RomSize { size, file: data.len() });
+            }
+            data.drain(..data.len() - size);
+        }
+
+        if data.len() > ADDRESS_SPACE {
+            return Err(Error::RomTooLarge(data.len()));
+        }
+
+        let base = options.base.map_or(ADDRESS_SPACE - data.len(), |b| b as usize);
+        if base + data.len() > ADDRESS_SPACE {
+            return Err(Error::RomDoesNotFit { base: base as u16, size: data.len() });
+        }
+
+        Ok(Rom {
+            base: base as u16,
+            data,
+        })
+    }
+}
+
+/// Parses an address given as `0x8000`, `$8000` or decimal.
+pub fn parse_address(s: &str) -> Option<u16> {
+    parse_number(s).and_then(|n| u16::try_from(n).ok())
+}
+
+/// Parses a size given as `32K`, `0x8000`, `$8000` or decimal.
+pub fn parse_size(s: &str) -> Option<usize> {
+    match s.strip_suffix(['K', 'k']) {
+        Some(k) => k.parse::<usize>().ok().and_then(|k| k.checked_mul(1024)),
+        None => parse_number(s),
+    }
+}
+
+fn parse_number(s: &str) -> Option<usize> {
+    if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix('$')) {
+        return usize::from_str_radix(hex, 16).ok();
+    }
+    s.parse().ok()
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+use rhai::{Dynamic, Engine, Scope, AST};
+
+/// Values given to a script as constants, such as symbols.
+pub type Vars = HashMap<String, i64>;
+
+/// Operations a single run may take, so a script stuck in a loop fails
+/// instead of hanging the computer
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// [Rhai](https://rhai.rs) script, for handlers that do not deserve Rust code:
+///
+/// ```text
+/// // Latch the level written to register 0, and interrupt when it is odd
+/// let level = if reg == 0 { value } else { level };
+/// irq = level & 1;
+/// ```
+///
+/// Variables that were never set read as 0, like the registers of the hardware
+/// they model. Variables declared with `let` at the top level are kept in the
+/// scope for the next run. A script evaluates to its last statement, with `true`
+/// and `false` as 1 and 0.
+#[derive(Clone, Debug)]
+pub struct Script {
+    ast: AST,
+}
+
+impl Script {
+    pub fn parse(source: &str) -> Result<Script, String> {
+        engine().compile(source).map(|ast| Script { ast }).map_err(|e| e.to_string())
+    }
+
+    /// Runs the script with the functions registered in `engine`, returning the
+    /// value of its last statement, 0 if it has none.
+    pub fn run(&self, engine: &Engine, scope: &mut Scope<'static>) -> Result<i64, String> {
+        let len = scope.len();
+        let result = engine.eval_ast_with_scope::<Dynamic>(scope, &self.ast);
+        keep_latest(scope, len);
+        int(&result.map_err(|e| e.to_string())?)
+    }
+}
+
+/// Engine without any function besides those of Rhai, shared by all scripts that need none.
+pub fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(new_engine)
+}
+
+/// Engine to register the functions of a host in.
+pub fn new_engine() -> Engine {
+    let mut engine = strict_engine();
+    // Marked deprecated by Rhai to say the API may change, not that it is going away
+    #[allow(deprecated)]
+    engine.on_var(|name, _, context| match context.scope().contains(name) {
+        true => Ok(None),
+        false => Ok(Some(Dynamic::from(0_i64))),
+    });
+    engine
+}
+
+/// Engine where variables that were never set are an error, for expressions typed
+/// by the user, where they are more likely a mistyped symbol than a register.
+fn strict_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine
+}
+
+/// Adds `peek(addr)` and `word(addr)`, reading a 64K address space through `read`.
+pub fn register_memory(engine: &mut Engine, read: impl Fn(u16) -> u8 + Send + Sync + 'static) {
+    let read = Arc::new(read);
+    let byte = read.clone();
+    engine.register_fn("peek", move |addr: i64| byte(addr as u16) as i64);
+    engine.register_fn("word", move |addr: i64| {
+        let addr = addr as u16;
+        read(addr) as i64 | (read(addr.wrapping_add(1)) as i64) << 8
+    });
+}
+
+/// Parses and runs `source` once over a copy of `memory`, with `vars` as constants.
+/// Unlike handlers, it fails on variables that are not in `vars`.
+pub fn eval(source: &str, vars: &Vars, memory: &[u8]) -> Result<i64, String> {
+    let script = Script::parse(source)?;
+    let mut engine = strict_engine();
+    let memory: Arc<[u8]> = memory.into();
+    register_memory(&mut engine, move |addr| memory.get(addr as usize).copied().unwrap_or(0));
+    let mut scope = Scope::new();
+    for (name, &value) in vars {
+        scope.push_constant(name.as_str(), value);
+    }
+    script.run(&engine, &mut scope)
+}
+
+/// Number a script value stands for, nothing being 0 and `true` 1.
+pub fn int(value: &Dynamic) -> Result<i64, String> {
+    if value.is_unit() {
+        return Ok(0);
+    }
+    value.as_int()
+        .or_else(|_| value.as_bool().map(i64::from))
+        .map_err(|type_name| format!("expected a number, found {}", type_name))
+}
+
+/// Drops the variables a run declared again with `let`, keeping their last value,
+/// so the scope does not grow on every run.
+fn keep_latest(scope: &mut Scope<'static>, len: usize) {
+    if scope.len() == len {
+        return;
+    }
+    let declared: Vec<(String, Dynamic)> = scope.iter().skip(len).map(|(name, _, value)| (name.to_string(), value)).collect();
+    scope.rewind(len);
+    for (name, value) in declared {
+        scope.set_or_push(name, value);
+    }
+}
@@ -0,0 +1,52 @@
+use memchr::memmem;
+
+/// Pattern searched in the Memory tab, with the matches of the last search.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Search {
+    pub pattern: Vec<u8>,
+    /// Start addresses of the matches in memory, in increasing order
+    pub matches: Vec<usize>,
+    /// Offsets of the matches in the CF card image, in increasing order
+    pub disk_matches: Vec<usize>,
+}
+
+impl Search {
+    pub fn new(pattern: Vec<u8>) -> Self {
+        Self {
+            pattern,
+            ..Default::default()
+        }
+    }
+
+    /// Is the byte at `addr` part of a match?
+    pub fn covers(&self, addr: usize) -> bool {
+        let i = self.matches.partition_point(|&m| m <= addr);
+        i > 0 && addr < self.matches[i - 1] + self.pattern.len()
+    }
+
+    /// First match starting after `addr`, wrapping around to the first one.
+    pub fn next_after(&self, addr: usize) -> Option<usize> {
+        let i = self.matches.partition_point(|&m| m <= addr);
+        self.matches.get(i).or(self.matches.first()).copied()
+    }
+}
+
+/// Parses a search pattern: text between double quotes, or hex bytes like `A9 00` or `A900`.
+pub fn parse_pattern(s: &str) -> Option<Vec<u8>> {
+    let s = s.trim();
+    if let Some(text) = s.strip_prefix('"') {
+        let text = text.strip_suffix('"').unwrap_or(text);
+        return (!text.is_empty()).then(|| text.as_bytes().to_vec());
+    }
+
+    let hex: String = s.split_whitespace().collect();
+    if hex.is_empty() || !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+/// Finds all non overlapping occurrences of `finder`'s pattern in `haystack`.
+pub fn find_all(finder: &memmem::Finder, haystack: &[u8]) -> Vec<usize> {
+    finder.find_iter(haystack).collect()
+}
@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+
+use crate::args::{self, Args};
+use crate::error::{self as plu_error, Error};
+
+/// Debugger setup saved with Ctrl+S and restored with `plu --session FILE` or Ctrl+O, such as:
+///
+/// ```json
+/// {
+///   "project": "game/project.toml",
+///   "symbols": "game/rom.sym",
+///   "rtc": false,
+///   "trap": true,
+///   "strict_map": false,
+///   "irq_order": [6, 4],
+///   "breakpoints": [65280],
+///   "watchpoints": [512],
+///   "watches": [512, 513],
+///   "patches": [{ "address": 65282, "bytes": [234, 234] }]
+/// }
+/// ```
+///
+/// Paths are kept as given, relative to the directory plu runs from, addresses are decimal.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Session {
+    /// Project built and run when no ROM is given on the command line
+    pub project: Option<String>,
+    /// Symbol file, for ROMs run without their project
+    pub symbols: Option<String>,
+    pub rtc: bool,
+    pub trap: bool,
+    pub strict_map: bool,
+    /// Slots first in the interrupt daisy chain
+    pub irq_order: Option<Vec<u16>>,
+    /// Instructions the computer stops before
+    pub breakpoints: Vec<u16>,
+    /// Addresses the computer stops after writing to
+    pub watchpoints: Vec<u16>,
+    /// Bytes shown above the Memory tab
+    pub watches: Vec<u16>,
+    /// Bytes written over memory, ROM included, when the session is loaded
+    pub patches: Vec<Patch>,
+}
+
+/// Bytes written from `address` on, wrapping around the address space
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Patch {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+}
+
+impl Session {
+    pub fn open(path: &str) -> Result<Session, Error> {
+        let text = String::from_utf8_lossy(&plu_error::read_file(path)?).into_owned();
+        Session::parse(&text).map_err(|msg| Error::Argument(format!("invalid session {}: {}", path, msg)))
+    }
+
+    pub fn parse(text: &str) -> Result<Session, String> {
+        let session: Session = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        if let Some(order) = &session.irq_order {
+            if !args::valid_slots(order) {
+                return Err(format!("invalid interrupt order {:?}", order));
+            }
+        }
+        Ok(session)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Error> {
+        std::fs::write(path, self.to_json() + "\n")
+            .map_err(|source| Error::Write { path: path.to_string(), source })
+    }
+
+    /// Adds the cards and options of the session to the command line ones,
+    /// which win when both set the same value.
+    pub fn apply(&self, args: &mut Args) {
+        args.rtc |= self.rtc;
+        args.trap |= self.trap;
+        args.strict_map |= self.strict_map;
+        if args.irq_order.is_none() {
+            args.irq_order = self.irq_order.clone();
+        }
+        if args.symbols_file.is_none() {
+            args.symbols_file = self.symbols.clone();
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+/// Names of addresses, read from the symbol file written by the assembler.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Symbols {
+    names: BTreeMap<u16, String>,
+}
+
+impl Symbols {
+    /// Parses VICE label files (`al C:FF00 .reset`, written by ld65 -Ln or
+    /// 64tass --vice-labels) and `reset = $FF00` lines (64tass --labels).
+    /// Other lines are ignored. The first name given to an address is kept.
+    pub fn parse(text: &str) -> Symbols {
+        let mut names = BTreeMap::new();
+        for line in text.lines() {
+            if let Some((addr, name)) = parse_vice(line).or_else(|| parse_assignment(line)) {
+                names.entry(addr).or_insert_with(|| name.to_string());
+            }
+        }
+        Symbols { names }
+    }
+
+    pub fn get(&self, addr: u16) -> Option<&str> {
+        self.names.get(&addr).map(String::as_str)
+    }
+
+    /// Addresses and their names, lowest address first
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &str)> {
+        self.names.iter().map(|(&addr, name)| (addr, name.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+fn parse_vice(line: &str) -> Option<(u16, &str)> {
+    let mut words = line.split_whitespace();
+    if words.next()? != "al" {
+        return None;
+    }
+    let addr = words.next()?;
+    // The address may be prefixed with a memory space, C: for the CPU
+    let addr = addr.rsplit(':').next()?;
+    let name = words.next()?.trim_start_matches('.');
+    Some((u16::from_str_radix(addr, 16).ok()?, name))
+}
+
+fn parse_assignment(line: &str) -> Option<(u16, &str)> {
+    let (name, value) = line.split_once('=')?;
+    let name = name.trim();
+    let value = value.split(';').next()?.trim().strip_prefix('$')?;
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((u16::from_str_radix(value, 16).ok()?, name))
+}
@@ -1,19 +1,26 @@
 pub mod header;
 pub mod main;
 pub mod memory;
+pub mod disassembly;
 
 pub mod stateful_list;
 use std::rc::Rc;
 
 use ratatui::{
-    layout::{Rect, Layout, Direction, Constraint},
-    style::{Color, Style, Stylize},
-    widgets::{Block, BorderType, Borders, Paragraph, Wrap, Clear, Padding, Table},
+    layout::{Alignment, Rect, Layout, Direction, Constraint},
+    style::{Color, Modifier, Style, Stylize},
+    text::Line,
+    widgets::{Block, BorderType, Borders, Paragraph, Wrap, Clear, Padding, Table, Row, Cell},
     Frame,
 };
 
 use crate::app::App;
 use crate::app::Tab;
+use crate::computer::map::MemoryMap;
+use crate::computer::{Card, IO_BASE};
+use crate::button::{action_button, Button};
+use crate::error::SECTOR_SIZE;
+use crate::usage::UsageReport;
 
 
 
@@ -40,11 +47,135 @@ pub fn render(app: &mut App, frame: &mut Frame) {
         Tab::Main => main::draw_main_tab(frame, app, chunks[1]),
         Tab::Help => main::draw_main_help(frame, app, chunks[1]),
         Tab::Memory => memory::draw_main_tab(frame, app, chunks[1]),
+        Tab::Disassembly => disassembly::draw_main_tab(frame, app, chunks[1]),
+    }
+
+    if let Some(error) = &app.error {
+        draw_error(frame, error);
+    } else if let Some((pc, message)) = &app.breakpoint {
+        draw_breakpoint(frame, *pc, message);
+    } else if let Some(error) = &app.build_error {
+        draw_build_error(frame, error, app.build_error_scroll);
+    } else if let Some(cards) = &app.cards {
+        draw_cards(frame, cards, &app.memory_map, app.card_selected);
+    } else if let Some(usage) = &app.usage {
+        draw_usage(frame, usage);
+    } else if let (Some(scroll), Some(search)) = (app.disk_matches_scroll, &app.search) {
+        draw_disk_matches(frame, &search.disk_matches, scroll);
     }
 
     
     
 
+}
+
+fn draw_error(f: &mut Frame, error: &str) {
+    let title = Paragraph::new("Computer stopped")
+        .alignment(Alignment::Center)
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let text = Paragraph::new(error.to_string());
+    let buttons = Table::new(
+        [Row::new(vec![
+            Cell::from(Line::from(action_button(Button::new("Close".to_string(), Some("Esc".to_string()))))),
+            Cell::from(Line::from(action_button(Button::new("Reset".to_string(), Some("F4".to_string()))))),
+            Cell::from(Line::from(action_button(Button::new("Quit".to_string(), Some("F2".to_string()))))),
+        ])],
+        [Constraint::Ratio(1, 3), Constraint::Ratio(1, 3), Constraint::Ratio(1, 3)],
+    );
+
+    modal(f, title, text, buttons, None);
+}
+
+fn draw_breakpoint(f: &mut Frame, pc: u16, message: &str) {
+    let title = Paragraph::new(format!("Breakpoint at ${:04X}", pc))
+        .alignment(Alignment::Center)
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let text = Paragraph::new(message.to_string());
+    let buttons = Table::new(
+        [Row::new(vec![
+            Cell::from(Line::from(action_button(Button::new("Close".to_string(), Some("Esc".to_string()))))),
+            Cell::from(Line::from(action_button(Button::new("Continue".to_string(), Some("F7".to_string()))))),
+            Cell::from(Line::from(action_button(Button::new("Quit".to_string(), Some("F2".to_string()))))),
+        ])],
+        [Constraint::Ratio(1, 3), Constraint::Ratio(1, 3), Constraint::Ratio(1, 3)],
+    );
+
+    modal(f, title, text, buttons, None);
+}
+
+fn draw_build_error(f: &mut Frame, error: &str, scroll: u16) {
+    let title = Paragraph::new("Build failed, scroll with the arrows")
+        .alignment(Alignment::Center)
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let text = Paragraph::new(error.to_string()).scroll((scroll, 0));
+    let buttons = Table::new(
+        [Row::new(vec![
+            Cell::from(Line::from(action_button(Button::new("Close".to_string(), Some("Esc".to_string()))))),
+            Cell::from(Line::from(action_button(Button::new("Rebuild".to_string(), Some("F9".to_string()))))),
+            Cell::from(Line::from(action_button(Button::new("Quit".to_string(), Some("F2".to_string()))))),
+        ])],
+        [Constraint::Ratio(1, 3), Constraint::Ratio(1, 3), Constraint::Ratio(1, 3)],
+    );
+
+    modal(f, title, text, buttons, None);
+}
+
+fn draw_usage(f: &mut Frame, usage: &UsageReport) {
+    let title = Paragraph::new("Memory usage")
+        .alignment(Alignment::Center)
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let text = Paragraph::new(usage.summary());
+    let buttons = Table::new(
+        [Row::new(vec![
+            Cell::from(Line::from(action_button(Button::new("Close".to_string(), Some("Esc".to_string()))))),
+        ])],
+        [Constraint::Ratio(1, 1)],
+    );
+
+    modal(f, title, text, buttons, None);
+}
+
+fn draw_cards(f: &mut Frame, cards: &[Card], map: &MemoryMap, selected: usize) {
+    let title = Paragraph::new("Cards")
+        .alignment(Alignment::Center)
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let lines: Vec<Line> = cards.iter().enumerate().map(|(i, card)| {
+        let base = IO_BASE + card.slot * 0x10;
+        let text = format!(
+            "Slot {} ${:04X}-${:04X} {:8}{}",
+            card.slot, base, base + 0xF, map.card_name(card), if card.enabled { "enabled" } else { "removed" },
+        );
+        let style = if i == selected { Style::default().add_modifier(Modifier::REVERSED) } else { Style::default() };
+        Line::styled(text, style)
+    }).collect();
+    let text = Paragraph::new(lines);
+    let buttons = Table::new(
+        [Row::new(vec![
+            Cell::from(Line::from(action_button(Button::new("Close".to_string(), Some("Esc".to_string()))))),
+            Cell::from(Line::from(action_button(Button::new("Toggle".to_string(), Some("Space".to_string()))))),
+        ])],
+        [Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)],
+    );
+
+    modal(f, title, text, buttons, None);
+}
+
+fn draw_disk_matches(f: &mut Frame, offsets: &[usize], scroll: usize) {
+    let title = Paragraph::new(format!("{} matches on disk", offsets.len()))
+        .alignment(Alignment::Center)
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let lines: Vec<Line> = offsets.iter().skip(scroll).map(|&offset| {
+        Line::from(format!("Sector {:6} + ${:03X}  offset ${:08X}", offset / SECTOR_SIZE, offset % SECTOR_SIZE, offset))
+    }).collect();
+    let text = Paragraph::new(lines);
+    let buttons = Table::new(
+        [Row::new(vec![
+            Cell::from(Line::from(action_button(Button::new("Close".to_string(), Some("Esc".to_string()))))),
+        ])],
+        [Constraint::Ratio(1, 1)],
+    );
+
+    modal(f, title, text, buttons, None);
 }
 
 pub fn modal(f: &mut Frame, title: Paragraph, text: Paragraph, buttons: Table, input: Option<Paragraph>) -> Rc<[Rect]>
@@ -84,5 +215,5 @@ pub fn modal(f: &mut Frame, title: Paragraph, text: Paragraph, buttons: Table, i
     }
     f.render_widget(buttons, Rect::new(chunks[3].x + 1, chunks[3].y, chunks[3].width - 2, chunks[3].height));
 
-    return chunks;
+    chunks
 }
\ No newline at end of file
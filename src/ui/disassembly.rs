@@ -0,0 +1,93 @@
+use itertools::Itertools;
+use std::sync::atomic::Ordering;
+
+use ratatui::{prelude::*, widgets::*};
+
+use crate::{app::App, button::Button};
+use crate::computer::disassembler;
+use crate::ui::header;
+
+
+pub fn draw_main_tab(f: &mut Frame, app: &mut App, area: Rect)
+{
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(0)
+        .constraints(
+            [
+                Constraint::Min(20),
+                Constraint::Max(1),     // Tab Footer
+            ]
+            .as_ref(),
+        )
+        .split(area);
+
+    let sides = Layout::default()
+    .direction(Direction::Horizontal)
+    .margin(0)
+    .constraints(
+        [
+            Constraint::Max(40),
+            Constraint::Min(0)
+        ]
+        .as_ref(),
+    ).split(chunks[0]);
+
+    let block = Block::default()
+        .title("Disassembly").title_alignment(Alignment::Center)
+        .borders(Borders::NONE);
+    let rows = block.inner(sides[0]).height as usize;
+    app.visible_rows = rows;
+
+    let code: Vec<Line> = if app.mem.is_empty() {
+        vec![]
+    } else {
+        disassembler::disassemble_range(&app.mem, app.processor.pc, rows).iter().flat_map(|inst| {
+            // Symbols get a line of their own, like in an assembler listing
+            let label = app.symbols.get(inst.address)
+                .map(|name| Line::styled(format!("{}:", name), Style::default().fg(Color::Cyan)));
+            let line = format!("{:04X}  {:<9} {}", inst.address, inst.bytes.iter().map(|b| format!("{:02X}", b)).join(" "), inst.text);
+            let line = if inst.address == app.processor.pc {
+                Line::styled(line, Style::default().fg(Color::Black).bg(Color::Yellow))
+            } else {
+                Line::from(line)
+            };
+            label.into_iter().chain([line])
+        }).take(rows).collect()
+    };
+
+    let p = Paragraph::new(code).block(block);
+    f.render_widget(p, sides[0]);
+
+    let txt = vec![
+        Line::from(format!("PC: {:04X}", app.processor.pc)),
+        Line::from(format!("SP: {:04X}", app.processor.sp)),
+        Line::from(format!("ST: {:04X}", app.processor.flags)),
+        Line::from(""),
+        Line::from(format!("A: {:02X}", app.processor.acc)),
+        Line::from(format!("X: {:02X}", app.processor.rx)),
+        Line::from(format!("Y: {:02X}", app.processor.ry)),
+        Line::from(""),
+        Line::from(format!("Cycles: {}", app.metrics.cycles.load(Ordering::Relaxed))),
+        Line::from(format!("Instr:  {}", app.metrics.instructions.load(Ordering::Relaxed))),
+        Line::from(format!("IRQs:   {}", app.metrics.irqs.load(Ordering::Relaxed))),
+        Line::from(format!("Frames: {}", app.metrics.frames.load(Ordering::Relaxed))),
+        Line::from(format!("Load:   {:.0}%", app.frame_load())),
+    ];
+
+    let p = Paragraph::new(txt)
+        .block(Block::default()
+        .title("Processor")
+            .borders(Borders::NONE)
+        );
+    f.render_widget(p, sides[1]);
+
+    let buttons = vec![
+        Button::new("Quit".to_string(), Some("2".to_string())),
+        Button::new("Main".to_string(), Some("3".to_string())),
+        Button::new("Reset".to_string(), Some("4".to_string())),
+        Button::new("Pause".to_string(), Some("7".to_string())),
+    ];
+
+    header::draw_footer(f, chunks[1], buttons); 
+}
@@ -16,7 +16,7 @@ pub fn draw_header(frame: &mut Frame, app: &mut App, area: Rect)
     .constraints(
         [
             Constraint::Min(20),
-            Constraint::Max(30)
+            Constraint::Max(40)
         ].as_ref()
     )
     .split(area);
@@ -33,8 +33,14 @@ pub fn draw_header(frame: &mut Frame, app: &mut App, area: Rect)
     frame.render_widget(p, header[0]);
 
 
-    let sl = Paragraph::new(Span::styled(format!("Log level: {} {:03.2} MHz  ", app.log_level, app.speed/1000.0), 
-    Style::new().white().on_green().add_modifier(Modifier::BOLD)))
+    let mut status = vec![];
+    if app.fast_forward {
+        status.push(Span::styled(" >> FAST ", Style::new().white().on_red().add_modifier(Modifier::BOLD)));
+    }
+    status.push(Span::styled(format!(" Log level: {} {:03.2} MHz  ", app.log_level, app.speed/1000.0), 
+    Style::new().white().on_green().add_modifier(Modifier::BOLD)));
+
+    let sl = Paragraph::new(Line::from(status))
     
     .alignment(Alignment::Right)
         .block(Block::default()
@@ -59,7 +65,7 @@ pub fn draw_footer(f: &mut Frame, area: Rect, buttons: Vec<Button>)
 
     f.render_widget(block, area);
 
-    let constraints = Constraint::from_ratios(buttons.iter().map(|_| (1 as u32, buttons.len() as u32)).into_iter());
+    let constraints = Constraint::from_ratios(buttons.iter().map(|_| (1, buttons.len() as u32)));
 
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
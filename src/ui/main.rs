@@ -1,18 +1,26 @@
 
 use itertools::Itertools;
-use ratatui::{Frame, prelude::*, widgets::{Paragraph, Block, Borders, Wrap, Scrollbar, ScrollbarOrientation}};
+use ratatui::{Frame, prelude::*, widgets::{Paragraph, Block, Borders, Wrap, Scrollbar, ScrollbarOrientation, Table, Row}};
 
 
 use crate::{app::App, button::Button};
+use crate::computer::opcodes::OPCODES;
 use crate::ui::header;
 
 const MAIN_HELP_TEXT: &str = "
 This is the Planck 6502 emulator. Enjoy
+With --rtc, F10 moves the RTC forward by an hour and Shift+F10 by a day.
+With --trap, writing a message address to $FFA0-$FFA1 pauses, F7 continues.
+With --irq-order, $FF90 reads the highest priority slot asserting IRQ.
+Ctrl+S saves the cards, symbols, breakpoints, watches and patches in use,
+plu --session FILE or Ctrl+O loads them. b in Disassembly stops at the PC,
+w in Memory watches the top row's first byte and x stops on writes to it.
+F12 pulses the SO pin, setting the overflow flag. Devices pulse it with so = 1.
 ";
 
 
 
-pub fn draw_main_help(f: &mut Frame, _: &mut App, area: Rect)
+pub fn draw_main_help(f: &mut Frame, app: &mut App, area: Rect)
 {
 
     let chunks = Layout::default()
@@ -20,7 +28,8 @@ pub fn draw_main_help(f: &mut Frame, _: &mut App, area: Rect)
     .margin(0)
     .constraints(
         [
-            Constraint::Min(6),     // Help text
+            Constraint::Length(10),     // Help text
+            Constraint::Min(4),     // Instruction reference
             Constraint::Max(1),     // Tab Footer
         ]
         .as_ref(),
@@ -37,12 +46,29 @@ pub fn draw_main_help(f: &mut Frame, _: &mut App, area: Rect)
         ;
     f.render_widget(p, chunks[0]);    
 
+    let rows = OPCODES.iter().enumerate().skip(app.reference_scroll).take(chunks[1].height as usize).map(|(opcode, op)| {
+        Row::new(vec![
+            format!("${:02X}", opcode),
+            if op.mnemonic.is_empty() { String::from("???") } else { op.mnemonic.to_string() },
+            format!("{:?}", op.mode),
+            op.bytes.to_string(),
+            op.cycles.to_string(),
+        ])
+    });
+    let t = Table::new(rows, [Constraint::Length(7), Constraint::Length(9), Constraint::Length(18), Constraint::Length(7), Constraint::Length(7)])
+        .header(Row::new(vec!["Opcode", "Mnemonic", "Mode", "Bytes", "Cycles"]).style(Style::default().add_modifier(Modifier::BOLD)))
+        .block(Block::default()
+            .title(" Instruction reference ")
+            .borders(Borders::TOP)
+        );
+    f.render_widget(t, chunks[1]);
+
     let buttons = vec![
         Button::new("Close".to_string(), Some("1".to_string())),
         Button::new("Quit".to_string(), Some("2".to_string())),
         Button::new("Memory".to_string(), Some("3".to_string())),
     ];
-    header::draw_footer(f, chunks[1], buttons);
+    header::draw_footer(f, chunks[2], buttons);
 
 }
 
@@ -73,28 +99,23 @@ pub fn draw_main_tab(f: &mut Frame, app: &mut App, area: Rect)
         ;
     f.render_widget(p, chunks[0]);    
   
-    let mut output: Vec<Line> = app.output.iter().map(|l| Line::from(l.as_str())).collect();
-    app.output_scroll_state = app.output_scroll_state.content_length(output.len());
+    app.output.set_columns(chunks[1].width.saturating_sub(2) as usize);
+    let rows = app.output.len();
+    app.output_scroll_state = app.output_scroll_state.content_length(rows);
 
-    if output.len() < app.output_scroll {
-        app.output_scroll = output.len();
-    }
-
-    if output.len() > 1 && output.len() - 1 < app.output_scroll {
-        app.output_scroll = output.len() - 1;
-    }
+    // There is always at least the row holding the cursor
+    app.output_scroll = app.output_scroll.min(rows - 1);
 
     let ch = chunks[1].height as usize - 2;
-    if output.len() > ch && output.len() - ch < app.output_scroll {
-        app.output_scroll = output.len() - ch;
-    }
-
-
-
-    if output.len() >= app.output_scroll {
-        output.drain(0..app.output_scroll);
+    app.output_size = (app.output.columns() as u16, ch as u16);
+    if rows > ch && rows - ch < app.output_scroll {
+        app.output_scroll = rows - ch;
     }
 
+    // Only the rows on screen are turned into text
+    let output: Vec<Line> = app.output.rows(app.output_scroll, ch)
+        .map(|row| Line::from(row.iter().map(|&b| b as char).collect::<String>()))
+        .collect();
 
     let p = Paragraph::new(output)
 
@@ -104,8 +125,6 @@ pub fn draw_main_tab(f: &mut Frame, app: &mut App, area: Rect)
             .title(" Output ")
             .title_alignment(Alignment::Center)
         )
-        
-        .wrap(Wrap { trim: false })
         ;
     f.render_widget(p, chunks[1]);   
 
@@ -118,7 +137,7 @@ pub fn draw_main_tab(f: &mut Frame, app: &mut App, area: Rect)
         &mut app.output_scroll_state,
     );
 
-    let cy = chunks[1].y + app.output.len() as u16 - app.output_scroll as u16;
+    let cy = chunks[1].y + (rows - app.output_scroll) as u16;
     // if cy > chunks[1].y+chunks[1].height - 2 {
     //     cy = chunks[1].y+chunks[1].height - 2;
     // }
@@ -126,7 +145,7 @@ pub fn draw_main_tab(f: &mut Frame, app: &mut App, area: Rect)
         f.set_cursor(
             // Draw the cursor at the current position in the input field.
             // This position is can be controlled via the left and right arrow key
-            chunks[1].x + app.cursor_position as u16 + 1,
+            chunks[1].x + app.output.cursor() as u16 + 1,
             // Move one line down, from the border to the input line
             cy,
         );
@@ -134,7 +153,7 @@ pub fn draw_main_tab(f: &mut Frame, app: &mut App, area: Rect)
 
 
 
-    let buttons = vec![
+    let mut buttons = vec![
         Button::new("Help".to_string(), Some("1".to_string())),
         Button::new("Quit".to_string(), Some("2".to_string())),
         Button::new("Memory".to_string(), Some("3".to_string())),
@@ -143,7 +162,11 @@ pub fn draw_main_tab(f: &mut Frame, app: &mut App, area: Rect)
         Button::new("Debug -".to_string(), Some("5".to_string())),
         Button::new("Debug +".to_string(), Some("6".to_string())),
         Button::new("Pause".to_string(), Some("7".to_string())),
+        Button::new("Fast".to_string(), Some("8".to_string())),
     ];
+    if app.project.is_some() {
+        buttons.push(Button::new("Build".to_string(), Some("9".to_string())));
+    }
 
     header::draw_footer(f, chunks[2], buttons); 
 }
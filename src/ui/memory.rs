@@ -1,7 +1,9 @@
-use itertools::Itertools;
+use std::sync::atomic::Ordering;
+
 use ratatui::{prelude::*, widgets::*};
 
-use crate::{app::App, button::Button};
+use crate::{app::{App, InputMode}, button::Button};
+use crate::computer::map::Region;
 use crate::ui::header;
 
 
@@ -12,6 +14,7 @@ pub fn draw_main_tab(f: &mut Frame, app: &mut App, area: Rect)
         .margin(0)
         .constraints(
             [
+                Constraint::Max(1),     // Owner of the top row
                 Constraint::Min(20),
                 Constraint::Max(1),     // Tab Footer
             ]
@@ -29,30 +32,67 @@ pub fn draw_main_tab(f: &mut Frame, app: &mut App, area: Rect)
             Constraint::Min(0)
         ]
         .as_ref(),
-    ).split(chunks[0]);
-
-    let ch = app.mem.chunks(16);
-
-
+    ).split(chunks[1]);
+    app.visible_rows = sides[0].height as usize;
+
+    let rows = app.mem.len() / 16;
+    app.memory_scroll_state = app.memory_scroll_state.content_length(rows);
+    // Keep at least the last row on screen
+    app.memory_scroll = app.memory_scroll.min(rows - 1);
+
+    // Only format the rows that fit on screen
+    let visible = app.mem.chunks(16).enumerate().skip(app.memory_scroll).take(app.visible_rows);
+
+    let found = Style::default().fg(Color::Black).bg(Color::Yellow);
+    let search = app.search.as_ref();
+    let map = &app.memory_map;
+    let style = |addr: usize| if search.is_some_and(|s| s.covers(addr)) {
+        found
+    } else {
+        match map.region(addr as u16) {
+            Region::Ram => Style::default(),
+            Region::Rom => Style::default().fg(Color::Cyan),
+            Region::Card(_) => Style::default().fg(Color::Magenta),
+            Region::Unmapped => Style::default().fg(Color::DarkGray),
+        }
+    };
+
+    // Name what answers at the top row, the I/O window rows are the registers of one card
+    let top = (app.memory_scroll * 16) as u16;
+    let owner = match map.region(top) {
+        Region::Ram => String::from("RAM"),
+        Region::Rom => String::from("ROM"),
+        Region::Card(card) => format!("{} in slot {}", map.card_name(card), card.slot),
+        Region::Unmapped => String::from("Open bus"),
+    };
+    let mut status = vec![Span::styled(format!("{:04X}: {}", top, owner), style(top as usize))];
+    // Followed by the watched bytes, read every tick wherever the view is
+    for &addr in &app.session.watches {
+        let label = app.symbols.get(addr).map_or_else(|| format!("${:04X}", addr), String::from);
+        status.push(Span::raw(format!("  {}: {:02X}", label, app.mem[addr as usize])));
+    }
+    f.render_widget(Paragraph::new(Line::from(status)), chunks[0]);
 
-    let mut hex: Vec<Line> = ch.map(|c| c.as_ref().iter()).enumerate().map(|(i, x)| {
-        return Line::from(format!("{:04X} {} ", i*16, x.map(|n| format!("{:02X}", n)).join(" ") ))
+    let hex: Vec<Line> = visible.clone().map(|(i, x)| {
+        let mut spans = vec![Span::raw(format!("{:04X} ", i*16))];
+        for (j, n) in x.iter().enumerate() {
+            if j > 0 {
+                spans.push(Span::raw(" "));
+            }
+            spans.push(Span::styled(format!("{:02X}", n), style(i * 16 + j)));
+        }
+        spans.push(Span::raw(" "));
+        Line::from(spans)
     }).collect();
 
-    app.memory_scroll_state = app.memory_scroll_state.content_length(hex.len());
-
-    if app.memory_scroll > hex.len() {
-        app.memory_scroll = hex.len();
-    }
-    if hex.len() >= app.memory_scroll {
-        hex.drain(0..app.memory_scroll);
-    }
-    
-    // let hex = app.mem.iter().map(|&x| format!("{:X}", x)).join(" ");
+    let title = match &app.search {
+        Some(s) => format!("Memory Hex - {} matches, {} on disk", s.matches.len(), s.disk_matches.len()),
+        None => String::from("Memory Hex"),
+    };
 
     let p = Paragraph::new(hex)
         .block(Block::default()
-        .title("Memory Hex").title_alignment(Alignment::Center)
+        .title(title).title_alignment(Alignment::Center)
             .borders(Borders::NONE)
         )
         
@@ -68,6 +108,17 @@ pub fn draw_main_tab(f: &mut Frame, app: &mut App, area: Rect)
         Line::from(format!("A: {:02X}", app.processor.acc)),
         Line::from(format!("X: {:02X}", app.processor.rx)),
         Line::from(format!("Y: {:02X}", app.processor.ry)),
+        Line::from(""),
+        Line::from("Cycles:"),
+        Line::from(format!("{}", app.metrics.cycles.load(Ordering::Relaxed))),
+        Line::from("Instr:"),
+        Line::from(format!("{}", app.metrics.instructions.load(Ordering::Relaxed))),
+        Line::from("IRQs:"),
+        Line::from(format!("{}", app.metrics.irqs.load(Ordering::Relaxed))),
+        Line::from("Frames:"),
+        Line::from(format!("{}", app.metrics.frames.load(Ordering::Relaxed))),
+        Line::from("Load:"),
+        Line::from(format!("{:.0}%", app.frame_load())),
     ];
 
     let p = Paragraph::new(txt)
@@ -80,20 +131,13 @@ pub fn draw_main_tab(f: &mut Frame, app: &mut App, area: Rect)
         ;
     f.render_widget(p, sides[2]);   
 
-    let mut ascii: Vec<Line> = app.mem.chunks(16).map(|c| c.as_ref().iter()).enumerate().map(|(_, x)| {
-        // println!("{:?}", x);
-         return Line::from(x.map(|&n| {
-            if n > 0x20 && n < 0x7F {
-                return format!("{}", n as char);
-            }
-            return String::from(".");
-         }).join("") )
+    let ascii: Vec<Line> = visible.map(|(i, x)| {
+         Line::from(x.iter().enumerate().map(|(j, &n)| {
+            let c = if n > 0x20 && n < 0x7F { n as char } else { '.' };
+            Span::styled(c.to_string(), style(i * 16 + j))
+         }).collect::<Vec<Span>>())
      }).collect();
 
-    if ascii.len() >= app.memory_scroll {
-        ascii.drain(0..app.memory_scroll);
-    }
-
     let p = Paragraph::new(ascii)
     .block(Block::default()
     .title("ASCII").title_alignment(Alignment::Center)
@@ -114,12 +158,31 @@ pub fn draw_main_tab(f: &mut Frame, app: &mut App, area: Rect)
     );
 
 
-    let buttons = vec![
+    if app.input.mode != InputMode::Normal {
+        let prompt = if app.input.mode == InputMode::Eval { "Eval: " } else { "Search (hex bytes or \"text\"): " };
+        f.render_widget(Paragraph::new(format!("{}{}", prompt, app.input.value)), chunks[2]);
+        f.set_cursor(chunks[2].x + prompt.len() as u16 + app.input.cursor_position, chunks[2].y);
+        return;
+    }
+
+    if let Some(eval) = &app.eval {
+        f.render_widget(Paragraph::new(eval.as_str()), chunks[2]);
+        return;
+    }
+
+    let mut buttons = vec![
         
         Button::new("Quit".to_string(), Some("2".to_string())),
-        Button::new("Main".to_string(), Some("3".to_string())),
+        Button::new("Disasm".to_string(), Some("3".to_string())),
         Button::new("Reset".to_string(), Some("4".to_string())),
+        Button::new("Search".to_string(), Some("/".to_string())),
+        Button::new("Usage".to_string(), Some("u".to_string())),
+        Button::new("Cards".to_string(), Some("c".to_string())),
+        Button::new("Eval".to_string(), Some("e".to_string())),
     ];
+    if app.search.is_some() {
+        buttons.push(Button::new("Disk".to_string(), Some("d".to_string())));
+    }
 
-    header::draw_footer(f, chunks[1], buttons); 
+    header::draw_footer(f, chunks[2], buttons); 
 }
@@ -0,0 +1,95 @@
+use std::fmt;
+use std::ops::{Range, RangeInclusive};
+
+/// Which parts of the address space a program touched since its ROM was loaded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UsageReport {
+    /// RAM regions written at least once, writes to the I/O area and the ROM are left out
+    pub written: Vec<RangeInclusive<u16>>,
+    /// Lowest stack address written, the stack grows down from $01FF
+    pub stack_low: Option<u16>,
+    pub rom_size: usize,
+    /// ROM bytes never run as an opcode or operand, without the I/O area and vectors
+    pub unexecuted: Vec<RangeInclusive<u16>>,
+}
+
+/// Card registers, which are not memory
+const IO: RangeInclusive<usize> = 0xFF80..=0xFFEF;
+
+/// Addresses where the ROM cannot be run from
+const NOT_CODE: [RangeInclusive<usize>; 2] = [IO, 0xFFFA..=0xFFFF];
+
+impl UsageReport {
+    pub fn new(written: &[bool], executed: &[bool], rom: Range<usize>) -> UsageReport {
+        let stack_low = (0x100..0x200).find(|&a| written[a]).map(|a| a as u16);
+        let unexecuted = regions(rom.clone().filter(|&a| !executed[a] && !NOT_CODE.iter().any(|r| r.contains(&a))));
+        UsageReport {
+            written: regions((0..written.len()).filter(|&a| written[a] && !rom.contains(&a) && !IO.contains(&a))),
+            stack_low,
+            rom_size: rom.len(),
+            unexecuted,
+        }
+    }
+
+    /// Deepest the stack went, in bytes
+    pub fn stack_depth(&self) -> usize {
+        self.stack_low.map_or(0, |low| 0x200 - low as usize)
+    }
+
+    pub fn written_bytes(&self) -> usize {
+        size(&self.written)
+    }
+
+    pub fn unexecuted_bytes(&self) -> usize {
+        size(&self.unexecuted)
+    }
+
+    /// A few lines for the UI, the full report is the [`Display`](fmt::Display) output
+    pub fn summary(&self) -> String {
+        format!(
+            "Written: {} bytes in {} regions\nStack depth: {} bytes\nROM never run: {} of {} bytes in {} regions",
+            self.written_bytes(), self.written.len(), self.stack_depth(),
+            self.unexecuted_bytes(), self.rom_size, self.unexecuted.len()
+        )
+    }
+}
+
+impl fmt::Display for UsageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Memory written: {} bytes", self.written_bytes())?;
+        for r in &self.written {
+            writeln!(f, "  {}", region(r))?;
+        }
+        match self.stack_low {
+            Some(low) => writeln!(f, "Stack depth: {} bytes, down to ${:04X}", self.stack_depth(), low)?,
+            None => writeln!(f, "Stack depth: unused")?,
+        }
+        writeln!(f, "ROM never run: {} of {} bytes", self.unexecuted_bytes(), self.rom_size)?;
+        for r in &self.unexecuted {
+            writeln!(f, "  {}", region(r))?;
+        }
+        Ok(())
+    }
+}
+
+fn region(r: &RangeInclusive<u16>) -> String {
+    let len = *r.end() as usize - *r.start() as usize + 1;
+    format!("${:04X}-${:04X} {:>6} bytes", r.start(), r.end(), len)
+}
+
+fn size(regions: &[RangeInclusive<u16>]) -> usize {
+    regions.iter().map(|r| *r.end() as usize - *r.start() as usize + 1).sum()
+}
+
+/// Merges increasing addresses into contiguous regions
+fn regions(addresses: impl Iterator<Item = usize>) -> Vec<RangeInclusive<u16>> {
+    let mut regions: Vec<RangeInclusive<u16>> = vec![];
+    for a in addresses {
+        let a = a as u16;
+        match regions.last_mut() {
+            Some(r) if *r.end() as usize + 1 == a as usize => *r = *r.start()..=a,
+            _ => regions.push(a..=a),
+        }
+    }
+    regions
+}
@@ -0,0 +1,519 @@
+mod common;
+
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use plu::app::{self, App, InputMode, Tab};
+use plu::computer::{CardType, ControllerMessage, Request, IRQ_NONE, IRQ_SLOT, RTC_SLOT, TRAP_SLOT};
+use plu::computer::device::{Device, Handlers};
+use plu::error::Error;
+use plu::rom::RomOptions;
+use plu::symbols::Symbols;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[test]
+fn boots_rom_and_prints_to_serial() {
+    let mut app = App::with_data(common::rom(&common::PRINT, b"HELLO\rWORLD\0"), vec![]).unwrap();
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.output_text().ends_with("WORLD"));
+
+    assert_eq!(app.output_text(), "HELLO\nWORLD");
+    assert_eq!(app.output.cursor(), 5);
+}
+
+#[test]
+fn backspace_removes_last_character() {
+    let mut app = App::with_data(common::rom(&common::PRINT, b"ABC\x08D\0"), vec![]).unwrap();
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.output_text().ends_with('D'));
+
+    assert_eq!(app.output_text(), "ABD");
+}
+
+#[test]
+fn reports_processor_state() {
+    let mut app = App::with_data(common::rom(&common::PRINT, b"\0"), vec![]).unwrap();
+    // Registers are only polled on the tabs showing them
+    app.current_tab = Tab::Disassembly;
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.processor.pc == 0xFF0D);
+
+    assert_eq!(app.processor.acc, 0);
+    assert_eq!(app.processor.rx, 0);
+}
+
+#[test]
+fn reset_runs_rom_again() {
+    let mut app = App::with_data(common::rom(&common::PRINT, b"HI\0"), vec![]).unwrap();
+    common::tick_until(&mut app, TIMEOUT, |app| app.output_text() == "HI");
+
+    let _ = app.tx.send(ControllerMessage::Reset);
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.output_text() == "HIHI");
+}
+
+#[test]
+fn rejects_invalid_images() {
+    assert!(matches!(App::with_data(vec![], vec![]), Err(Error::EmptyRom)));
+    assert!(matches!(App::with_data(vec![0; 0x10001], vec![]), Err(Error::RomTooLarge(0x10001))));
+    assert!(matches!(App::with_data(common::rom(&common::PRINT, b"\0"), vec![0; 100]), Err(Error::DiskSize(100))));
+    assert!(matches!(App::new("does/not/exist.bin".to_string(), None, RomOptions::default()), Err(Error::File { .. })));
+}
+
+#[test]
+fn reports_faults_to_the_ui() {
+    let code = [
+        0xA9, 0x01,         // LDA #$01
+        0x8D, 0xD3, 0xFF,   // STA $FFD3, LBA 1
+        0xA9, 0x20,         // LDA #$20
+        0x8D, 0xD7, 0xFF,   // STA $FFD7, read command
+        0xAD, 0xD0, 0xFF,   // LDA $FFD0
+        0x80, 0xFE,         // BRA *
+    ];
+    let mut app = App::with_data(common::rom(&code, b""), vec![0; 512]).unwrap();
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.error.is_some());
+
+    assert!(app.error.as_ref().unwrap().contains("beyond the end of the disk"));
+}
+
+#[test]
+fn reads_and_writes_memory_by_request() {
+    let mut app = App::with_data(common::rom(&common::PRINT, b"\0"), vec![]).unwrap();
+
+    app.request(Request::WriteByte { addr: 0x0200, value: 0x42 });
+    let id = app.request(Request::ReadRange { start: 0x01FF, len: 2 });
+    common::tick_until(&mut app, TIMEOUT, |app| app.last_reply_id >= id);
+
+    assert_eq!(app.mem[0x01FF..=0x0201], [0x00, 0x42, 0x00]);
+}
+
+#[test]
+fn memory_tab_reads_visible_window() {
+    let mut app = App::with_data(common::rom(&common::PRINT, b"\0"), vec![]).unwrap();
+    app.current_tab = Tab::Memory;
+    app.memory_scroll = 0xFF0;
+    app.visible_rows = 4;
+
+    let id = app.last_request_id;
+    common::tick_until(&mut app, TIMEOUT, |app| app.last_reply_id > id + 1);
+
+    // ROM code is visible, the reset vector is not
+    assert_eq!(app.mem[0xFF00..0xFF02], common::PRINT[..2]);
+    assert_eq!(app.mem[0xFFFC], 0);
+}
+
+#[test]
+fn publishes_counters() {
+    let mut app = App::with_data(common::rom(&common::PRINT, b"HELLO\0"), vec![]).unwrap();
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.metrics.instructions.load(Ordering::Relaxed) > 1000);
+
+    assert!(app.metrics.cycles.load(Ordering::Relaxed) >= app.metrics.instructions.load(Ordering::Relaxed));
+    assert_eq!(app.metrics.irqs.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn does_not_count_brk_as_an_interrupt() {
+    // BRK goes through the IRQ vector, which points back to it
+    let mut app = App::with_data(common::rom(&[0x00, 0xEA], b""), vec![]).unwrap();
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.metrics.instructions.load(Ordering::Relaxed) > 1000);
+
+    assert_eq!(app.metrics.irqs.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn keeps_up_with_fast_output() {
+    let text = "0123456789ABCDEF".repeat(8);
+    let mut data = text.clone().into_bytes();
+    data.push(0);
+    let mut app = App::with_data(common::rom(&common::PRINT, &data), vec![]).unwrap();
+    app.set_fast_forward(true);
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.output_text() == text);
+
+    assert_eq!(app.output.cursor(), text.len() % app.output.columns());
+    assert_eq!(app.metrics.output_backlog.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn searches_memory_and_disk() {
+    let mut disk = vec![0; 1024];
+    disk[600..605].copy_from_slice(b"HELLO");
+    let mut app = App::with_data(common::rom(&common::PRINT, b"HELLO\0"), disk).unwrap();
+    app.current_tab = Tab::Memory;
+
+    app.start_search();
+    app.input.value = String::from("\"HELLO\"");
+    app.submit_search();
+
+    common::tick_until(&mut app, TIMEOUT, |app| {
+        app.search.as_ref().is_some_and(|s| !s.matches.is_empty() && !s.disk_matches.is_empty())
+    });
+    let search = app.search.clone().unwrap();
+    assert_eq!(search.matches, vec![0xFF40]);
+    assert_eq!(search.disk_matches, vec![600]);
+
+    app.next_match();
+    assert_eq!(app.memory_scroll, 0xFF4);
+}
+
+#[test]
+fn reports_memory_usage() {
+    let code = [
+        0xA9, 0x48,         // LDA #'H'
+        0x8D, 0x00, 0x02,   // STA $0200
+        0x8D, 0x00, 0xFF,   // STA $FF00, the ROM
+        0x8D, 0xE0, 0xFF,   // STA $FFE0, the serial card
+        0x80, 0xFE,         // BRA *
+    ];
+    let mut app = App::with_data(common::rom(&code, b""), vec![]).unwrap();
+    common::tick_until(&mut app, TIMEOUT, |app| app.output_text() == "H");
+
+    let report = app.usage_report().unwrap();
+
+    // Only RAM counts as written
+    assert_eq!(report.written, vec![0x0200..=0x0200]);
+    assert_eq!(report.stack_low, None);
+    // Everything after the program
+    assert_eq!(report.unexecuted, vec![0xFF0D..=0xFF7F, 0xFFF0..=0xFFF9]);
+}
+
+#[test]
+fn reads_the_rtc_card() {
+    let code = [
+        0xAD, 0xB7, 0xFF,   // LDA $FFB7, century
+        0x8D, 0x00, 0x02,   // STA $0200
+        0x80, 0xF8,         // BRA $FF00
+    ];
+    let mut app = App::with_data(common::rom(&code, b""), vec![]).unwrap();
+    app.insert_card(RTC_SLOT, CardType::Rtc);
+    // The Memory tab keeps the rows on screen up to date
+    app.current_tab = Tab::Memory;
+    app.memory_scroll = 0x20;
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.mem[0x200] == 0x20);
+}
+
+#[test]
+fn resumes_where_it_stopped() {
+    let rom = common::rom(&common::PRINT, b"HI\0");
+    let path = common::temp_dir("resume").join("rom.bin.snapshot");
+    let mut app = App::with_data(rom.clone(), vec![]).unwrap();
+    common::tick_until(&mut app, TIMEOUT, |app| app.output_text() == "HI");
+    app.request(Request::WriteByte { addr: 0x0200, value: 0x42 });
+    app.hibernate(&path).unwrap();
+
+    let mut resumed = App::with_data(rom, vec![]).unwrap();
+    assert!(resumed.resume(&path).unwrap());
+    let id = resumed.request(Request::ReadRange { start: 0x0200, len: 1 });
+    common::tick_until(&mut resumed, TIMEOUT, |app| app.last_reply_id >= id);
+
+    // The program is not run again from reset
+    assert_eq!(resumed.output_text(), "HI");
+    assert_eq!(resumed.mem[0x0200], 0x42);
+    assert!(resumed.processor.clock > 0);
+
+    let mut other = App::with_data(common::rom(&common::PRINT, b"HO\0"), vec![]).unwrap();
+    assert!(matches!(other.resume(&path), Err(Error::Snapshot(_))));
+    std::fs::remove_file(&path).unwrap();
+    assert!(!other.resume(&path).unwrap());
+}
+
+#[test]
+fn resumes_with_the_cards_of_the_snapshot() {
+    let rom = common::rom(&common::PRINT, b"HI\0");
+    let disk = vec![0; 1024];
+    let path = common::temp_dir("resume-cards").join("rom.bin.snapshot");
+    let mut app = App::with_data(rom.clone(), disk.clone()).unwrap();
+    app.insert_card(RTC_SLOT, CardType::Rtc);
+    common::tick_until(&mut app, TIMEOUT, |app| app.output_text() == "HI");
+    app.hibernate(&path).unwrap();
+
+    // Cards asked for when resuming join the ones of the snapshot
+    let mut resumed = App::with_data(rom.clone(), disk).unwrap();
+    assert!(resumed.resume(&path).unwrap());
+    resumed.insert_card(TRAP_SLOT, CardType::Trap);
+    resumed.request(Request::Cards);
+    common::tick_until(&mut resumed, TIMEOUT, |app| app.cards.is_some());
+    let cards = resumed.cards.clone().unwrap();
+    assert!(cards.iter().any(|c| c.slot == RTC_SLOT && c.card_type == CardType::Rtc));
+    assert!(cards.iter().any(|c| c.slot == TRAP_SLOT && c.card_type == CardType::Trap));
+    assert!(resumed.session.rtc && resumed.session.trap);
+
+    // The disk in the snapshot would hide the changes made to the image
+    let mut changed = App::with_data(rom, vec![0xFF; 1024]).unwrap();
+    assert!(matches!(changed.resume(&path), Err(Error::Snapshot(_))));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn boots_with_the_cards_inserted_after_construction() {
+    let code = [
+        0xAD, 0xB7, 0xFF,   // LDA $FFB7, century
+        0x8D, 0x00, 0x02,   // STA $0200
+        0x80, 0xFE,         // BRA *
+    ];
+    let mut app = App::with_data(common::rom(&code, b""), vec![]).unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+    app.insert_card(RTC_SLOT, CardType::Rtc);
+
+    // Nothing runs before the computer is booted
+    assert_eq!(app.usage_report().unwrap().written, vec![]);
+    app.boot();
+    app.current_tab = Tab::Memory;
+    app.memory_scroll = 0x20;
+    common::tick_until(&mut app, TIMEOUT, |app| app.mem[0x200] == 0x20);
+}
+
+#[test]
+fn disabled_cards_read_as_open_bus() {
+    let code = [
+        0xAD, 0xB7, 0xFF,   // LDA $FFB7, century
+        0x8D, 0x00, 0x02,   // STA $0200
+        0x80, 0xF8,         // BRA $FF00
+    ];
+    let mut app = App::with_data(common::rom(&code, b""), vec![]).unwrap();
+    app.insert_card(RTC_SLOT, CardType::Rtc);
+    app.current_tab = Tab::Memory;
+    app.memory_scroll = 0x20;
+    common::tick_until(&mut app, TIMEOUT, |app| app.mem[0x200] == 0x20);
+
+    app.request(Request::Cards);
+    common::tick_until(&mut app, TIMEOUT, |app| app.cards.is_some());
+    assert_eq!(app.cards.as_ref().unwrap()[0].slot, RTC_SLOT);
+    app.card_selected = 0;
+    app.toggle_card();
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.mem[0x200] == 0xFF);
+}
+
+#[test]
+fn stops_on_writes_to_shadowed_rom_with_strict_map() {
+    let code = [
+        0x8D, 0xB0, 0xFF,   // STA $FFB0
+        0x80, 0xFE,         // BRA *
+    ];
+    let mut data = vec![0; 0x80];
+    data[0x74] = b'A';   // $FFB4
+    let mut app = App::with_data(common::rom(&code, &data), vec![]).unwrap();
+    app.set_strict_map(true);
+    app.insert_card(RTC_SLOT, CardType::Rtc);
+    let _ = app.tx.send(ControllerMessage::Reset);
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.error.is_some());
+
+    assert!(app.debug.iter().any(|l| l == "Address conflict: Rtc card in slot 3 hides ROM data at $FFB0-$FFBF"));
+    assert!(app.error.as_ref().unwrap().starts_with("Write to $FFB0 hits both the Rtc card in slot 3 and the ROM"));
+}
+
+#[test]
+fn pauses_on_guest_breakpoints() {
+    let code = [
+        0xA9, 0x40,         // LDA #<message
+        0x8D, 0xA0, 0xFF,   // STA $FFA0
+        0xA9, 0xFF,         // LDA #>message
+        0x8D, 0xA1, 0xFF,   // STA $FFA1, $FF07
+        0xA9, 0x07,         // LDA #$07
+        0x8D, 0xA2, 0xFF,   // STA $FFA2, $FF0C
+        0x80, 0xFE,         // BRA *
+    ];
+    let mut app = App::with_data(common::rom(&code, b"HALT HERE\0"), vec![]).unwrap();
+    app.insert_card(TRAP_SLOT, CardType::Trap);
+    let _ = app.tx.send(ControllerMessage::Reset);
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.breakpoint.is_some());
+    assert_eq!(app.breakpoint.take(), Some((0xFF07, String::from("HALT HERE"))));
+
+    let _ = app.tx.send(ControllerMessage::TogglePause);
+    common::tick_until(&mut app, TIMEOUT, |app| app.breakpoint.is_some());
+    assert_eq!(app.breakpoint, Some((0xFF0C, String::from("Code $07"))));
+}
+
+/// Starts timer 1 on the VIAs in slots 0 and 4 with interrupts disabled, then lets
+/// them interrupt. The handler at $FF40 records the slots acknowledged at $0200.
+fn irq_order(order: Option<Vec<u16>>) -> App {
+    let code = [
+        0x78,               // SEI
+        0xAD, 0x30, 0x02,   // LDA $0230, set once the machine is reset
+        0xF0, 0xFB,         // BEQ $FF01
+        0xA9, 0xC0,         // LDA #$C0, enable timer 1 interrupts
+        0x8D, 0xCE, 0xFF,   // STA $FFCE
+        0x8D, 0x8E, 0xFF,   // STA $FF8E
+        0xA9, 0x10,         // LDA #$10
+        0x8D, 0xC4, 0xFF,   // STA $FFC4
+        0x8D, 0x84, 0xFF,   // STA $FF84
+        0xA9, 0x00,         // LDA #$00
+        0x8D, 0xC5, 0xFF,   // STA $FFC5
+        0x8D, 0x85, 0xFF,   // STA $FF85
+        0xA0, 0x40,         // LDY #$40
+        0x88,               // DEY
+        0xD0, 0xFD,         // BNE $FF1F
+        0x58,               // CLI
+        0xAD, 0x90, 0xFF,   // LDA $FF90
+        0x8D, 0x20, 0x02,   // STA $0220
+        0x80, 0xF8,         // BRA $FF23
+    ];
+    let handler = [
+        0xAE, 0x10, 0x02,   // LDX $0210
+        0xAD, 0x90, 0xFF,   // LDA $FF90, acknowledge
+        0x9D, 0x00, 0x02,   // STA $0200,X
+        0xE8,               // INX
+        0x8E, 0x10, 0x02,   // STX $0210
+        0xAD, 0x91, 0xFF,   // LDA $FF91, slot * 2
+        0x0A,               // ASL
+        0x0A,               // ASL
+        0x0A,               // ASL
+        0xAA,               // TAX
+        0xBD, 0x84, 0xFF,   // LDA $FF84,X, clear the timer flag
+        0x40,               // RTI
+    ];
+    let mut rom = common::rom(&code, &handler);
+    rom[0xFE] = 0x40;
+    let mut app = App::with_data(rom, vec![]).unwrap();
+    app.insert_card(0, CardType::IO);
+    app.insert_card(IRQ_SLOT, CardType::Irq);
+    if let Some(order) = order {
+        app.set_irq_order(order);
+    }
+    let _ = app.tx.send(ControllerMessage::Reset);
+    // Requests are handled in order, the program only starts after the reset
+    app.request(Request::WriteByte { addr: 0x0230, value: 1 });
+    app.current_tab = Tab::Memory;
+    app.memory_scroll = 0x20;
+    common::tick_until(&mut app, TIMEOUT, |app| app.mem[0x210] == 2 && app.mem[0x220] == IRQ_NONE);
+    app
+}
+
+#[test]
+fn acknowledges_interrupts_in_daisy_chain_order() {
+    let app = irq_order(None);
+    assert_eq!(app.mem[0x200..0x202], [0, 4]);
+    assert_eq!(app.metrics.irqs.load(Ordering::Relaxed), 2);
+
+    let app = irq_order(Some(vec![4]));
+    assert_eq!(app.mem[0x200..0x202], [4, 0]);
+}
+
+#[test]
+fn runs_scripted_devices() {
+    let code = [
+        0xA9, 0x21,         // LDA #$21
+        0x8D, 0x80, 0xFF,   // STA $FF80
+        0xAD, 0x80, 0xFF,   // LDA $FF80
+        0x8D, 0x00, 0x02,   // STA $0200
+        0xAD, 0x81, 0xFF,   // LDA $FF81
+        0x8D, 0x01, 0x02,   // STA $0201
+        0x80, 0xF2,         // BRA $FF05
+    ];
+    let mut app = App::with_data(common::rom(&code, b""), vec![]).unwrap();
+    let handlers = Handlers {
+        read: Some("if reg == 0 { level * 2 } else { ticks }"),
+        write: Some("let level = value"),
+        tick: Some("let ticks = ticks + 1"),
+        ..Default::default()
+    };
+    let device = Device::new(0, "doubler", handlers, 100).unwrap();
+    app.insert_device(device);
+    let _ = app.tx.send(ControllerMessage::Reset);
+    app.current_tab = Tab::Memory;
+    app.memory_scroll = 0x20;
+    common::tick_until(&mut app, TIMEOUT, |app| app.mem[0x200] == 0x42 && app.mem[0x201] > 10);
+
+    let device = Device::new(0, "broken", Handlers { read: Some("1 / reg"), ..Default::default() }, 0).unwrap();
+    app.insert_device(device);
+    common::tick_until(&mut app, TIMEOUT, |app| app.error.is_some());
+    assert_eq!(app.error.as_deref(), Some("Read handler of device broken failed: Division by zero: 1 / 0"));
+}
+
+#[test]
+fn interrupts_on_the_raster_line_of_scripted_devices() {
+    let code = [
+        0xA9, 0x64,         // LDA #100
+        0x8D, 0x80, 0xFF,   // STA $FF80, raster line
+        0x58,               // CLI
+        0x80, 0xFE,         // BRA *
+    ];
+    let handler = [
+        0xAD, 0x80, 0xFF,   // LDA $FF80, acknowledge
+        0x8D, 0x00, 0x02,   // STA $0200
+        0xAD, 0x81, 0xFF,   // LDA $FF81, frames
+        0x8D, 0x01, 0x02,   // STA $0201
+        0x40,               // RTI
+    ];
+    let mut rom = common::rom(&code, &handler);
+    rom[0xFE] = 0x40;
+    let mut app = App::with_data(rom, vec![]).unwrap();
+    let handlers = Handlers {
+        read: Some("if reg == 0 { irq = 0; line } else { frames }"),
+        write: Some("raster = value"),
+        frame: Some("let frames = frames + 1"),
+        scanline: Some("irq = 1"),
+        ..Default::default()
+    };
+    app.insert_device(Device::new(0, "video", handlers, 0).unwrap());
+    let _ = app.tx.send(ControllerMessage::Reset);
+    app.current_tab = Tab::Memory;
+    app.memory_scroll = 0x20;
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.mem[0x200] == 100 && app.mem[0x201] >= 3);
+    assert!(app.metrics.irqs.load(Ordering::Relaxed) >= 2);
+    assert!(app.metrics.frames.load(Ordering::Relaxed) >= 3);
+}
+
+#[test]
+fn evaluates_expressions_over_memory_registers_and_symbols() {
+    let mut app = App::with_data(common::rom(&common::PRINT, b"\0"), vec![]).unwrap();
+    app.symbols = Symbols::parse("ptr = $0200\n");
+    app.request(Request::WriteByte { addr: 0x0200, value: 0x34 });
+    app.request(Request::WriteByte { addr: 0x0201, value: 0x12 });
+    app.start_eval();
+    app.input.value = String::from("(word(ptr) + Y) & 0xFF00");
+    app.submit_eval();
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.eval.is_some());
+    assert_eq!(app.eval.as_deref(), Some("(word(ptr) + Y) & 0xFF00 = 4608 $1200 %1001000000000"));
+    assert_eq!(app.input.mode, InputMode::Normal);
+
+    app.start_eval();
+    app.input.value = String::from("peek(");
+    app.submit_eval();
+    common::tick_until(&mut app, TIMEOUT, |app| app.eval.as_deref().is_some_and(|e| e.starts_with("peek(:")));
+}
+
+#[test]
+fn writes_values_in_several_radices() {
+    assert_eq!(app::radices(65), "65 $41 %1000001 'A'");
+    assert_eq!(app::radices(-1), "-1 $FFFF %1111111111111111");
+    assert_eq!(app::radices(0x10000), "65536 $10000 %10000000000000000");
+}
+
+#[test]
+fn reports_the_output_size_to_the_guest() {
+    let code = [
+        0xAD, 0xE1, 0xFF,   // LDA $FFE1, serial status
+        0x29, 0x20,         // AND #$20, resized
+        0xF0, 0xF9,         // BEQ $FF00
+        0xAD, 0xE4, 0xFF,   // LDA $FFE4, columns
+        0x8D, 0x00, 0x02,   // STA $0200
+        0xAD, 0xE5, 0xFF,   // LDA $FFE5, rows
+        0x8D, 0x01, 0x02,   // STA $0201
+        0x80, 0xFE,         // BRA *
+    ];
+    let mut app = App::with_data(common::rom(&code, b""), vec![]).unwrap();
+    app.current_tab = Tab::Memory;
+    app.memory_scroll = 0x20;
+    app.output_size = (78, 20);
+    app.report_size = true;
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.mem[0x200..0x202] == [78, 20]);
+    assert_eq!(app.reported_size, Some((78, 20)));
+
+    // Programs started again learn the size too
+    app.request(Request::WriteByte { addr: 0x0200, value: 0 });
+    common::tick_until(&mut app, TIMEOUT, |app| app.mem[0x200] == 0);
+    let _ = app.tx.send(ControllerMessage::Reset);
+    common::tick_until(&mut app, TIMEOUT, |app| app.mem[0x200..0x202] == [78, 20]);
+}
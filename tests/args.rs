@@ -0,0 +1,67 @@
+use plu::args::{self, Command};
+use plu::computer::InvalidOpcode;
+use plu::rom::RomOptions;
+
+#[test]
+fn parses_command_line() {
+    let parsed = args::parse(["--rom-size", "32K", "rom.bin", "cf.img", "--rom-base", "$8000"].map(String::from)).unwrap();
+
+    assert_eq!(parsed.rom_file.as_deref(), Some("rom.bin"));
+    assert_eq!(parsed.cf_file.as_deref(), Some("cf.img"));
+    assert_eq!(parsed.rom_options, RomOptions { base: Some(0x8000), size: Some(0x8000) });
+    assert_eq!(parsed.metrics_file, None);
+    assert!(!parsed.hibernate);
+
+    let parsed = args::parse(["--metrics", "run.jsonl", "rom.bin"].map(String::from)).unwrap();
+    assert_eq!(parsed.metrics_file.as_deref(), Some("run.jsonl"));
+
+    assert_eq!(parsed.crash_dir, None);
+    assert_eq!(parsed.on_invalid_opcode, InvalidOpcode::Nop);
+    let parsed = args::parse(["--on-invalid-opcode", "halt", "--crash-dir", "dumps", "rom.bin"].map(String::from)).unwrap();
+    assert_eq!(parsed.on_invalid_opcode, InvalidOpcode::Halt);
+    assert_eq!(parsed.crash_dir.as_deref(), Some("dumps"));
+    assert!(args::parse(["--on-invalid-opcode", "skip", "rom.bin"].map(String::from)).is_err());
+
+    let parsed = args::parse(["--hibernate", "--resume", "rom.bin"].map(String::from)).unwrap();
+    assert!(parsed.hibernate && parsed.resume);
+
+    assert!(args::parse(["--rom-base"].map(String::from)).is_err());
+    assert!(args::parse(["--fast", "rom.bin"].map(String::from)).is_err());
+    assert!(args::parse(["a", "b", "c"].map(String::from)).is_err());
+
+    let parsed = args::parse(["--irq-order", "6,4", "rom.bin"].map(String::from)).unwrap();
+    assert_eq!(parsed.irq_order, Some(vec![6, 4]));
+    assert!(args::parse(["--irq-order", "7", "rom.bin"].map(String::from)).is_err());
+    assert!(args::parse(["--irq-order", "4,4", "rom.bin"].map(String::from)).is_err());
+
+    let parsed = args::parse(["--symbols", "rom.sym", "--session", "debug.json"].map(String::from)).unwrap();
+    assert_eq!(parsed.symbols_file.as_deref(), Some("rom.sym"));
+    assert_eq!(parsed.session_file.as_deref(), Some("debug.json"));
+    assert_eq!(parsed.rom_file, None);
+    assert!(args::parse(["--symbols", "rom.sym"].map(String::from)).is_err());
+}
+
+#[test]
+fn parses_subcommands() {
+    assert_eq!(args::parse_command(["demo"].map(String::from)).unwrap(), Command::Demo(None));
+    assert_eq!(args::parse_command(["demo", "cf-read"].map(String::from)).unwrap(), Command::Demo(Some("cf-read".to_string())));
+    assert!(args::parse_command(["demo", "a", "b"].map(String::from)).is_err());
+
+    let Command::Run(parsed) = args::parse_command(["rom.bin"].map(String::from)).unwrap() else {
+        panic!("expected a ROM to run");
+    };
+    assert_eq!(parsed.rom_file.as_deref(), Some("rom.bin"));
+
+    let Command::Lockstep(parsed) = args::parse_command(["lockstep", "rom.bin", "cf.img"].map(String::from)).unwrap() else {
+        panic!("expected a lockstep run");
+    };
+    assert_eq!(parsed.cf_file.as_deref(), Some("cf.img"));
+
+    let Command::Bisect(parsed) = args::parse_command(["bisect", "--script", "test.rhai", "old.bin", "new.bin"].map(String::from)).unwrap() else {
+        panic!("expected a bisection");
+    };
+    assert_eq!(parsed.script_file, "test.rhai");
+    assert_eq!(parsed.builds, ["old.bin", "new.bin"]);
+    assert!(args::parse_command(["bisect", "old.bin", "new.bin"].map(String::from)).is_err());
+    assert!(args::parse_command(["bisect", "--script", "test.rhai", "new.bin"].map(String::from)).is_err());
+}
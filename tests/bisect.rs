@@ -0,0 +1,72 @@
+mod common;
+
+use std::fs;
+use std::time::Duration;
+
+use plu::app::App;
+use plu::computer::bisect::{Bisect, Outcome};
+use plu::computer::Request;
+use plu::rom::RomOptions;
+use plu::script::Script;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What a `test.rhai` given to `plu bisect --script` looks like
+const PRINTS_OK: &str = r#"
+// Give the build time to print, then look for its greeting
+let running = run(1000);
+running && output("OK")
+"#;
+
+fn bisect(script: &str) -> Bisect {
+    Bisect::new(Script::parse(script).unwrap(), RomOptions::default(), vec![])
+}
+
+/// Builds printing OK up to `bad`, and BAD from there on
+fn builds(name: &str, count: usize, bad: usize) -> Vec<String> {
+    let dir = common::temp_dir(name);
+    (0..count).map(|i| {
+        let path = dir.join(format!("rom-{}.bin", i));
+        let text: &[u8] = if i < bad { b"OK\0" } else { b"BAD\0" };
+        fs::write(&path, common::rom(&common::PRINT, text)).unwrap();
+        path.to_string_lossy().into_owned()
+    }).collect()
+}
+
+#[test]
+fn finds_the_first_failing_build() {
+    let builds = builds("first", 9, 6);
+    let mut tested = vec![];
+
+    let first = bisect(PRINTS_OK).run(&builds, |build, outcome| tested.push((build.to_string(), outcome.clone()))).unwrap();
+
+    assert_eq!(first, Some(6));
+    // The newest and oldest builds, then halving the rest
+    let order: Vec<&str> = tested.iter().map(|(b, _)| b.as_str()).collect();
+    assert_eq!(order, [&builds[8], &builds[0], &builds[4], &builds[6], &builds[5]]);
+    assert_eq!(tested[0].1, Outcome::Fail(None));
+    assert_eq!(tested[1].1, Outcome::Pass);
+}
+
+#[test]
+fn reports_when_no_build_fails_or_all_do() {
+    assert_eq!(bisect(PRINTS_OK).run(&builds("none", 3, 3), |_, _| {}).unwrap(), None);
+    assert_eq!(bisect(PRINTS_OK).run(&builds("all", 3, 0), |_, _| {}).unwrap(), Some(0));
+
+    let builds = builds("error", 2, 2);
+    assert_eq!(bisect("run(1000);\n1 / 0").test(&builds[0]).unwrap(), Outcome::Fail(Some(String::from("Division by zero: 1 / 0"))));
+    assert!(bisect("1").test("/nonexistent/rom.bin").is_err());
+}
+
+#[test]
+fn runs_against_snapshots() {
+    let path = common::temp_dir("snapshot").join("state.snapshot");
+    let mut app = App::with_data(common::rom(&common::PRINT, b"HI\0"), vec![]).unwrap();
+    common::tick_until(&mut app, TIMEOUT, |app| app.output_text() == "HI");
+    app.request(Request::WriteByte { addr: 0x0200, value: 0x42 });
+    app.hibernate(&path).unwrap();
+
+    let path = path.to_string_lossy();
+    assert_eq!(bisect("run(100);\npeek(0x200) == 0x42 && word(0xFFFC) == 0xFF00").test(&path).unwrap(), Outcome::Pass);
+    assert_eq!(bisect("run(100);\noutput(\"HI\")").test(&path).unwrap(), Outcome::Fail(None));
+}
@@ -0,0 +1,87 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use plu::app::App;
+use plu::computer::{Computer, ComputerMessage, ControllerMessage, Metrics};
+use plu::rom::{Rom, RomOptions};
+
+/// Empty directory for one test. `name` only has to be unique within a test file,
+/// since each file runs in its own process.
+#[allow(dead_code)]
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("plu-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Address at which [`rom`] places the program, also used as the reset vector.
+#[allow(dead_code)]
+pub const ROM_START: u16 = 0xFF00;
+
+/// Builds a 256 byte ROM image ending at $FFFF, with `code` at [`ROM_START`]
+/// and `data` at $FF40. All vectors point to the start of the code.
+#[allow(dead_code)]
+pub fn rom(code: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut rom = vec![0xEA; 0x100];
+    rom[..code.len()].copy_from_slice(code);
+    rom[0x40..0x40 + data.len()].copy_from_slice(data);
+    for vector in [0xFA, 0xFC, 0xFE] {
+        rom[vector] = (ROM_START & 0xFF) as u8;
+        rom[vector + 1] = (ROM_START >> 8) as u8;
+    }
+    rom
+}
+
+/// Computer reset on a [`rom`] of `code` and `data`, driven without its thread,
+/// with the sender of its controller messages, the receiver of its own and its metrics.
+#[allow(dead_code)]
+pub fn computer(code: &[u8], data: &[u8]) -> (Computer, mpsc::Sender<ControllerMessage>, mpsc::Receiver<ComputerMessage>, Arc<Metrics>) {
+    let (tx, rx) = mpsc::channel();
+    let (computer_tx, computer_rx) = mpsc::channel();
+    let metrics = Arc::new(Metrics::default());
+    let rom = Rom::load(rom(code, data), &RomOptions::default()).unwrap();
+    let mut computer = Computer::new(computer_tx, rx, metrics.clone(), rom, vec![]);
+    computer.reset();
+    (computer, tx, computer_rx, metrics)
+}
+
+/// Program printing the zero terminated string at $FF40 to the serial card, then looping forever.
+#[allow(dead_code)]
+pub const PRINT: [u8; 15] = [
+    0xA2, 0x00,         // LDX #$00
+    0xBD, 0x40, 0xFF,   // LDA $FF40,X
+    0xF0, 0x06,         // BEQ done
+    0x8D, 0xE0, 0xFF,   // STA $FFE0
+    0xE8,               // INX
+    0x80, 0xF5,         // BRA $FF02
+    0x80, 0xFE,         // done: BRA done
+];
+
+/// Program copying every byte received by the serial card back to it.
+#[allow(dead_code)]
+pub const ECHO: [u8; 15] = [
+    0xAD, 0xE1, 0xFF,   // LDA $FFE1, status
+    0x29, 0x08,         // AND #$08, received
+    0xF0, 0xF9,         // BEQ $FF00
+    0xAD, 0xE0, 0xFF,   // LDA $FFE0
+    0x8D, 0xE0, 0xFF,   // STA $FFE0
+    0x80, 0xF1,         // BRA $FF00
+];
+
+/// Ticks the application until `done` returns true, panicking after `timeout`.
+#[allow(dead_code)]
+pub fn tick_until(app: &mut App, timeout: Duration, done: impl Fn(&App) -> bool) {
+    let start = Instant::now();
+    loop {
+        app.tick();
+        if done(app) {
+            return;
+        }
+        assert!(start.elapsed() < timeout, "timed out, output was {:?}", app.output_text());
+        thread::sleep(Duration::from_millis(10));
+    }
+}
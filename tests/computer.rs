@@ -0,0 +1,412 @@
+mod common;
+
+use std::fs;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use plu::computer::device::{Device, Handlers};
+use plu::computer::{Card, CardType, Computer, ComputerMessage, ControllerMessage, InvalidOpcode, Metrics, Reply, Request, IRQ_NONE, IRQ_SLOT, OUTPUT_BACKLOG};
+use plu::rom::{Rom, RomOptions};
+
+/// Program writing the same byte to the serial card forever.
+const FLOOD: [u8; 7] = [
+    0xA9, 0x2A,         // LDA #'*'
+    0x8D, 0xE0, 0xFF,   // STA $FFE0
+    0x80, 0xFB,         // BRA $FF02
+];
+
+fn outputs(rx: &mpsc::Receiver<ComputerMessage>) -> Vec<Vec<u8>> {
+    rx.try_iter().filter_map(|message| match message {
+        ComputerMessage::Output(bytes) => Some(bytes),
+        _ => None,
+    }).collect()
+}
+
+#[test]
+fn batches_serial_output() {
+    let (mut computer, _tx, rx, metrics) = common::computer(&common::PRINT, b"HELLO WORLD\0");
+
+    computer.step();
+
+    assert_eq!(outputs(&rx), vec![b"HELLO WORLD".to_vec()]);
+    assert_eq!(metrics.output_backlog.load(Ordering::Relaxed), 11);
+}
+
+#[test]
+fn waits_for_the_ui_to_catch_up() {
+    let (mut computer, tx, rx, metrics) = common::computer(&FLOOD, b"");
+    tx.send(ControllerMessage::SetFastForward(true)).unwrap();
+
+    while metrics.output_backlog.load(Ordering::Relaxed) <= OUTPUT_BACKLOG {
+        computer.step();
+    }
+    let backlog = metrics.output_backlog.load(Ordering::Relaxed);
+    let instructions = metrics.instructions.load(Ordering::Relaxed);
+
+    computer.step();
+    assert_eq!(metrics.instructions.load(Ordering::Relaxed), instructions);
+
+    // The UI handles everything sent so far
+    let sent: usize = outputs(&rx).iter().map(Vec::len).sum();
+    assert_eq!(sent as u64, backlog);
+    metrics.output_backlog.fetch_sub(backlog, Ordering::Relaxed);
+
+    computer.step();
+    assert!(metrics.instructions.load(Ordering::Relaxed) > instructions);
+}
+
+#[test]
+fn throttles_to_clock_speed() {
+    let (mut computer, _tx, _rx, metrics) = common::computer(&FLOOD, b"");
+    let start = Instant::now();
+
+    // 50ms of emulated time at 1 MHz
+    while metrics.cycles.load(Ordering::Relaxed) < 50_000 {
+        computer.step();
+    }
+
+    assert!(start.elapsed() >= Duration::from_millis(40));
+}
+
+/// The single fault sent by the computer.
+fn fault(rx: &mpsc::Receiver<ComputerMessage>) -> String {
+    let mut faults = rx.try_iter().filter_map(|message| match message {
+        ComputerMessage::Fault(msg) => Some(msg),
+        _ => None,
+    });
+    let fault = faults.next().expect("the computer did not fault");
+    assert_eq!(faults.next(), None);
+    fault
+}
+
+#[test]
+fn writes_crash_dump_on_fault() {
+    let code = [
+        0xA9, 0x20,         // LDA #$20
+        0x8D, 0xD7, 0xFF,   // STA $FFD7, read command
+        0x8D, 0xD3, 0xFF,   // STA $FFD3, LBA 32 after the end of the disk
+        0xAD, 0xD0, 0xFF,   // LDA $FFD0
+        0x80, 0xFE,         // BRA *
+    ];
+    let dir = common::temp_dir("crash");
+    let (tx, rx) = mpsc::channel();
+    let (computer_tx, computer_rx) = mpsc::channel();
+    let rom = Rom::load(common::rom(&code, b""), &RomOptions::default()).unwrap();
+    let mut computer = Computer::new(computer_tx, rx, Arc::new(Metrics::default()), rom, vec![0; 512]);
+    computer.reset();
+    tx.send(ControllerMessage::SetCrashDir(Some(dir.clone()))).unwrap();
+
+    computer.step();
+
+    let fault = fault(&computer_rx);
+    assert!(fault.contains("Crash dump written to"), "{}", fault);
+    let files: Vec<_> = fs::read_dir(&dir).unwrap().map(|f| f.unwrap().path()).collect();
+    assert_eq!(files.len(), 1);
+    let dump = fs::read_to_string(&files[0]).unwrap();
+    assert!(dump.contains("Reason: CF card read beyond the end of the disk at LBA 32"), "{}", dump);
+    assert!(dump.contains("PC: FF08  A: 20"), "{}", dump);
+    assert!(dump.contains("FF05  8D D3 FF  STA $FFD3"), "{}", dump);
+    assert!(dump.contains("\nStack page\n0100  00 00"), "{}", dump);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn halts_on_invalid_opcodes_when_asked() {
+    let code = [
+        0xA9, 0x01,         // LDA #$01
+        0x03,               // undefined
+        0x8D, 0x00, 0x02,   // STA $0200
+        0x80, 0xFE,         // BRA *
+    ];
+    let (mut computer, _tx, _rx, _metrics) = common::computer(&code, b"");
+    computer.step();
+    assert_eq!(computer.memory()[0x200], 1);
+
+    let (mut computer, tx, rx, _metrics) = common::computer(&code, b"");
+    tx.send(ControllerMessage::SetInvalidOpcode(InvalidOpcode::Halt)).unwrap();
+    computer.step();
+    assert!(computer.is_paused());
+    assert_eq!(computer.processor().pc, 0xFF02);
+    assert_eq!(computer.memory()[0x200], 0);
+    assert_eq!(fault(&rx), "Invalid opcode $03 at $FF02");
+}
+
+#[test]
+fn faults_on_trap_loops_with_interrupts_disabled() {
+    let code = [
+        0x78,               // SEI
+        0x4C, 0x01, 0xFF,   // JMP *
+    ];
+    let (mut computer, _tx, rx, metrics) = common::computer(&code, b"");
+    computer.step();
+    assert!(computer.is_paused());
+    assert_eq!(metrics.instructions.load(Ordering::Relaxed), 2);
+    assert_eq!(fault(&rx), "Trap loop at $FF01 with interrupts disabled");
+
+    // Idle loops waiting for an interrupt keep running
+    let (mut computer, _tx, rx, _metrics) = common::computer(&common::PRINT, b"Hi\0");
+    computer.step();
+    computer.step();
+    assert!(!computer.is_paused());
+    assert!(rx.try_iter().all(|message| !matches!(message, ComputerMessage::Fault(_))));
+}
+
+/// Program waiting for the overflow flag, then writing to $0200.
+const WAIT_OVERFLOW: [u8; 10] = [
+    0xB8,               // CLV
+    0x50, 0xFE,         // BVC *
+    0xA9, 0x01,         // LDA #$01
+    0x8D, 0x00, 0x02,   // STA $0200
+    0x80, 0xFE,         // BRA *
+];
+
+#[test]
+fn sets_overflow_when_the_so_pin_is_pulsed() {
+    let (mut computer, tx, _rx, _metrics) = common::computer(&WAIT_OVERFLOW, b"");
+    computer.step();
+    assert_eq!(computer.memory()[0x200], 0);
+
+    tx.send(ControllerMessage::SetOverflow).unwrap();
+    computer.step();
+    assert_eq!(computer.memory()[0x200], 1);
+    assert_ne!(computer.processor().flags & 0x40, 0);
+}
+
+#[test]
+fn sets_overflow_on_the_edge_of_device_so_lines() {
+    let code = [
+        0xA9, 0x01,         // LDA #$01
+        0x8D, 0x80, 0xFF,   // STA $FF80, pull SO
+        0x50, 0xFE,         // BVC *
+        0xB8,               // CLV
+        0x8D, 0x80, 0xFF,   // STA $FF80, SO is still pulled
+        0x50, 0xFE,         // BVC *
+    ];
+    let (mut computer, tx, _rx, _metrics) = common::computer(&code, b"");
+    let device = Device::new(0, "so", Handlers { write: Some("so = value"), ..Default::default() }, 0).unwrap();
+    tx.send(ControllerMessage::InsertDevice(Box::new(device))).unwrap();
+
+    computer.step();
+    assert_eq!(computer.processor().pc, 0xFF0B);
+}
+
+#[test]
+fn sets_overflow_after_the_instruction_that_pulled_so() {
+    let code = [
+        0xB8,               // CLV
+        0x2C, 0x80, 0xFF,   // BIT $FF80, pull SO and read 0
+        0x50, 0xFE,         // BVC *
+        0xA9, 0x01,         // LDA #$01
+        0x8D, 0x00, 0x02,   // STA $0200
+        0x80, 0xFE,         // BRA *
+    ];
+    let (mut computer, tx, _rx, _metrics) = common::computer(&code, b"");
+    let device = Device::new(0, "so", Handlers { read: Some("so = 1; 0"), ..Default::default() }, 0).unwrap();
+    tx.send(ControllerMessage::InsertDevice(Box::new(device))).unwrap();
+
+    computer.step();
+    // BIT clears V from the byte read, the pulse still sets it
+    assert_eq!(computer.memory()[0x200], 1);
+}
+
+#[test]
+fn stops_the_clock_on_stp_until_a_reset() {
+    let code = [
+        0xA9, 0x01,         // LDA #$01
+        0xDB,               // STP
+    ];
+    let (mut computer, tx, _rx, metrics) = common::computer(&code, b"");
+    computer.step();
+    let clock = computer.processor().clock;
+    assert_eq!(metrics.instructions.load(Ordering::Relaxed), 2);
+
+    computer.step();
+    assert_eq!(computer.processor().clock, clock);
+    assert_eq!(metrics.instructions.load(Ordering::Relaxed), 2);
+
+    tx.send(ControllerMessage::Reset).unwrap();
+    computer.step();
+    assert!(metrics.instructions.load(Ordering::Relaxed) > 2);
+}
+
+#[test]
+fn runs_free_running_via_timers_at_a_steady_rate() {
+    let code = [
+        0xA9, 0x40,         // LDA #$40
+        0x8D, 0xCB, 0xFF,   // STA $FFCB, timer 1 free running
+        0xA9, 0x07,         // LDA #$07
+        0x8D, 0xC4, 0xFF,   // STA $FFC4
+        0xA9, 0x00,         // LDA #$00
+        0x8D, 0xC5, 0xFF,   // STA $FFC5, start timer 1 every 7 cycles, from cycle 25
+        0x80, 0xFE,         // BRA *, 3 cycles
+    ];
+    let (mut computer, tx, rx, _metrics) = common::computer(&code, b"");
+    computer.set_fast_forward(true);
+    for _ in 0..40 {
+        computer.run_one();
+    }
+    tx.send(ControllerMessage::Request(1, Request::Snapshot)).unwrap();
+    computer.step();
+
+    let snapshot = rx.try_iter().find_map(|message| match message {
+        ComputerMessage::Reply(1, Reply::Snapshot(snapshot)) => Some(snapshot),
+        _ => None,
+    }).unwrap();
+    let (at, _, _) = snapshot.timers.iter().find(|&&(_, slot, timer)| (slot, timer) == (4, 1)).unwrap();
+    // Instructions running past the end of a period do not delay the next one
+    assert_eq!((at - 25) % 7, 0);
+    assert!(*at > 100);
+}
+
+#[test]
+fn clears_the_any_bit_of_the_via_flags_with_the_last_flag() {
+    let code = [
+        0xA9, 0x01,         // LDA #$01
+        0x8D, 0xC4, 0xFF,   // STA $FFC4
+        0xA9, 0x00,         // LDA #$00
+        0x8D, 0xC5, 0xFF,   // STA $FFC5, timer 1 expires after one cycle
+        0xEA,               // NOP
+        0xAD, 0xCD, 0xFF,   // LDA $FFCD
+        0x8D, 0x00, 0x02,   // STA $0200
+        0xA9, 0x40,         // LDA #$40
+        0x8D, 0xCD, 0xFF,   // STA $FFCD, clear the timer 1 flag
+        0xAD, 0xCD, 0xFF,   // LDA $FFCD
+        0x8D, 0x01, 0x02,   // STA $0201
+        0x80, 0xFE,         // BRA *
+    ];
+    let (mut computer, _tx, _rx, _metrics) = common::computer(&code, b"");
+    for _ in 0..12 {
+        computer.run_one();
+    }
+
+    assert_eq!(computer.memory()[0x200..0x202], [0xC0, 0]);
+}
+
+#[test]
+fn stops_on_writes_to_a_shared_slot_with_strict_map() {
+    let code = [
+        0x8D, 0xC0, 0xFF,   // STA $FFC0
+        0x80, 0xFE,         // BRA *
+    ];
+    let (mut computer, tx, rx, _metrics) = common::computer(&code, b"");
+    tx.send(ControllerMessage::Request(1, Request::Snapshot)).unwrap();
+    computer.step();
+    let mut snapshot = rx.try_iter().find_map(|message| match message {
+        ComputerMessage::Reply(1, Reply::Snapshot(snapshot)) => Some(snapshot),
+        _ => None,
+    }).unwrap();
+
+    // Only a snapshot can bring two cards into the same slot
+    snapshot.cards.push(Card { slot: 4, card_type: CardType::Rtc, enabled: true });
+    tx.send(ControllerMessage::Restore(snapshot)).unwrap();
+    tx.send(ControllerMessage::SetStrictMap(true)).unwrap();
+    computer.step();
+
+    assert!(computer.is_paused());
+    assert_eq!(fault(&rx), "Write to $FFC0 hits both the IO and Rtc cards in slot 4");
+}
+
+#[test]
+fn raises_irq_when_the_via_enables_a_flag_already_set() {
+    let code = [
+        0x78,               // SEI
+        0xA9, 0x01,         // LDA #$01
+        0x8D, 0xC4, 0xFF,   // STA $FFC4
+        0x9C, 0xC5, 0xFF,   // STZ $FFC5, timer 1 expires after one cycle
+        0xEA,               // NOP
+        0xAD, 0x90, 0xFF,   // LDA $FF90, slot interrupting
+        0x8D, 0x00, 0x02,   // STA $0200
+        0xA9, 0xC0,         // LDA #$C0
+        0x8D, 0xCE, 0xFF,   // STA $FFCE, enable timer 1 interrupts
+        0xAD, 0x90, 0xFF,   // LDA $FF90
+        0x8D, 0x01, 0x02,   // STA $0201
+        0xDB,               // STP
+    ];
+    let (mut computer, tx, _rx, _metrics) = common::computer(&code, b"");
+    tx.send(ControllerMessage::InsertCard { slot: IRQ_SLOT, card_type: CardType::Irq }).unwrap();
+    computer.step();
+
+    assert_eq!(computer.memory()[0x200..0x202], [IRQ_NONE, 4]);
+}
+
+#[test]
+fn restores_the_variables_of_devices() {
+    let code = [
+        0xA9, 0x05,         // LDA #$05
+        0x8D, 0x80, 0xFF,   // STA $FF80
+        0x8D, 0x80, 0xFF,   // STA $FF80
+        0xAD, 0x00, 0x02,   // LDA $0200
+        0xF0, 0xFB,         // BEQ $FF08, until the snapshot is restored
+        0xAD, 0x80, 0xFF,   // LDA $FF80
+        0x8D, 0x01, 0x02,   // STA $0201
+        0xDB,               // STP
+    ];
+    let device = || {
+        let handlers = Handlers { read: Some("total"), write: Some("let total = total + value"), ..Default::default() };
+        Box::new(Device::new(0, "adder", handlers, 0).unwrap())
+    };
+    let (mut computer, tx, rx, _metrics) = common::computer(&code, b"");
+    tx.send(ControllerMessage::InsertDevice(device())).unwrap();
+    computer.step();
+    tx.send(ControllerMessage::Request(1, Request::Snapshot)).unwrap();
+    computer.step();
+    let mut snapshot = rx.try_iter().find_map(|message| match message {
+        ComputerMessage::Reply(1, Reply::Snapshot(snapshot)) => Some(snapshot),
+        _ => None,
+    }).unwrap();
+    snapshot.memory[0x200] = 1;
+
+    let (mut resumed, tx, _rx, _metrics) = common::computer(&code, b"");
+    tx.send(ControllerMessage::InsertDevice(device())).unwrap();
+    tx.send(ControllerMessage::Restore(snapshot)).unwrap();
+    resumed.step();
+
+    assert_eq!(resumed.memory()[0x201], 10);
+}
+
+/// The single breakpoint sent by the computer.
+fn breakpoint(rx: &mpsc::Receiver<ComputerMessage>) -> (u16, String) {
+    let mut breakpoints = rx.try_iter().filter_map(|message| match message {
+        ComputerMessage::Breakpoint { pc, message } => Some((pc, message)),
+        _ => None,
+    });
+    let breakpoint = breakpoints.next().expect("the computer did not stop");
+    assert_eq!(breakpoints.next(), None);
+    breakpoint
+}
+
+/// Program storing 1 to $0200, then incrementing it.
+const STORE_AND_INCREMENT: [u8; 10] = [
+    0xA9, 0x01,         // LDA #$01
+    0x8D, 0x00, 0x02,   // STA $0200
+    0xEE, 0x00, 0x02,   // INC $0200
+    0x80, 0xFE,         // BRA *
+];
+
+#[test]
+fn stops_on_breakpoints_and_watchpoints() {
+    let (mut computer, tx, rx, _metrics) = common::computer(&STORE_AND_INCREMENT, b"");
+    tx.send(ControllerMessage::SetBreakpoints(vec![0xFF05])).unwrap();
+    computer.step();
+    assert!(computer.is_paused());
+    assert_eq!(computer.processor().pc, 0xFF05);
+    assert_eq!(computer.memory()[0x200], 1);
+    assert_eq!(breakpoint(&rx), (0xFF05, String::from("Breakpoint")));
+
+    // Carrying on runs the instruction stopped on
+    tx.send(ControllerMessage::TogglePause).unwrap();
+    computer.step();
+    computer.step();
+    assert!(!computer.is_paused());
+    assert_eq!(computer.memory()[0x200], 2);
+
+    let (mut computer, tx, rx, _metrics) = common::computer(&STORE_AND_INCREMENT, b"");
+    tx.send(ControllerMessage::SetWatchpoints(vec![0x200])).unwrap();
+    computer.step();
+    assert!(computer.is_paused());
+    assert_eq!(computer.processor().pc, 0xFF05);
+    assert_eq!(breakpoint(&rx), (0xFF02, String::from("Wrote $01 to $0200")));
+}
@@ -0,0 +1,43 @@
+use plu::console::{Console, SCROLLBACK};
+
+fn rows(console: &Console) -> Vec<&[u8]> {
+    console.rows(0, console.len()).collect()
+}
+
+#[test]
+fn wraps_long_lines() {
+    let mut console = Console::new(4);
+    console.write(b"ABCDEFGHIJ\rKL");
+
+    assert_eq!(rows(&console), vec![&b"ABCD"[..], b"EFGH", b"IJ", b"KL"]);
+    assert_eq!(console.cursor(), 2);
+    assert_eq!(console.text(), "ABCDEFGHIJ\nKL");
+}
+
+#[test]
+fn backspace_goes_back_over_wrapped_rows() {
+    let mut console = Console::new(4);
+    console.write(b"ABCDE\x08\x08");
+
+    assert_eq!(rows(&console), vec![&b"ABC"[..]]);
+    assert_eq!(console.cursor(), 3);
+}
+
+#[test]
+fn rewraps_when_resized() {
+    let mut console = Console::new(4);
+    console.write(b"ABCDEFGHIJ\r\rKL");
+    console.set_columns(6);
+
+    assert_eq!(rows(&console), vec![&b"ABCDEF"[..], b"GHIJ", b"", b"KL"]);
+    assert_eq!(console.text(), "ABCDEFGHIJ\n\nKL");
+}
+
+#[test]
+fn drops_old_rows() {
+    let mut console = Console::default();
+    let dropped = console.write(&vec![b'\r'; SCROLLBACK + 10]);
+
+    assert_eq!(dropped, 11);
+    assert_eq!(console.len(), SCROLLBACK);
+}
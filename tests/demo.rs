@@ -0,0 +1,55 @@
+mod common;
+
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use plu::app::App;
+use plu::demo::{self, Demo, CF_MESSAGE, DEMOS};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn start(demo: &Demo) -> App {
+    App::with_data(demo.rom.to_vec(), (demo.disk)()).unwrap()
+}
+
+#[test]
+fn roms_point_all_vectors_at_the_start() {
+    for demo in &DEMOS {
+        assert_eq!(demo.rom.len(), 0x100, "{}", demo.name);
+        assert_eq!(demo.rom[0xFA..], [0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF], "{}", demo.name);
+    }
+    assert!(demo::find("hello-serial").is_some());
+    assert!(demo::find("missing").is_none());
+}
+
+#[test]
+fn hello_serial_prints_greeting() {
+    let mut app = start(demo::find("hello-serial").unwrap());
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.output_text().ends_with('\n'));
+
+    assert_eq!(app.output_text(), "Hello from the Planck 6502!\n");
+}
+
+#[test]
+fn via_blink_toggles_on_timer() {
+    let mut app = start(demo::find("via-blink").unwrap());
+    app.set_fast_forward(true);
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.output_text().len() >= 4);
+
+    assert!(app.output_text().starts_with("*.*."), "{:?}", app.output_text());
+    // 50000 cycles between two changes
+    let cycles = app.metrics.cycles.load(Ordering::Relaxed);
+    assert!(cycles >= 4 * 50_000, "{} cycles", cycles);
+}
+
+#[test]
+fn cf_read_prints_sector() {
+    let mut app = start(demo::find("cf-read").unwrap());
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.output_text().ends_with('\n'));
+
+    let expected = String::from_utf8_lossy(&CF_MESSAGE[..CF_MESSAGE.len() - 1]);
+    assert_eq!(app.output_text(), format!("{}\n", expected));
+}
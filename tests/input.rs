@@ -0,0 +1,94 @@
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use plu::app::App;
+use plu::computer::SERIAL_RX_CAPACITY;
+use plu::error::Error;
+use plu::input::InputSource;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Path of a file that does not exist yet, in an empty directory for the test
+fn temp_path(name: &str) -> PathBuf {
+    common::temp_dir(name).join("input")
+}
+
+#[test]
+fn parses_sources() {
+    assert_eq!(InputSource::parse("-"), InputSource::Stdin);
+    assert_eq!(InputSource::parse("vectors.txt"), InputSource::Path(PathBuf::from("vectors.txt")));
+}
+
+#[test]
+fn reads_a_whole_file_without_dropping_bytes() {
+    let path = temp_path("file");
+    let text = "0123456789".repeat(50);
+    fs::write(&path, &text).unwrap();
+    let mut app = App::with_data(common::rom(&common::ECHO, b""), vec![]).unwrap();
+
+    app.add_serial_input(InputSource::Path(path.clone())).unwrap();
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.output_text().len() == text.len());
+    assert_eq!(app.output_text(), text);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn waits_for_the_guest_on_bursts_larger_than_the_queue() {
+    let path = temp_path("burst");
+    let text: String = (0..SERIAL_RX_CAPACITY * 3).map(|i| (b'A' + (i % 26) as u8) as char).collect();
+    fs::write(&path, &text).unwrap();
+    let mut app = App::with_data(common::rom(&common::ECHO, b""), vec![]).unwrap();
+    app.set_fast_forward(true);
+
+    app.add_serial_input(InputSource::Path(path.clone())).unwrap();
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.output_text().len() == text.len());
+    assert_eq!(app.output_text(), text);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn echoes_typing_bursts_without_dropping_keys() {
+    let mut app = App::with_data(common::rom(&common::ECHO, b""), vec![]).unwrap();
+    let text = "The quick brown fox jumps over the lazy dog. ".repeat(40);
+
+    for c in text.bytes() {
+        app.send_char(c);
+    }
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.output_text().len() == text.len());
+    assert_eq!(app.output_text(), text);
+    assert!(app.debug.is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn keeps_reading_a_named_pipe_after_each_writer() {
+    use std::fs::OpenOptions;
+    use std::io::Write;
+    use std::process::Command;
+
+    let path = temp_path("fifo");
+    assert!(Command::new("mkfifo").arg(&path).status().unwrap().success());
+    let mut app = App::with_data(common::rom(&common::ECHO, b""), vec![]).unwrap();
+    app.add_serial_input(InputSource::Path(path.clone())).unwrap();
+
+    for text in ["AB", "CD"] {
+        let mut fifo = OpenOptions::new().write(true).open(&path).unwrap();
+        fifo.write_all(text.as_bytes()).unwrap();
+    }
+
+    common::tick_until(&mut app, TIMEOUT, |app| app.output_text() == "ABCD");
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn rejects_missing_files() {
+    let mut app = App::with_data(common::rom(&common::ECHO, b""), vec![]).unwrap();
+
+    assert!(matches!(app.add_serial_input(InputSource::Path(temp_path("missing"))), Err(Error::File { .. })));
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use plu::computer::lockstep::{Difference, Lockstep};
+use plu::demo;
+use plu::rom::{Rom, RomOptions};
+
+#[test]
+fn cores_agree_on_the_demos() {
+    for demo in &demo::DEMOS {
+        let rom = Rom::load(demo.rom.to_vec(), &RomOptions::default()).unwrap();
+
+        assert_eq!(Lockstep::new(rom, (demo.disk)()).run(100_000), Ok(100_000), "{}", demo.name);
+    }
+}
+
+#[test]
+fn stops_at_the_first_register_difference() {
+    let (legacy, ..) = common::computer(&[0xA9, 0x01, 0x80, 0xFE], b"");   // LDA #$01
+    let (table, ..) = common::computer(&[0xA9, 0x02, 0x80, 0xFE], b"");    // LDA #$02
+
+    let divergence = Lockstep::with_computers(legacy, table).run(100).unwrap_err();
+
+    assert_eq!(divergence.instructions, 1);
+    assert_eq!(divergence.pc, common::ROM_START);
+    assert_eq!(divergence.difference, Difference::Registers);
+    assert_eq!((divergence.legacy.acc, divergence.table.acc), (1, 2));
+    assert!(divergence.to_string().starts_with("Cores diverged after 1 instructions, the last one at $FF00\n"));
+}
+
+#[test]
+fn compares_memory() {
+    let (legacy, ..) = common::computer(&[0x80, 0xFE], b"A");
+    let (table, ..) = common::computer(&[0x80, 0xFE], b"B");
+    let divergence = Lockstep::with_computers(legacy, table)
+        .run(10)
+        .unwrap_err();
+
+    assert_eq!(divergence.instructions, 10);
+    assert_eq!(divergence.difference, Difference::Memory { addr: 0xFF40, legacy: b'A', table: b'B' });
+}
+
+#[test]
+fn runs_each_core_on_the_same_rom() {
+    let code = [
+        0xA9, 0x01,         // LDA #$01
+        0xC9, 0xFF,         // CMP #$FF
+        0x80, 0xFE,         // BRA *
+    ];
+    let divergence = Lockstep::new(Rom::load(common::rom(&code, b""), &RomOptions::default()).unwrap(), vec![])
+        .run(10)
+        .unwrap_err();
+
+    // 1 - $FF is 2, positive, which the legacy core misses by comparing unsigned values
+    assert_eq!(divergence.instructions, 2);
+    assert_eq!(divergence.difference, Difference::Registers);
+    assert_eq!((divergence.legacy.flags & 0x80, divergence.table.flags & 0x80), (0x80, 0));
+}
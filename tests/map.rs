@@ -0,0 +1,76 @@
+use plu::computer::device::{Device, Handlers};
+use plu::computer::map::{self, Conflict, MemoryMap, Region};
+use plu::computer::{Card, CardType};
+use plu::demo;
+use plu::rom::{Rom, RomOptions};
+
+fn card(slot: u16, card_type: CardType) -> Card {
+    Card { slot, card_type, enabled: true }
+}
+
+fn rom(data: Vec<u8>) -> Rom {
+    Rom::load(data, &RomOptions::default()).unwrap()
+}
+
+#[test]
+fn reports_cards_sharing_a_slot() {
+    let mut cards = vec![card(3, CardType::Rtc), card(3, CardType::IO)];
+
+    assert_eq!(map::check(&cards, &rom(vec![0xEA; 0x100])), vec![Conflict::Slot { slot: 3, cards: [CardType::Rtc, CardType::IO] }]);
+
+    cards[1].enabled = false;
+    assert_eq!(map::check(&cards, &rom(vec![0xEA; 0x100])), vec![]);
+}
+
+#[test]
+fn reports_rom_data_hidden_by_cards() {
+    let mut data = vec![0xEA; 0x100];
+    data[0xB4] = b'A';
+    let cards = [card(3, CardType::Rtc), card(6, CardType::Serial)];
+
+    let conflicts = map::check(&cards, &rom(data));
+
+    // Slot 6 only hides padding
+    assert_eq!(conflicts, vec![Conflict::RomShadowed { slot: 3, card_type: CardType::Rtc }]);
+    assert_eq!(conflicts[0].to_string(), "Rtc card in slot 3 hides ROM data at $FFB0-$FFBF");
+}
+
+#[test]
+fn reports_rom_over_the_stack() {
+    let conflicts = map::check(&[], &Rom::load(vec![0; 0x300], &RomOptions { base: Some(0x0100), size: None }).unwrap());
+
+    assert_eq!(conflicts, vec![Conflict::RomOverRam(0x0100..=0x01FF)]);
+}
+
+#[test]
+fn demos_have_no_conflicts() {
+    let cards = [card(4, CardType::IO), card(5, CardType::CF), card(6, CardType::Serial)];
+    for demo in &demo::DEMOS {
+        assert_eq!(map::check(&cards, &rom(demo.rom.to_vec())), vec![], "{}", demo.name);
+    }
+}
+
+#[test]
+fn tells_regions_apart() {
+    let mut cards = vec![card(4, CardType::IO), card(6, CardType::Serial)];
+    cards[1].enabled = false;
+    let map = MemoryMap::new(&cards, &[], &rom(vec![0xEA; 0x1000]));
+
+    assert_eq!(map.region(0x0200), Region::Ram);
+    assert_eq!(map.region(0xF000), Region::Rom);
+    assert_eq!(map.region(0xFFC5), Region::Card(&cards[0]));
+    assert_eq!(map.region(0xFFE0), Region::Unmapped);
+    // Empty slots leave the ROM visible
+    assert_eq!(map.region(0xFF80), Region::Rom);
+    assert_eq!(map.region(0xFFF0), Region::Rom);
+}
+
+#[test]
+fn names_scripted_devices() {
+    let cards = [card(0, CardType::Device), card(4, CardType::IO)];
+    let devices = [Device::new(0, "video", Handlers::default(), 0).unwrap()];
+    let map = MemoryMap::new(&cards, &devices, &rom(vec![0xEA; 0x100]));
+
+    assert_eq!(map.card_name(&cards[0]), "video");
+    assert_eq!(map.card_name(&cards[1]), "IO");
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use std::fs;
+use std::time::Duration;
+
+use plu::app::App;
+use plu::metrics::Sample;
+
+#[test]
+fn formats_sample_as_json() {
+    let sample = Sample {
+        mhz: 1.0,
+        host_cpu: Some(12.34),
+        instructions: 250_000,
+        output_backlog: 3,
+        requests_in_flight: 1,
+    };
+
+    assert_eq!(
+        sample.to_json("2024-01-01T00:00:00.000Z"),
+        r#"{"time":"2024-01-01T00:00:00.000Z","mhz":1.000,"host_cpu":12.3,"instructions":250000,"output_backlog":3,"requests_in_flight":1}"#
+    );
+    let unknown = Sample { host_cpu: None, ..sample };
+    assert!(unknown.to_json("t").contains(r#""host_cpu":null"#));
+}
+
+#[test]
+fn writes_a_line_every_second() {
+    let path = common::temp_dir("metrics").join("run.jsonl");
+    let path = path.to_str().unwrap();
+    let mut app = App::with_data(common::rom(&common::PRINT, b"\0"), vec![]).unwrap();
+    app.record_metrics(path).unwrap();
+
+    common::tick_until(&mut app, Duration::from_secs(5), |_| {
+        fs::read_to_string(path).is_ok_and(|log| log.lines().count() >= 2)
+    });
+
+    let log = fs::read_to_string(path).unwrap();
+    fs::remove_file(path).unwrap();
+    for line in log.lines() {
+        assert!(line.starts_with(r#"{"time":""#), "{}", line);
+        assert!(line.contains(r#""mhz":"#) && line.ends_with('}'), "{}", line);
+    }
+}
+
+#[test]
+fn reports_files_that_cannot_be_created() {
+    let mut app = App::with_data(common::rom(&common::PRINT, b"\0"), vec![]).unwrap();
+
+    assert!(app.record_metrics("/nonexistent/dir/metrics.jsonl").is_err());
+}
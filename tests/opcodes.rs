@@ -0,0 +1,127 @@
+mod common;
+
+use plu::computer::disassembler;
+use plu::computer::opcodes::OPCODES;
+use plu::computer::{AdressingMode, Core};
+
+const FLAG_C: u8 = 1;
+const FLAG_Z: u8 = 2;
+
+#[test]
+fn lengths_match_addressing_modes() {
+    for (opcode, op) in OPCODES.iter().enumerate() {
+        let expected = match op.mode {
+            AdressingMode::Implied | AdressingMode::Accumulator | AdressingMode::None => 1,
+            AdressingMode::Absolute | AdressingMode::AbsoluteX | AdressingMode::AbsoluteY
+            | AdressingMode::Indirect | AdressingMode::ZeroPageRelative => 3,
+            // JMP (abs,X)
+            AdressingMode::IndirectX if opcode == 0x7C => 3,
+            _ => 2,
+        };
+        assert_eq!(op.bytes, expected, "length of opcode {:02X}", opcode);
+    }
+}
+
+#[test]
+fn undefined_opcodes_are_single_byte() {
+    for (opcode, op) in OPCODES.iter().enumerate().filter(|(_, op)| op.mnemonic.is_empty()) {
+        assert_eq!((op.mode, op.bytes), (AdressingMode::Implied, 1), "opcode {:02X}", opcode);
+    }
+}
+
+#[test]
+fn disassembler_uses_the_table() {
+    let mem = [0x5C, 0x34, 0x12, 0x0F, 0x10, 0xFE, 0x7C, 0x00, 0x80];
+    let text: Vec<String> = disassembler::disassemble_range(&mem, 0, 3).into_iter().map(|i| i.text).collect();
+
+    assert_eq!(text, ["NOP $1234,X", "BBR0 $10,$0004", "JMP ($8000,X)"]);
+}
+
+#[test]
+fn cycles_are_those_of_the_65c02() {
+    let cycles = |opcode: usize| OPCODES[opcode].cycles;
+
+    assert_eq!([0x12, 0x32, 0x52, 0x72, 0xF2].map(cycles), [5; 5]);
+    assert_eq!([0x4C, 0x6C, 0x7C].map(cycles), [3, 6, 6]);
+    assert_eq!([0x04, 0x0C, 0x07, 0x87, 0xCB, 0xDB].map(cycles), [5, 6, 5, 5, 3, 3]);
+    assert_eq!((OPCODES[0x1A].mnemonic, OPCODES[0x3A].mnemonic), ("INC", "DEC"));
+    for (opcode, op) in OPCODES.iter().enumerate() {
+        assert!(op.cycles > 0, "cycles of opcode {:02X}", opcode);
+    }
+}
+
+#[test]
+fn runs_bit_instructions() {
+    let code = [
+        0xA9, 0x05,         // LDA #$05
+        0x85, 0x10,         // STA $10
+        0xA9, 0x0A,         // LDA #$0A
+        0x04, 0x10,         // TSB $10, no bit in common
+        0x07, 0x10,         // RMB0 $10
+        0xF7, 0x10,         // SMB7 $10
+        0xA9, 0x06,         // LDA #$06
+        0x14, 0x10,         // TRB $10
+        0x1A,               // INC A
+        0x3A,               // DEC A
+        0x3A,               // DEC A
+        0xDB,               // STP
+    ];
+    for core in [Core::Legacy, Core::Table] {
+        let (mut computer, ..) = common::computer(&code, b"");
+        computer.set_fast_forward(true);
+        computer.set_core(core);
+
+        for _ in 0..4 {
+            computer.run_one();
+        }
+        assert_eq!(computer.memory()[0x10], 0x0F);
+        assert_ne!(computer.processor().flags & FLAG_Z, 0);
+        for _ in 0..4 {
+            computer.run_one();
+        }
+        assert_eq!(computer.memory()[0x10], 0x88);
+        assert_eq!(computer.processor().flags & FLAG_Z, 0);
+        for _ in 0..4 {
+            computer.run_one();
+        }
+        assert_eq!(computer.processor().acc, 0x05);
+        // STP holds the processor until a reset
+        assert_eq!(computer.processor().pc, common::ROM_START + code.len() as u16 - 1);
+        assert!(computer.is_paused());
+    }
+}
+
+#[test]
+fn table_core_runs_decimal_arithmetic_and_memory_rotations() {
+    let code = [
+        0xF8,               // SED
+        0x18,               // CLC
+        0xA9, 0x19,         // LDA #$19
+        0x69, 0x28,         // ADC #$28
+        0x85, 0x10,         // STA $10
+        0x38,               // SEC
+        0xA9, 0x40,         // LDA #$40
+        0xE9, 0x13,         // SBC #$13
+        0x85, 0x11,         // STA $11
+        0xA9, 0x99,         // LDA #$99
+        0x69, 0x01,         // ADC #$01, with the carry of SBC
+        0x85, 0x12,         // STA $12
+        0xD8,               // CLD
+        0xA9, 0x81,         // LDA #$81
+        0x8D, 0x00, 0x02,   // STA $0200
+        0x18,               // CLC
+        0x2E, 0x00, 0x02,   // ROL $0200
+        0xDB,               // STP
+    ];
+    let (mut computer, ..) = common::computer(&code, b"");
+    computer.set_fast_forward(true);
+    computer.set_core(Core::Table);
+    while !computer.is_paused() {
+        computer.run_one();
+    }
+
+    assert_eq!(computer.memory()[0x10..0x13], [0x47, 0x27, 0x01]);
+    assert_eq!(computer.memory()[0x200], 0x02);
+    assert_eq!(computer.processor().acc, 0x81);
+    assert_eq!(computer.processor().flags & FLAG_C, FLAG_C);
+}
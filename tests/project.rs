@@ -0,0 +1,98 @@
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use plu::app::App;
+use plu::demo;
+use plu::error::Error;
+use plu::input::InputSource;
+use plu::project::Project;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Empty directory for one test, with `project.toml` using `cp` as the assembler
+fn project_dir(name: &str, build: &str) -> PathBuf {
+    let dir = common::temp_dir(name);
+    fs::write(dir.join("project.toml"), format!("build = {}\nrom = \"rom.bin\"\nsymbols = \"rom.sym\"\n", build)).unwrap();
+    fs::write(dir.join("rom.sym"), "al 00FF00 .reset\n").unwrap();
+    dir
+}
+
+fn open(dir: &Path) -> Project {
+    Project::open(dir.join("project.toml").to_str().unwrap()).unwrap()
+}
+
+#[test]
+fn parses_project_files() {
+    let project = Project::parse("build = [[\"vasm\", \"main.s\"]]\nrom = \"a.out\"\nrom_base = \"$8000\"\n");
+    assert!(project.is_ok());
+
+    assert!(Project::parse("rom = \"a.out\"\n").is_err());
+    assert!(Project::parse("build = [[]]\nrom = \"a.out\"\n").is_err());
+    assert!(Project::parse("build = []\nrom = \"a.out\"\nrom_size = \"lots\"\n").is_err());
+    assert!(Project::parse("build = []\nrom = \"a.out\"\nassembler = \"ca65\"\n").is_err());
+
+    let project = Project::parse("build = []\nrom = \"a.out\"\nserial_input = \"-\"\n").unwrap();
+    assert_eq!(project.serial_input(), Some(InputSource::Stdin));
+
+    let devices = "build = []\nrom = \"a.out\"\n[[device]]\nname = \"dac\"\nslot = 0\n";
+    let project = Project::parse(&format!("{}read = \"level\"\n", devices)).unwrap();
+    assert_eq!(project.devices().unwrap()[0].name, "dac");
+    assert!(Project::parse(&format!("{}read = \"level +\"\n", devices)).unwrap_err().contains("read handler of device dac"));
+    assert!(Project::parse(&format!("{}tick = \"n = n + 1\"\n", devices)).is_err());
+    assert!(Project::parse(&format!("{}frame = \"raster = 100\"\nscanline = \"irq = 1\"\n", devices)).is_ok());
+    assert!(Project::parse(&format!("{}scanline = \"irq =\"\n", devices)).unwrap_err().contains("scanline handler of device dac"));
+    assert!(Project::parse(&devices.replace("slot = 0", "slot = 7")).is_err());
+}
+
+#[test]
+fn builds_and_runs() {
+    let dir = project_dir("run", r#"[["cp", "hello.bin", "rom.bin"]]"#);
+    fs::write(dir.join("hello.bin"), demo::find("hello-serial").unwrap().rom).unwrap();
+
+    let mut app = App::with_project(open(&dir)).unwrap();
+
+    assert_eq!(app.build_error, None);
+    assert_eq!(app.symbols.get(0xFF00), Some("reset"));
+    assert_eq!(app.session.project.as_deref(), dir.join("project.toml").to_str());
+    common::tick_until(&mut app, TIMEOUT, |app| app.output_text().contains("Hello"));
+
+    // A new build replaces the running program
+    fs::write(dir.join("hello.bin"), demo::find("via-blink").unwrap().rom).unwrap();
+    app.rebuild();
+    // Builds run in the background and are picked up by ticks
+    assert!(app.build_rx.is_some());
+    common::tick_until(&mut app, TIMEOUT, |app| app.output_text().contains("*."));
+    assert_eq!(app.build_error, None);
+    assert!(!app.output_text().contains("Hello"));
+
+    fs::write(dir.join("project.toml"), "build = [[\"false\"]]\nrom = \"rom.bin\"\n").unwrap();
+    let mut app = App::with_project(open(&dir)).unwrap();
+    app.build_error = None;
+    app.rebuild();
+    common::tick_until(&mut app, TIMEOUT, |app| app.build_rx.is_none());
+    assert!(app.build_error.unwrap().starts_with("false failed"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn reports_assembler_errors() {
+    let dir = project_dir("fail", r#"[["sh", "-c", "echo 'main.s(3): Error: Unexpected trailing garbage' >&2; exit 1"]]"#);
+    let project = open(&dir);
+
+    let Err(Error::Build { command, output }) = App::with_project(project.clone()) else {
+        panic!("expected the build to fail");
+    };
+    assert!(command.starts_with("sh -c"));
+    assert_eq!(output, "main.s(3): Error: Unexpected trailing garbage");
+
+    // The ROM left by an earlier build still runs
+    fs::write(dir.join("rom.bin"), demo::find("hello-serial").unwrap().rom).unwrap();
+    let app = App::with_project(project).unwrap();
+    assert!(app.build_error.unwrap().contains("Unexpected trailing garbage"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
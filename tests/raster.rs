@@ -0,0 +1,26 @@
+use plu::computer::raster::{Position, Raster, SCANLINES};
+
+#[test]
+fn splits_the_clock_in_frames_and_lines() {
+    let raster = Raster::new(1_000_000);
+    assert_eq!(raster.cycles_per_frame, 16_666);
+    assert_eq!(raster.cycles_per_line(), 63);
+
+    assert_eq!(raster.position(0), Position { frame: 0, line: 0, cycle: 0 });
+    assert_eq!(raster.position(63 * 10 + 5), Position { frame: 0, line: 10, cycle: 5 });
+    assert_eq!(raster.position(16_666 * 2 + 64), Position { frame: 2, line: 1, cycle: 1 });
+    // The last line takes the cycles left over
+    assert_eq!(raster.position(16_665).line, SCANLINES - 1);
+}
+
+#[test]
+fn finds_the_next_frame_and_line() {
+    let raster = Raster::new(1_000_000);
+    assert_eq!(raster.next_frame(0), 16_666);
+    assert_eq!(raster.next_frame(16_666), 33_332);
+
+    assert_eq!(raster.next_line(0, 100), 6_300);
+    // A line already reached comes again on the next frame
+    assert_eq!(raster.next_line(6_300, 100), 16_666 + 6_300);
+    assert_eq!(raster.next_line(7_000, 0), 16_666);
+}
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+use std::thread;
+
+use plu::ring::RingBuffer;
+
+#[test]
+fn queues_bytes_in_order_until_full() {
+    let ring = RingBuffer::new(5);
+    assert_eq!(ring.capacity(), 8);
+    assert_eq!(ring.pop(), None);
+
+    // Several laps around the cells
+    for lap in 0..3u8 {
+        assert_eq!(ring.push_slice(&[lap; 10]), 8);
+        assert!(!ring.push(0xFF));
+        assert_eq!(ring.len(), 8);
+        let popped: Vec<u8> = std::iter::from_fn(|| ring.pop()).collect();
+        assert_eq!(popped, [lap; 8]);
+        assert!(ring.is_empty());
+    }
+}
+
+#[test]
+fn keeps_the_order_of_each_producer() {
+    const PRODUCERS: u8 = 4;
+    const BYTES: usize = 20_000;
+    let ring = Arc::new(RingBuffer::new(64));
+
+    let producers: Vec<_> = (0..PRODUCERS).map(|p| {
+        let ring = ring.clone();
+        thread::spawn(move || {
+            // The producer in the top bits, a sequence number in the others
+            for i in 0..BYTES {
+                while !ring.push(p << 6 | (i % 64) as u8) {
+                    thread::yield_now();
+                }
+            }
+        })
+    }).collect();
+
+    let mut next = [0u8; PRODUCERS as usize];
+    let mut received = 0;
+    while received < PRODUCERS as usize * BYTES {
+        let Some(b) = ring.pop() else {
+            thread::yield_now();
+            continue;
+        };
+        let p = (b >> 6) as usize;
+        assert_eq!(b & 0x3F, next[p], "producer {}", p);
+        next[p] = (next[p] + 1) % 64;
+        received += 1;
+    }
+    for producer in producers {
+        producer.join().unwrap();
+    }
+    assert!(ring.is_empty());
+}
@@ -0,0 +1,58 @@
+use plu::error::Error;
+use plu::rom::{self, Rom, RomOptions};
+
+fn image(size: usize) -> Vec<u8> {
+    (0..size).map(|i| (i >> 8) as u8).collect()
+}
+
+#[test]
+fn loads_rom_at_top_of_memory_by_default() {
+    let rom = Rom::load(image(0x8000), &RomOptions::default()).unwrap();
+
+    assert_eq!(rom.base, 0x8000);
+    assert_eq!(rom.data.len(), 0x8000);
+}
+
+#[test]
+fn loads_rom_at_fixed_base() {
+    let options = RomOptions { base: Some(0xC000), size: None };
+    let rom = Rom::load(image(0x2000), &options).unwrap();
+
+    assert_eq!(rom.base, 0xC000);
+}
+
+#[test]
+fn loads_top_of_larger_image() {
+    let options = RomOptions { base: None, size: Some(0x4000) };
+    let rom = Rom::load(image(0x20000), &options).unwrap();
+
+    assert_eq!(rom.base, 0xC000);
+    assert_eq!(rom.data.len(), 0x4000);
+    // Last 16K of the 128K image
+    assert_eq!(rom.data[0], 0xC0);
+    assert_eq!(rom.data[0x3FFF], 0xFF);
+}
+
+#[test]
+fn rejects_images_that_do_not_fit() {
+    assert!(matches!(Rom::load(image(0x20000), &RomOptions::default()), Err(Error::RomTooLarge(0x20000))));
+    assert!(matches!(Rom::load(vec![], &RomOptions::default()), Err(Error::EmptyRom)));
+
+    let options = RomOptions { base: Some(0xC000), size: None };
+    assert!(matches!(Rom::load(image(0x8000), &options), Err(Error::RomDoesNotFit { base: 0xC000, size: 0x8000 })));
+
+    let options = RomOptions { base: None, size: Some(0x8000) };
+    assert!(matches!(Rom::load(image(0x4000), &options), Err(Error::RomSize { size: 0x8000, file: 0x4000 })));
+}
+
+#[test]
+fn parses_addresses_and_sizes() {
+    assert_eq!(rom::parse_address("0x8000"), Some(0x8000));
+    assert_eq!(rom::parse_address("$C000"), Some(0xC000));
+    assert_eq!(rom::parse_address("4096"), Some(4096));
+    assert_eq!(rom::parse_address("0x10000"), None);
+    assert_eq!(rom::parse_size("32K"), Some(0x8000));
+    assert_eq!(rom::parse_size("16k"), Some(0x4000));
+    assert_eq!(rom::parse_size("0x100"), Some(0x100));
+    assert_eq!(rom::parse_size("big"), None);
+}
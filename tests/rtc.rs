@@ -0,0 +1,78 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+use plu::computer::rtc::{self, Rtc};
+
+fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32, s: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(y, mo, d).unwrap().and_hms_opt(h, mi, s).unwrap()
+}
+
+fn read_all(rtc: &mut Rtc, seconds: u64) -> [u8; 8] {
+    [rtc::SECONDS, rtc::MINUTES, rtc::HOURS, rtc::WEEKDAY, rtc::DATE, rtc::MONTH, rtc::YEAR, rtc::CENTURY]
+        .map(|reg| rtc.read(reg, seconds))
+}
+
+#[test]
+fn reads_time_in_bcd() {
+    let mut rtc = Rtc::new(at(2024, 2, 28, 23, 59, 58));
+
+    // Wednesday
+    assert_eq!(read_all(&mut rtc, 0), [0x58, 0x59, 0x23, 0x04, 0x28, 0x02, 0x24, 0x20]);
+    // Leap day, two emulated seconds later
+    assert_eq!(read_all(&mut rtc, 2), [0x00, 0x00, 0x00, 0x05, 0x29, 0x02, 0x24, 0x20]);
+}
+
+#[test]
+fn advances_across_year_end() {
+    let mut rtc = Rtc::new(at(1999, 12, 31, 23, 30, 0));
+
+    rtc.advance(Duration::hours(1));
+
+    assert_eq!(read_all(&mut rtc, 0), [0x00, 0x30, 0x00, 0x07, 0x01, 0x01, 0x00, 0x20]);
+    assert_eq!(rtc.now(0), at(2000, 1, 1, 0, 30, 0));
+}
+
+#[test]
+fn programs_set_the_time() {
+    let mut rtc = Rtc::new(at(2024, 1, 1, 12, 0, 0));
+
+    rtc.write(rtc::HOURS, 0x08, 10);
+    rtc.write(rtc::YEAR, 0x99, 10);
+
+    assert_eq!(rtc.now(10), at(2099, 1, 1, 8, 0, 10));
+    assert_eq!(rtc.now(20), at(2099, 1, 1, 8, 0, 20));
+    // Time survives the emulated clock going back to zero
+    rtc.reset(20);
+    assert_eq!(rtc.now(0), at(2099, 1, 1, 8, 0, 20));
+}
+
+#[test]
+fn alarm_fires_when_skipped_past() {
+    let mut rtc = Rtc::new(at(2024, 1, 1, 12, 0, 0));
+    rtc.write(rtc::ALARM_HOURS, 0x07, 0);
+    rtc.write(rtc::ALARM_MINUTES, 0x30, 0);
+
+    assert_eq!(rtc.read(rtc::FLAGS, 60), 0);
+    rtc.advance(Duration::hours(12));
+    assert_eq!(rtc.read(rtc::FLAGS, 60), 0);
+    rtc.advance(Duration::hours(8));
+    assert_eq!(rtc.read(rtc::FLAGS, 60), rtc::FLAG_ALARM);
+
+    rtc.write(rtc::FLAGS, rtc::FLAG_ALARM, 60);
+    assert_eq!(rtc.read(rtc::FLAGS, 60), 0);
+    rtc.advance(Duration::days(1));
+    assert_eq!(rtc.read(rtc::FLAGS, 60), rtc::FLAG_ALARM);
+}
+
+#[test]
+fn keeps_running_while_saved() {
+    let mut rtc = Rtc::new(at(2024, 1, 1, 12, 0, 0));
+    rtc.write(rtc::ALARM_HOURS, 0x13, 10);
+    let (offset, registers) = rtc.save(10, at(2024, 1, 1, 11, 0, 0));
+
+    // Restored a day later, in a machine that has run for 5 seconds
+    let mut restored = Rtc::new(at(2030, 1, 1, 0, 0, 0));
+    restored.restore(offset, registers, 5, at(2024, 1, 2, 11, 0, 0));
+
+    assert_eq!(read_all(&mut restored, 5), [0x10, 0x00, 0x12, 0x03, 0x02, 0x01, 0x24, 0x20]);
+    assert_eq!(restored.read(rtc::ALARM_HOURS, 5), 0x13);
+}
@@ -0,0 +1,38 @@
+use plu::computer::scheduler::{Event, Scheduler};
+
+#[test]
+fn pops_events_in_cycle_order() {
+    let mut scheduler = Scheduler::default();
+    scheduler.schedule(300, Event::Card { slot: 1, timer: 0 });
+    scheduler.schedule(100, Event::Throttle);
+    scheduler.schedule(200, Event::Card { slot: 2, timer: 0 });
+
+    assert_eq!(scheduler.next(), Some(100));
+    assert_eq!(scheduler.pop_due(99), None);
+    assert_eq!(scheduler.pop_due(250), Some((100, Event::Throttle)));
+    assert_eq!(scheduler.pop_due(250), Some((200, Event::Card { slot: 2, timer: 0 })));
+    assert_eq!(scheduler.pop_due(250), None);
+    assert_eq!(scheduler.next(), Some(300));
+}
+
+#[test]
+fn keeps_events_due_on_the_same_cycle_in_order() {
+    let mut scheduler = Scheduler::default();
+    scheduler.schedule(10, Event::Card { slot: 3, timer: 1 });
+    scheduler.schedule(10, Event::Card { slot: 1, timer: 0 });
+
+    assert_eq!(scheduler.pop_due(10), Some((10, Event::Card { slot: 3, timer: 1 })));
+    assert_eq!(scheduler.pop_due(10), Some((10, Event::Card { slot: 1, timer: 0 })));
+}
+
+#[test]
+fn cancels_timers() {
+    let mut scheduler = Scheduler::default();
+    scheduler.schedule(10, Event::Card { slot: 1, timer: 0 });
+    scheduler.schedule(20, Event::Throttle);
+    scheduler.cancel(Event::Card { slot: 1, timer: 0 });
+
+    assert_eq!(scheduler.next(), Some(20));
+    scheduler.clear();
+    assert_eq!(scheduler.next(), None);
+}
@@ -0,0 +1,69 @@
+use rhai::Scope;
+
+use plu::script::{self, Script, Vars};
+
+fn eval(source: &str) -> Result<i64, String> {
+    script::eval(source, &Vars::new(), &[])
+}
+
+#[test]
+fn evaluates_rhai_expressions() {
+    assert_eq!(eval("1 + 2 * 3"), Ok(7));
+    assert_eq!(eval("(1 + 2) * 3"), Ok(9));
+    assert_eq!(eval("0xF0 | 0x0F & 0x3C"), Ok(0xFC));
+    assert_eq!(eval("1 << 4 == 16"), Ok(1));
+    assert_eq!(eval("true && 2 > 1 || false"), Ok(1));
+    assert_eq!(eval("1_000"), Ok(1000));
+    assert_eq!(eval("let x = 2; if x > 1 { x * 10 } else { 0 }"), Ok(20));
+    assert_eq!(eval(""), Ok(0));
+}
+
+#[test]
+fn keeps_variables_between_runs() {
+    let script = Script::parse("
+        // Count the runs, from 0 since unset variables read as 0
+        let count = count + 1;
+        if count == 3 { done = 1 } else if count > 3 {
+            count = 0
+        }
+        count").unwrap();
+    let mut scope = Scope::new();
+    scope.push("done", 0_i64);
+    let runs: Vec<i64> = (0..4).map(|_| script.run(script::engine(), &mut scope).unwrap()).collect();
+    assert_eq!(runs, [1, 2, 3, 0]);
+    assert_eq!(scope.get_value::<i64>("done"), Some(1));
+    // Declaring the variable again on every run does not grow the scope
+    assert_eq!(scope.len(), 2);
+}
+
+#[test]
+fn reports_errors() {
+    assert!(Script::parse("1 +").is_err());
+    assert!(Script::parse("if x { 1").is_err());
+    assert!(Script::parse("x = 1 y = 2").is_err());
+    assert!(eval("1 / (2 - 2)").unwrap_err().starts_with("Division by zero"));
+    assert!(eval("\"a\"").unwrap_err().contains("expected a number"));
+    // Only variables declared with let can be assigned
+    assert!(eval("x = 1").is_err());
+    // The right side of && is not run when the left side is false
+    assert_eq!(eval("false && 1 / 0 == 1"), Ok(0));
+    // Scripts stuck in a loop give up instead of hanging
+    assert!(eval("loop {}").is_err());
+}
+
+#[test]
+fn reads_memory_and_variables() {
+    let mut memory = vec![0; 0x10000];
+    memory[0x1234] = 0x34;
+    memory[0x1235] = 0x12;
+    let vars = Vars::from([(String::from("buffer"), 0x1234)]);
+
+    assert_eq!(script::eval("peek(buffer) + peek(\n1\n)", &vars, &memory), Ok(0x34));
+    assert_eq!(script::eval("word(0x1234)", &vars, &memory), Ok(0x1234));
+    assert!(script::eval("peek(\"a\")", &vars, &memory).is_err());
+    // Symbols are constants
+    assert!(script::eval("buffer = 1", &vars, &memory).is_err());
+    // A mistyped symbol is not 0
+    assert!(script::eval("peek(bufer)", &vars, &memory).unwrap_err().contains("bufer"));
+    assert_eq!(script::eval("let x = 2; x + buffer", &vars, &memory), Ok(0x1236));
+}
@@ -0,0 +1,38 @@
+use memchr::memmem;
+use plu::search::{self, Search};
+
+#[test]
+fn parses_hex_and_text_patterns() {
+    assert_eq!(search::parse_pattern("A9 00"), Some(vec![0xA9, 0x00]));
+    assert_eq!(search::parse_pattern("a900ff"), Some(vec![0xA9, 0x00, 0xFF]));
+    assert_eq!(search::parse_pattern("\"HELLO\""), Some(b"HELLO".to_vec()));
+    assert_eq!(search::parse_pattern("\"HI"), Some(b"HI".to_vec()));
+    assert_eq!(search::parse_pattern("A9 0"), None);
+    assert_eq!(search::parse_pattern("ZZ"), None);
+    assert_eq!(search::parse_pattern(""), None);
+    assert_eq!(search::parse_pattern("\"\""), None);
+}
+
+#[test]
+fn finds_all_matches() {
+    let finder = memmem::Finder::new(b"AB");
+    assert_eq!(search::find_all(&finder, b"ABxxABABx"), vec![0, 4, 6]);
+    assert!(search::find_all(&finder, b"").is_empty());
+}
+
+#[test]
+fn tells_which_bytes_are_highlighted() {
+    let search = Search {
+        pattern: b"ABC".to_vec(),
+        matches: vec![0x10, 0x20],
+        ..Default::default()
+    };
+
+    assert!(!search.covers(0x0F));
+    assert!(search.covers(0x10));
+    assert!(search.covers(0x12));
+    assert!(!search.covers(0x13));
+    assert!(search.covers(0x22));
+    assert_eq!(search.next_after(0x10), Some(0x20));
+    assert_eq!(search.next_after(0x20), Some(0x10));
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use std::fs;
+use std::time::Duration;
+
+use plu::app::{App, Tab};
+use plu::args;
+use plu::computer::{CardType, IRQ_SLOT, TRAP_SLOT};
+use plu::session::{Patch, Session};
+
+#[test]
+fn parses_session_files() {
+    let session = Session::parse(r#"{
+        "symbols": "rom.sym", "trap": true, "irq_order": [6, 4],
+        "breakpoints": [65280], "watches": [512], "patches": [{"address": 65282, "bytes": [234, 234]}]
+    }"#).unwrap();
+    assert_eq!(session, Session {
+        symbols: Some("rom.sym".to_string()),
+        trap: true,
+        irq_order: Some(vec![6, 4]),
+        breakpoints: vec![0xFF00],
+        watches: vec![0x200],
+        patches: vec![Patch { address: 0xFF02, bytes: vec![0xEA, 0xEA] }],
+        ..Session::default()
+    });
+    assert_eq!(Session::parse(&session.to_json()), Ok(session));
+
+    assert_eq!(Session::parse("{}"), Ok(Session::default()));
+    assert!(Session::parse(r#"{"irq_order": [7]}"#).is_err());
+    assert!(Session::parse(r#"{"irq_order": [4, 4]}"#).is_err());
+    assert!(Session::parse(r#"{"bookmarks": []}"#).is_err());
+    assert!(Session::parse(r#"{"patches": [{"address": 512}]}"#).is_err());
+    assert!(Session::parse("trap = true").is_err());
+}
+
+#[test]
+fn adds_to_command_line_options() {
+    let session = Session {
+        symbols: Some("rom.sym".to_string()),
+        rtc: true,
+        irq_order: Some(vec![6]),
+        ..Session::default()
+    };
+    let mut parsed = args::parse(["--irq-order", "5", "--trap", "rom.bin"].map(String::from)).unwrap();
+
+    session.apply(&mut parsed);
+
+    assert!(parsed.rtc);
+    assert!(parsed.trap);
+    assert_eq!(parsed.irq_order, Some(vec![5]));
+    assert_eq!(parsed.symbols_file.as_deref(), Some("rom.sym"));
+}
+
+#[test]
+fn saves_cards_and_symbols_in_use() {
+    let dir = common::temp_dir("session");
+    let symbols = dir.join("rom.sym").to_string_lossy().into_owned();
+    fs::write(&symbols, "al 00FF00 .reset\n").unwrap();
+
+    let mut app = App::with_data(common::rom(&common::PRINT, b"\0"), vec![]).unwrap();
+    app.load_symbols(&symbols).unwrap();
+    app.insert_card(TRAP_SLOT, CardType::Trap);
+    app.insert_card(IRQ_SLOT, CardType::Irq);
+    app.set_irq_order(vec![6, 4]);
+    app.processor.pc = 0xFF05;
+    app.toggle_breakpoint();
+    app.memory_scroll = 0x20;
+    app.toggle_watch();
+    app.toggle_watchpoint();
+    app.session_file = dir.join("session.json").to_string_lossy().into_owned();
+    app.export_session();
+
+    assert_eq!(app.symbols.get(0xFF00), Some("reset"));
+    let saved = Session::open(&app.session_file).unwrap();
+    assert_eq!(saved, Session {
+        symbols: Some(symbols),
+        trap: true,
+        irq_order: Some(vec![6, 4]),
+        breakpoints: vec![0xFF05],
+        watchpoints: vec![0x200],
+        watches: vec![0x200],
+        ..Session::default()
+    });
+    assert_eq!(app.debug.back().cloned(), Some(format!("Session saved to {}", app.session_file)));
+}
+
+#[test]
+fn loads_sessions_into_the_running_app() {
+    let dir = common::temp_dir("import");
+    let symbols = dir.join("rom.sym").to_string_lossy().into_owned();
+    fs::write(&symbols, "al 000200 .count\n").unwrap();
+    let session = Session {
+        symbols: Some(symbols),
+        breakpoints: vec![0xFF05],
+        watches: vec![0x200],
+        patches: vec![Patch { address: 0x200, bytes: vec![0x42] }],
+        ..Session::default()
+    };
+    let mut app = App::with_data(common::rom(&common::PRINT, b"\0"), vec![]).unwrap();
+    app.session_file = dir.join("session.json").to_string_lossy().into_owned();
+    session.save(&app.session_file).unwrap();
+
+    app.import_session();
+
+    assert_eq!(app.debug.back().cloned(), Some(format!("Session loaded from {}", app.session_file)));
+    assert_eq!(app.symbols.get(0x200), Some("count"));
+    assert_eq!(app.session, session);
+    // Watches are read wherever the Memory tab is scrolled
+    app.current_tab = Tab::Memory;
+    app.memory_scroll = 0xFF0;
+    common::tick_until(&mut app, Duration::from_secs(5), |app| app.mem[0x200] == 0x42);
+
+    app.session_file = dir.join("missing.json").to_string_lossy().into_owned();
+    app.import_session();
+    assert!(app.debug.back().is_some_and(|m| m.starts_with("Session not loaded")));
+}
@@ -0,0 +1,57 @@
+use plu::computer::snapshot::{self, Snapshot};
+use plu::computer::{Card, CardType, Processor};
+use plu::error::Error;
+
+fn snapshot() -> Snapshot {
+    let mut memory = vec![0; 0x10000];
+    memory[0x200] = 0x42;
+    Snapshot {
+        rom_hash: snapshot::rom_hash(b"ROM"),
+        disk_hash: snapshot::disk_hash(&[0; 512]),
+        processor: Processor {
+            flags: 0b00110011,
+            acc: 0x48,
+            rx: 1,
+            ry: 2,
+            pc: 0xFF02,
+            sp: 0xFD,
+            clock: 123_456_789,
+            inst: 0xBD,
+        },
+        memory,
+        disk: vec![0xAA; 512],
+        lba: 7,
+        disk_cnt: 12,
+        command: 0x20,
+        cards: vec![
+            Card { slot: 3, card_type: CardType::Rtc, enabled: false },
+            Card { slot: 5, card_type: CardType::CF, enabled: true },
+        ],
+        timers: vec![(123_460_000, 4, 1)],
+        rtc_offset: -3600,
+        rtc_registers: [0x30, 0x15, 0x08, 0x01],
+        devices: vec![(0, String::from(r#"[["irq",1],["name","video"]]"#))],
+        output: b"HELLO\rWORLD".to_vec(),
+    }
+}
+
+#[test]
+fn round_trips_through_bytes() {
+    let snapshot = snapshot();
+
+    assert_eq!(Snapshot::from_bytes(&snapshot.to_bytes()).unwrap(), snapshot);
+}
+
+#[test]
+fn rejects_other_files() {
+    let bytes = snapshot().to_bytes();
+
+    assert!(matches!(Snapshot::from_bytes(b"not a snapshot"), Err(Error::Snapshot(_))));
+    assert!(matches!(Snapshot::from_bytes(&bytes[..bytes.len() - 1]), Err(Error::Snapshot(_))));
+}
+
+#[test]
+fn hashes_roms_by_content() {
+    assert_eq!(snapshot::rom_hash(b"ROM"), snapshot::rom_hash(b"ROM"));
+    assert_ne!(snapshot::rom_hash(b"ROM"), snapshot::rom_hash(b"ROM2"));
+}
@@ -0,0 +1,25 @@
+use plu::symbols::Symbols;
+
+#[test]
+fn parses_vice_labels() {
+    let symbols = Symbols::parse("al 00FF00 .reset\nal C:ff0f .message\nal 00FF00 .start\n");
+
+    assert_eq!(symbols.len(), 2);
+    assert_eq!(symbols.get(0xFF00), Some("reset"));
+    assert_eq!(symbols.get(0xFF0F), Some("message"));
+    assert_eq!(symbols.get(0xFF01), None);
+}
+
+#[test]
+fn parses_assignments() {
+    let symbols = Symbols::parse("reset           = $ff00\nSERIAL = $FFE0 ; serial card\ncount = 12\n");
+
+    assert_eq!(symbols.len(), 2);
+    assert_eq!(symbols.get(0xFF00), Some("reset"));
+    assert_eq!(symbols.get(0xFFE0), Some("SERIAL"));
+}
+
+#[test]
+fn ignores_other_lines() {
+    assert!(Symbols::parse("; comment\n\nal nothex .x\nlong name = $10\nx = $10000\n").is_empty());
+}
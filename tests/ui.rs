@@ -0,0 +1,227 @@
+mod common;
+
+use std::collections::VecDeque;
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+
+use plu::app::{App, Tab};
+use plu::computer::device::{Device, Handlers};
+use plu::computer::map::MemoryMap;
+use plu::computer::{Card, CardType, Metrics, Processor};
+use plu::console::Console;
+use plu::rom::Rom;
+use plu::search::Search;
+use plu::symbols::Symbols;
+use plu::usage::UsageReport;
+use plu::ui;
+use ratatui::backend::{Backend, TestBackend};
+use ratatui::Terminal;
+
+const WIDTH: u16 = 80;
+const HEIGHT: u16 = 32;
+
+/// Application with a fixed state, so renders do not depend on the running computer.
+fn app(tab: Tab) -> App {
+    let rom = common::rom(&common::PRINT, b"HELLO\rWORLD\0");
+    let mut app = App::with_data(rom.clone(), vec![]).unwrap();
+    app.current_tab = tab;
+    app.mem = vec![0; 0x10000 - rom.len()];
+    app.mem.extend(rom);
+    for (i, b) in b"Planck 6502".iter().enumerate() {
+        app.mem[0x200 + i] = *b;
+    }
+    app.output.write(b"HELLO\rWORLD");
+    app.debug = VecDeque::from([String::from("0xff02 - Running instruction lda")]);
+    app.processor = Processor {
+        flags: 0b00110010,
+        acc: 0x48,
+        rx: 0x01,
+        ry: 0x00,
+        pc: 0xFF02,
+        sp: 0xFD,
+        clock: 1234,
+        inst: 0xBD,
+    };
+    app.speed = 1000.0;
+    app.metrics = Arc::new(Metrics {
+        cycles: AtomicU64::new(1234),
+        instructions: AtomicU64::new(456),
+        irqs: AtomicU64::new(2),
+        ..Default::default()
+    });
+    app
+}
+
+fn render(app: &mut App) -> Terminal<TestBackend> {
+    let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+    terminal.draw(|frame| ui::render(app, frame)).unwrap();
+    terminal
+}
+
+#[test]
+fn main_tab() {
+    let mut terminal = render(&mut app(Tab::Main));
+
+    insta::assert_snapshot!(terminal.backend());
+    // Cursor sits right after the last character of the last output line
+    assert_eq!(terminal.backend_mut().get_cursor().unwrap(), (6, 11));
+}
+
+#[test]
+fn main_tab_scrolled_output() {
+    let mut app = app(Tab::Main);
+    app.output = Console::default();
+    app.output.write((0..40).map(|i| format!("line {}", i)).collect::<Vec<_>>().join("\r").as_bytes());
+    app.output_scroll = 100;
+    let terminal = render(&mut app);
+
+    insta::assert_snapshot!(terminal.backend());
+    assert_eq!(app.output_scroll, 20);
+    assert_eq!(app.output_size, (78, 20));
+}
+
+#[test]
+fn main_tab_long_line() {
+    let mut app = app(Tab::Main);
+    app.output = Console::default();
+    app.output.write(&[b'x'; 1000]);
+    let mut terminal = render(&mut app);
+
+    insta::assert_snapshot!(terminal.backend());
+    // 1000 bytes wrapped on 78 columns
+    assert_eq!(terminal.backend_mut().get_cursor().unwrap(), (1 + 1000 % 78, 22));
+}
+
+#[test]
+fn memory_tab() {
+    let mut app = app(Tab::Memory);
+    app.memory_scroll = 0x20;
+    let terminal = render(&mut app);
+
+    insta::assert_snapshot!(terminal.backend());
+}
+
+#[test]
+fn memory_tab_end_of_memory() {
+    let mut app = app(Tab::Memory);
+    app.memory_scroll = 0xFFF0;
+    let terminal = render(&mut app);
+
+    insta::assert_snapshot!(terminal.backend());
+    assert_eq!(app.memory_scroll, 0xFFF);
+}
+
+#[test]
+fn memory_tab_io_window() {
+    let mut app = app(Tab::Memory);
+    let cards = [
+        Card { slot: 0, card_type: CardType::Device, enabled: true },
+        Card { slot: 4, card_type: CardType::IO, enabled: true },
+        Card { slot: 6, card_type: CardType::Serial, enabled: false },
+    ];
+    let devices = [Device::new(0, "video", Handlers::default(), 0).unwrap()];
+    app.memory_map = MemoryMap::new(&cards, &devices, &Rom { base: 0xFF00, data: vec![0xEA; 0x100] });
+    app.memory_scroll = 0xFF8;
+    let terminal = render(&mut app);
+
+    insta::assert_snapshot!(terminal.backend());
+}
+
+#[test]
+fn memory_tab_eval_result() {
+    let mut app = app(Tab::Memory);
+    app.eval = Some(String::from("peek(0x200) = 80 $50 %1010000 'P'"));
+    let terminal = render(&mut app);
+
+    insta::assert_snapshot!(terminal.backend());
+}
+
+#[test]
+fn disassembly_tab() {
+    let terminal = render(&mut app(Tab::Disassembly));
+
+    insta::assert_snapshot!(terminal.backend());
+}
+
+#[test]
+fn help_tab() {
+    let terminal = render(&mut app(Tab::Help));
+
+    insta::assert_snapshot!(terminal.backend());
+}
+
+#[test]
+fn error_modal() {
+    let mut app = app(Tab::Main);
+    app.error = Some(String::from("CF card read beyond the end of the disk at LBA 1"));
+    let terminal = render(&mut app);
+
+    insta::assert_snapshot!(terminal.backend());
+}
+
+#[test]
+fn disassembly_tab_with_symbols() {
+    let mut app = app(Tab::Disassembly);
+    app.symbols = Symbols::parse("al 00FF02 .print\nal 00FF0D .done\n");
+    let terminal = render(&mut app);
+
+    insta::assert_snapshot!(terminal.backend());
+}
+
+#[test]
+fn build_error_modal() {
+    let mut app = app(Tab::Main);
+    app.build_error = Some(String::from("ca65 main.s failed:\nmain.s(3): Error: Unexpected trailing garbage characters"));
+    let terminal = render(&mut app);
+
+    insta::assert_snapshot!(terminal.backend());
+}
+
+#[test]
+fn usage_modal() {
+    let mut app = app(Tab::Memory);
+    app.usage = Some(UsageReport {
+        written: vec![0x0000..=0x00FF, 0x01F0..=0x01FF],
+        stack_low: Some(0x01F0),
+        rom_size: 0x100,
+        unexecuted: vec![0xFF0F..=0xFF7F],
+    });
+    let terminal = render(&mut app);
+
+    insta::assert_snapshot!(terminal.backend());
+}
+
+#[test]
+fn cards_modal() {
+    let mut app = app(Tab::Memory);
+    app.cards = Some(vec![
+        Card { slot: 3, card_type: CardType::Rtc, enabled: true },
+        Card { slot: 5, card_type: CardType::CF, enabled: false },
+        Card { slot: 6, card_type: CardType::Serial, enabled: true },
+    ]);
+    app.card_selected = 1;
+    let terminal = render(&mut app);
+
+    insta::assert_snapshot!(terminal.backend());
+}
+
+#[test]
+fn disk_matches_modal() {
+    let mut app = app(Tab::Memory);
+    let mut search = Search::new(b"HELLO".to_vec());
+    search.disk_matches = vec![0x10, 0x258, 0x1_0000];
+    app.search = Some(search);
+    app.disk_matches_scroll = Some(1);
+    let terminal = render(&mut app);
+
+    insta::assert_snapshot!(terminal.backend());
+}
+
+#[test]
+fn breakpoint_modal() {
+    let mut app = app(Tab::Main);
+    app.breakpoint = Some((0xFF07, String::from("before disk init")));
+    let terminal = render(&mut app);
+
+    insta::assert_snapshot!(terminal.backend());
+}
@@ -0,0 +1,43 @@
+use plu::usage::UsageReport;
+
+#[test]
+fn merges_addresses_into_regions() {
+    let mut written = vec![false; 0x10000];
+    let mut executed = vec![false; 0x10000];
+    written[0x10..0x20].fill(true);
+    written[0x1F0..0x200].fill(true);
+    written[0x300] = true;
+    // Card registers and ROM
+    written[0xFFE0] = true;
+    written[0xFF20] = true;
+    executed[0xFF00..0xFF10].fill(true);
+
+    let report = UsageReport::new(&written, &executed, 0xFF00..0x10000);
+
+    assert_eq!(report.written, vec![0x10..=0x1F, 0x1F0..=0x1FF, 0x300..=0x300]);
+    assert_eq!(report.written_bytes(), 33);
+    assert_eq!(report.stack_low, Some(0x1F0));
+    assert_eq!(report.stack_depth(), 16);
+    assert_eq!(report.rom_size, 0x100);
+    // The I/O area and the vectors are not counted
+    assert_eq!(report.unexecuted, vec![0xFF10..=0xFF7F, 0xFFF0..=0xFFF9]);
+}
+
+#[test]
+fn formats_report() {
+    let report = UsageReport {
+        written: vec![0x200..=0x2FF],
+        stack_low: None,
+        rom_size: 0x100,
+        unexecuted: vec![0xFF40..=0xFF4F],
+    };
+
+    assert_eq!(report.to_string(), "\
+Memory written: 256 bytes
+  $0200-$02FF    256 bytes
+Stack depth: unused
+ROM never run: 16 of 256 bytes
+  $FF40-$FF4F     16 bytes
+");
+    assert_eq!(report.summary(), "Written: 256 bytes in 1 regions\nStack depth: 0 bytes\nROM never run: 16 of 256 bytes in 1 regions");
+}